@@ -34,6 +34,12 @@
         postBuild = ''
           wrapProgram $out/bin/xeditor \
             --prefix LD_LIBRARY_PATH : "${pkgs.lib.makeLibraryPath dlopenLibraries}"
+
+          # The wrapped editor runs from ${xeditor}/bin and looks for the helper there.
+          mkdir -p $out/share/polkit-1/actions
+          substitute ${./assets/org.xeditor.write.policy.in} \
+            $out/share/polkit-1/actions/org.xeditor.write.policy \
+            --replace-fail @bindir@ ${xeditor}/bin
         '';
       };
 
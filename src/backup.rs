@@ -0,0 +1,214 @@
+use crate::editor::BufferId;
+use crate::xdg;
+use serde_json::Value;
+use serde_json::json;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+const DIRECTORY: &str = "backups";
+
+// The text of an unsaved buffer, taken by the running editor.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub buffer: BufferId,
+    pub path: Option<PathBuf>,
+    pub text: String,
+}
+
+// A snapshot left behind by an editor that is no longer running.
+#[derive(Debug, Clone)]
+pub struct Backup {
+    // The backup file itself.
+    pub file: PathBuf,
+    pub path: Option<PathBuf>,
+    pub text: String,
+    pub saved: SystemTime,
+    // The file as it is on disk now, none when it is untitled or gone.
+    pub disk: Option<String>,
+    // Whether the file was written or deleted after the snapshot was taken.
+    pub changed_on_disk: bool,
+}
+
+impl Backup {
+    pub fn name(&self) -> String {
+        match &self.path {
+            Some(path) => path.display().to_string(),
+            None => {
+                let first_line = self
+                    .text
+                    .lines()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .unwrap_or_default();
+                let preview: String = first_line.chars().take(40).collect();
+
+                format!("Untitled: {preview}")
+            }
+        }
+    }
+}
+
+fn directory() -> Option<PathBuf> {
+    xdg::state_directory().map(|directory| directory.join(DIRECTORY))
+}
+
+// Backups are named after the process and the buffer, so running editors never write
+// over each other.
+fn file_name(pid: u32, buffer: BufferId) -> String {
+    format!("{pid}-{buffer}.json")
+}
+
+// Writes `snapshots` and removes the backups of this process for buffers not in `keep`,
+// those were saved or closed since. Failures are ignored, there is nobody to tell while
+// the editor crashes.
+pub async fn save(snapshots: Vec<Snapshot>, keep: Vec<BufferId>) {
+    let Some(directory) = directory() else {
+        return;
+    };
+    let _ = tokio::fs::create_dir_all(&directory).await;
+    let pid = std::process::id();
+
+    for snapshot in snapshots {
+        let modified = match &snapshot.path {
+            Some(path) => modified(path).await,
+            None => None,
+        };
+
+        let json = json!({
+            "pid": pid,
+            "path": snapshot.path.as_deref().map(Path::to_string_lossy),
+            "modified": modified.map(to_millis),
+            "saved": to_millis(SystemTime::now()),
+            "text": snapshot.text,
+        });
+
+        // Written aside first, a crash halfway through keeps the previous snapshot.
+        let path = directory.join(file_name(pid, snapshot.buffer));
+        let temporary = path.with_extension("tmp");
+        if tokio::fs::write(&temporary, json.to_string()).await.is_ok() {
+            let _ = tokio::fs::rename(&temporary, &path).await;
+        }
+    }
+
+    let keep: Vec<String> = keep
+        .into_iter()
+        .map(|buffer| file_name(pid, buffer))
+        .collect();
+    let prefix = format!("{pid}-");
+
+    let Ok(mut entries) = tokio::fs::read_dir(&directory).await else {
+        return;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if name.starts_with(&prefix) && name.ends_with(".json") && !keep.contains(&name) {
+            let _ = tokio::fs::remove_file(entry.path()).await;
+        }
+    }
+}
+
+// The backups of editors that exited without saving, newest first. Backups matching the
+// file on disk are removed, there is nothing left to recover from them.
+pub async fn list() -> Vec<Backup> {
+    let Some(directory) = directory() else {
+        return Vec::new();
+    };
+    let Ok(mut entries) = tokio::fs::read_dir(&directory).await else {
+        return Vec::new();
+    };
+
+    let mut backups = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let file = entry.path();
+        if file.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+
+        let Some(json) = tokio::fs::read_to_string(&file)
+            .await
+            .ok()
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+        else {
+            continue;
+        };
+
+        let (Some(pid), Some(text)) = (json["pid"].as_u64(), json["text"].as_str()) else {
+            continue;
+        };
+        if is_running(pid).await {
+            continue;
+        }
+
+        let path = json["path"].as_str().map(PathBuf::from);
+        let (disk, modified) = match &path {
+            Some(path) => (
+                tokio::fs::read_to_string(path).await.ok(),
+                modified(path).await,
+            ),
+            None => (None, None),
+        };
+
+        if disk.as_deref() == Some(text) {
+            let _ = tokio::fs::remove_file(&file).await;
+            continue;
+        }
+
+        backups.push(Backup {
+            file,
+            text: text.to_owned(),
+            saved: from_millis(json["saved"].as_u64().unwrap_or(0)),
+            disk,
+            changed_on_disk: path.is_some() && modified.map(to_millis) != json["modified"].as_u64(),
+            path,
+        });
+    }
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.saved));
+    backups
+}
+
+pub async fn remove(file: PathBuf) {
+    let _ = tokio::fs::remove_file(file).await;
+}
+
+// Whether an editor with `pid` still runs, this one included. A recycled pid belongs to
+// another program, so the executables are compared too. Without `/proc` every other
+// editor counts as gone.
+async fn is_running(pid: u64) -> bool {
+    if pid == u64::from(std::process::id()) {
+        return true;
+    }
+
+    let Ok(executable) = tokio::fs::read_link(format!("/proc/{pid}/exe")).await else {
+        return false;
+    };
+    std::env::current_exe().is_ok_and(|current| current == executable)
+}
+
+async fn modified(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
+
+fn from_millis(millis: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_millis(millis)
+}
+
+// How long ago `time` was, roughly.
+pub fn ago(time: SystemTime) -> String {
+    let seconds = time.elapsed().map_or(0, |elapsed| elapsed.as_secs());
+
+    match seconds {
+        0..60 => String::from("just now"),
+        60..3600 => format!("{} min ago", seconds / 60),
+        3600..86400 => format!("{} h ago", seconds / 3600),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
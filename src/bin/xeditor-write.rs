@@ -0,0 +1,116 @@
+// Replaces a file the editor has no permission to write. The editor runs it through
+// `pkexec`, so it is the only part running as root, and it does nothing but this:
+//
+//     xeditor-write PATH < text
+//
+// The text on stdin replaces PATH atomically: it is written to a new file next to it,
+// which takes over the owner and the permissions of the old one, except for the setuid,
+// setgid and sticky bits, and is then renamed over it. Symbolic links are followed, the
+// file they point to is replaced. Exits with 0 once the file is replaced, with 1 and the
+// reason as the last line on stderr when it could not be, and with 2 when called wrongly.
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+// Like the umask usually leaves them, for files that do not exist yet.
+const NEW_FILE_MODE: u32 = 0o644;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [path] = args.as_slice() else {
+        eprintln!("Usage: xeditor-write PATH < text");
+        return ExitCode::from(2);
+    };
+
+    // pkexec starts programs in another directory.
+    let path = PathBuf::from(path);
+    if !path.is_absolute() {
+        eprintln!("{} is not an absolute path", path.display());
+        return ExitCode::from(2);
+    }
+
+    let mut text = Vec::new();
+    if let Err(error) = io::stdin().read_to_end(&mut text) {
+        eprintln!("could not read standard input: {error}");
+        return ExitCode::FAILURE;
+    }
+
+    match replace(&path, &text) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}: {error}", path.display());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn replace(path: &Path, text: &[u8]) -> io::Result<()> {
+    let target = match fs::canonicalize(path) {
+        Ok(target) => target,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => path.to_owned(),
+        Err(error) => return Err(error),
+    };
+    let existing = match fs::metadata(&target) {
+        Ok(metadata) if !metadata.is_file() => {
+            return Err(io::Error::other("not a regular file"));
+        }
+        Ok(metadata) => Some(metadata),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => return Err(error),
+    };
+
+    let directory = target
+        .parent()
+        .ok_or_else(|| io::Error::other("no parent directory"))?;
+    let name = target
+        .file_name()
+        .ok_or_else(|| io::Error::other("no file name"))?;
+    let temporary = directory.join(format!(
+        ".{}.xeditor-write-{}",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = write(&temporary, text, existing.as_ref())
+        .and_then(|()| fs::rename(&temporary, &target))
+        // The rename only lasts a crash once the directory is on disk too.
+        .and_then(|()| fs::File::open(directory)?.sync_all());
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    result
+}
+
+// Writes the new file with the permissions and the owner of `existing`. The setuid, setgid
+// and sticky bits are dropped, root must not hand out new programs running as someone else.
+fn write(temporary: &Path, text: &[u8], existing: Option<&fs::Metadata>) -> io::Result<()> {
+    let mode = existing.map_or(NEW_FILE_MODE, |metadata| metadata.mode() & 0o777);
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .open(temporary)?;
+
+    if let Some(existing) = existing {
+        let created = file.metadata()?;
+        // Only root can give files away, anyone else gets a file of their own, which
+        // would quietly take the old one over.
+        if (created.uid(), created.gid()) != (existing.uid(), existing.gid()) {
+            std::os::unix::fs::fchown(&file, Some(existing.uid()), Some(existing.gid()))?;
+        }
+        // The umask applied to `mode`.
+        file.set_permissions(fs::Permissions::from_mode(mode))?;
+    }
+
+    file.write_all(text)?;
+    file.sync_all()
+}
@@ -0,0 +1,183 @@
+use crate::Message;
+use crate::language::Language;
+use crate::theme::Tokens;
+use iced::Border;
+use iced::Element;
+use iced::Length;
+use iced::Rectangle;
+use iced::widget::Space;
+use iced::widget::container;
+use iced::widget::pin;
+use iced::widget::stack;
+use iced::widget::text_editor::Content;
+use iced::widget::text_editor::Position;
+
+// Matching stops looking after this many lines, long files stay responsive.
+const MAX_SCAN_LINES: usize = 2000;
+
+// Pairs with distinct sides, the ones that nest.
+fn brackets(language: &Language) -> impl Iterator<Item = (char, char)> {
+    language
+        .pairs
+        .iter()
+        .copied()
+        .filter(|(open, close)| open != close)
+}
+
+fn char_at(content: &Content, position: Position) -> Option<char> {
+    content
+        .line(position.line)?
+        .text
+        .chars()
+        .nth(position.column)
+}
+
+// The bracket next to the cursor and the one matching it. The bracket right of the
+// cursor wins over the one left of it.
+pub fn matching(
+    content: &Content,
+    cursor: Position,
+    language: &Language,
+) -> Option<(Position, Position)> {
+    let candidates = [
+        Some(cursor),
+        cursor.column.checked_sub(1).map(|column| Position {
+            line: cursor.line,
+            column,
+        }),
+    ];
+
+    candidates.into_iter().flatten().find_map(|position| {
+        let c = char_at(content, position)?;
+
+        brackets(language).find_map(|(open, close)| {
+            if c == open {
+                find_forward(content, position, open, close).map(|other| (position, other))
+            } else if c == close {
+                find_backward(content, position, open, close).map(|other| (position, other))
+            } else {
+                None
+            }
+        })
+    })
+}
+
+fn find_forward(content: &Content, from: Position, open: char, close: char) -> Option<Position> {
+    let mut depth = 0;
+    let last = (from.line + MAX_SCAN_LINES).min(content.line_count());
+
+    for line in from.line..last {
+        let text = content.line(line)?.text;
+        let skip = if line == from.line {
+            from.column + 1
+        } else {
+            0
+        };
+
+        for (column, c) in text.chars().enumerate().skip(skip) {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                if depth == 0 {
+                    return Some(Position { line, column });
+                }
+                depth -= 1;
+            }
+        }
+    }
+
+    None
+}
+
+fn find_backward(content: &Content, from: Position, open: char, close: char) -> Option<Position> {
+    let mut depth = 0;
+    let first = from.line.saturating_sub(MAX_SCAN_LINES);
+
+    for line in (first..=from.line).rev() {
+        let text = content.line(line)?.text;
+        let chars: Vec<char> = text.chars().collect();
+        let end = if line == from.line {
+            from.column
+        } else {
+            chars.len()
+        };
+
+        for column in (0..end.min(chars.len())).rev() {
+            let c = chars[column];
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                if depth == 0 {
+                    return Some(Position { line, column });
+                }
+                depth -= 1;
+            }
+        }
+    }
+
+    None
+}
+
+// What typing `c` should insert as its closing side, if anything. Quotes are only
+// paired at word boundaries so apostrophes and escaped quotes are left alone.
+pub fn auto_close(
+    language: &Language,
+    c: char,
+    before: Option<char>,
+    after: Option<char>,
+) -> Option<char> {
+    let &(_, close) = language.pairs.iter().find(|&&(open, _)| open == c)?;
+
+    let after_allows = after.is_none_or(|after| {
+        after.is_whitespace() || language.pairs.iter().any(|&(_, close)| close == after)
+    });
+
+    let before_allows =
+        c != close || before.is_none_or(|before| !before.is_alphanumeric() && before != '\\');
+
+    (after_allows && before_allows).then_some(close)
+}
+
+// Whether typing `c` in front of `after` should step over it instead of inserting.
+pub fn overtypes(language: &Language, c: char, after: Option<char>) -> bool {
+    after == Some(c) && language.pairs.iter().any(|&(_, close)| close == c)
+}
+
+// The closing side when `c` opens a pair, used to surround selections.
+pub fn closing(language: &Language, c: char) -> Option<char> {
+    language
+        .pairs
+        .iter()
+        .find(|&&(open, _)| open == c)
+        .map(|&(_, close)| close)
+}
+
+// Whether Backspace between `before` and `after` should remove both sides of an empty pair.
+pub fn is_empty_pair(language: &Language, before: Option<char>, after: Option<char>) -> bool {
+    let (Some(before), Some(after)) = (before, after) else {
+        return false;
+    };
+
+    language.pairs.contains(&(before, after))
+}
+
+// Outlines the two brackets, drawn under the editor text.
+pub fn highlight<'a>(bounds: [Rectangle; 2], tokens: Tokens) -> Element<'a, Message> {
+    stack(bounds.into_iter().map(|bounds| {
+        pin(container(Space::new())
+            .width(Length::Fixed(bounds.width))
+            .height(Length::Fixed(bounds.height))
+            .style(move |_theme| container::Style {
+                background: Some(tokens.bracket.into()),
+                border: Border {
+                    width: 1.0,
+                    color: tokens.bracket_border,
+                    radius: 2.0.into(),
+                },
+                ..container::Style::default()
+            }))
+        .position(bounds.position())
+        .into()
+    }))
+    .into()
+}
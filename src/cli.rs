@@ -0,0 +1,195 @@
+use iced::widget::text_editor::Position;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: xeditor [options] [paths...]
+
+Paths are files to open, each in its own pane, and folders to open together. A file may end in
+:line or :line:column to put the cursor there. `-` reads a buffer from standard input.
+
+Options:
+  -n, --new-window     Start fresh instead of restoring the last session
+  -w, --wait           Wait for the files to be closed before exiting, for $EDITOR
+  -r, --readonly       Open the files read-only
+  -d, --diff <a> <b>   Compare two files side by side
+      --no-restore     Neither restore nor save the session
+  -h, --help           Print this help
+  -V, --version        Print the version";
+
+// What the command line asks for.
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    pub files: Vec<Target>,
+    pub directories: Vec<PathBuf>,
+    // Whether `-` was given.
+    pub stdin: bool,
+    pub diff: Option<(PathBuf, PathBuf)>,
+    pub new_window: bool,
+    pub wait: bool,
+    pub readonly: bool,
+    pub no_restore: bool,
+    pub help: bool,
+    pub version: bool,
+}
+
+// A file to open, with the cursor position given after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub path: PathBuf,
+    pub position: Option<Position>,
+}
+
+// One thing to open, the command line broken into steps. A running editor gets them from
+// another `xeditor` over its socket.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Open {
+        target: Target,
+        readonly: bool,
+        wait: bool,
+    },
+    OpenFolder(PathBuf),
+    // Standard input.
+    OpenText {
+        text: String,
+        readonly: bool,
+        wait: bool,
+    },
+    Diff {
+        paths: (PathBuf, PathBuf),
+        readonly: bool,
+        wait: bool,
+    },
+    Focus,
+}
+
+impl Request {
+    pub fn waits(&self) -> bool {
+        match self {
+            Request::Open { wait, .. }
+            | Request::OpenText { wait, .. }
+            | Request::Diff { wait, .. } => *wait,
+            Request::OpenFolder(_) | Request::Focus => false,
+        }
+    }
+}
+
+impl Args {
+    // Whether anything is opened instead of the last session.
+    pub fn opens_files(&self) -> bool {
+        !self.files.is_empty() || self.stdin || self.diff.is_some()
+    }
+
+    // The steps of the command line, with absolute paths so they mean the same to an
+    // editor running elsewhere. Focusing the window comes last.
+    pub fn requests(&self, stdin: Option<String>) -> Vec<Request> {
+        let (readonly, wait) = (self.readonly, self.wait);
+
+        let folders = self
+            .directories
+            .iter()
+            .map(|path| Request::OpenFolder(absolute(path)));
+        let files = self.files.iter().map(|target| Request::Open {
+            target: Target {
+                path: absolute(&target.path),
+                position: target.position,
+            },
+            readonly,
+            wait,
+        });
+        let text = stdin.map(|text| Request::OpenText {
+            text,
+            readonly,
+            wait,
+        });
+        let diff = self.diff.as_ref().map(|(a, b)| Request::Diff {
+            paths: (absolute(a), absolute(b)),
+            readonly,
+            wait,
+        });
+
+        folders
+            .chain(files)
+            .chain(text)
+            .chain(diff)
+            .chain([Request::Focus])
+            .collect()
+    }
+}
+
+pub fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_owned())
+}
+
+// Parses the arguments after the program name. Relative paths are kept relative, they
+// resolve against the working directory like everywhere else.
+pub fn parse(arguments: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut arguments = arguments.into_iter();
+    let mut options = true;
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--" if options => options = false,
+            "-n" | "--new-window" if options => args.new_window = true,
+            "-w" | "--wait" if options => args.wait = true,
+            "-r" | "--readonly" if options => args.readonly = true,
+            "--no-restore" if options => args.no_restore = true,
+            "-h" | "--help" if options => args.help = true,
+            "-V" | "--version" if options => args.version = true,
+            "-d" | "--diff" if options => {
+                let (Some(a), Some(b)) = (arguments.next(), arguments.next()) else {
+                    return Err(format!("{argument} needs two files"));
+                };
+                args.diff = Some((PathBuf::from(a), PathBuf::from(b)));
+            }
+            "-" => args.stdin = true,
+            option if options && option.starts_with('-') => {
+                return Err(format!("unknown option `{option}`"));
+            }
+            path if Path::new(path).is_dir() => args.directories.push(PathBuf::from(path)),
+            path => args.files.push(target(path)),
+        }
+    }
+
+    Ok(args)
+}
+
+// Splits `file:line:column` and `file:line`, unless a file by the full name exists.
+// Lines and columns count from one.
+fn target(argument: &str) -> Target {
+    let whole = Target {
+        path: PathBuf::from(argument),
+        position: None,
+    };
+
+    if Path::new(argument).exists() {
+        return whole;
+    }
+
+    let number = |part: &str| part.parse::<usize>().ok().filter(|&number| number > 0);
+    let mut parts = argument.rsplitn(3, ':');
+    let (Some(last), Some(rest)) = (parts.next(), parts.next()) else {
+        return whole;
+    };
+    let Some(last_number) = number(last) else {
+        return whole;
+    };
+
+    let (path, line, column) = match (parts.next(), number(rest)) {
+        (Some(path), Some(line)) => (path, line, last_number),
+        _ => (&argument[..argument.len() - last.len() - 1], last_number, 1),
+    };
+
+    if path.is_empty() {
+        return whole;
+    }
+
+    Target {
+        path: PathBuf::from(path),
+        position: Some(Position {
+            line: line - 1,
+            column: column - 1,
+        }),
+    }
+}
@@ -0,0 +1,664 @@
+use crate::Message;
+use crate::lines::Case;
+use crate::sidebar::Panel;
+use iced::keyboard;
+use iced::widget::pane_grid;
+use std::fmt;
+
+// Everything the editor can do from a shortcut or the command palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    NewFile,
+    OpenFile,
+    OpenDirectory,
+    OpenRecent,
+    PinRecent,
+    ClearRecent,
+    AddFolderToWorkspace,
+    RemoveFolderFromWorkspace,
+    SaveFile,
+    SaveFileAs,
+    SaveCopy,
+    SaveAll,
+    RevertFile,
+    ToggleReadOnly,
+    OpenSettings,
+    RecoverUnsavedChanges,
+    SaveSessionAs,
+    SwitchSession,
+    IndentLines,
+    OutdentLines,
+    ConvertIndentation,
+    MoveLinesUp,
+    MoveLinesDown,
+    DuplicateLines,
+    DeleteLines,
+    JoinLines,
+    SortLines,
+    SortLinesNatural,
+    SortLinesUnique,
+    ToggleLineComment,
+    ToggleBlockComment,
+    UpperCase,
+    LowerCase,
+    TitleCase,
+    TrimTrailingWhitespace,
+    FormatDocument,
+    ToggleFormatOnSave,
+    JumpToBracket,
+    FoldAtCursor,
+    UnfoldAtCursor,
+    FoldAll,
+    UnfoldAll,
+    ToggleRelativeLineNumbers,
+    ToggleMinimap,
+    ToggleToolbar,
+    CycleSoftWrap,
+    ToggleRulers,
+    ToggleWhitespace,
+    SelectColorTheme,
+    SelectSyntaxTheme,
+    ReloadThemes,
+    SelectEditorFont,
+    ToggleLigatures,
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    ToggleSidebar,
+    ToggleSidebarSide,
+    ToggleZenMode,
+    ShowExplorer,
+    ShowSearch,
+    ShowSourceControl,
+    ShowOutline,
+    SplitRight,
+    SplitDown,
+    ClosePane,
+    ToggleMaximizedPane,
+    FocusLeftPane,
+    FocusRightPane,
+    FocusAbovePane,
+    FocusBelowPane,
+    ShowPalette,
+    ShowWelcome,
+}
+
+pub const ALL: &[Command] = &[
+    Command::NewFile,
+    Command::OpenFile,
+    Command::OpenDirectory,
+    Command::OpenRecent,
+    Command::PinRecent,
+    Command::ClearRecent,
+    Command::AddFolderToWorkspace,
+    Command::RemoveFolderFromWorkspace,
+    Command::SaveFile,
+    Command::SaveFileAs,
+    Command::SaveCopy,
+    Command::SaveAll,
+    Command::RevertFile,
+    Command::ToggleReadOnly,
+    Command::OpenSettings,
+    Command::RecoverUnsavedChanges,
+    Command::SaveSessionAs,
+    Command::SwitchSession,
+    Command::IndentLines,
+    Command::OutdentLines,
+    Command::ConvertIndentation,
+    Command::MoveLinesUp,
+    Command::MoveLinesDown,
+    Command::DuplicateLines,
+    Command::DeleteLines,
+    Command::JoinLines,
+    Command::SortLines,
+    Command::SortLinesNatural,
+    Command::SortLinesUnique,
+    Command::ToggleLineComment,
+    Command::ToggleBlockComment,
+    Command::UpperCase,
+    Command::LowerCase,
+    Command::TitleCase,
+    Command::TrimTrailingWhitespace,
+    Command::FormatDocument,
+    Command::ToggleFormatOnSave,
+    Command::JumpToBracket,
+    Command::FoldAtCursor,
+    Command::UnfoldAtCursor,
+    Command::FoldAll,
+    Command::UnfoldAll,
+    Command::ToggleRelativeLineNumbers,
+    Command::ToggleMinimap,
+    Command::ToggleToolbar,
+    Command::CycleSoftWrap,
+    Command::ToggleRulers,
+    Command::ToggleWhitespace,
+    Command::SelectColorTheme,
+    Command::SelectSyntaxTheme,
+    Command::ReloadThemes,
+    Command::SelectEditorFont,
+    Command::ToggleLigatures,
+    Command::ZoomIn,
+    Command::ZoomOut,
+    Command::ResetZoom,
+    Command::ToggleSidebar,
+    Command::ToggleSidebarSide,
+    Command::ToggleZenMode,
+    Command::ShowExplorer,
+    Command::ShowSearch,
+    Command::ShowSourceControl,
+    Command::ShowOutline,
+    Command::SplitRight,
+    Command::SplitDown,
+    Command::ClosePane,
+    Command::ToggleMaximizedPane,
+    Command::FocusLeftPane,
+    Command::FocusRightPane,
+    Command::FocusAbovePane,
+    Command::FocusBelowPane,
+    Command::ShowPalette,
+    Command::ShowWelcome,
+];
+
+impl Command {
+    pub fn title(self) -> &'static str {
+        match self {
+            Command::NewFile => "File: New File",
+            Command::OpenFile => "File: Open File...",
+            Command::OpenDirectory => "File: Open Folder...",
+            Command::OpenRecent => "File: Open Recent...",
+            Command::PinRecent => "File: Pin or Unpin Recent...",
+            Command::ClearRecent => "File: Clear Recently Opened",
+            Command::AddFolderToWorkspace => "File: Add Folder to Workspace...",
+            Command::RemoveFolderFromWorkspace => "File: Remove Folder from Workspace...",
+            Command::SaveFile => "File: Save",
+            Command::SaveFileAs => "File: Save As...",
+            Command::SaveCopy => "File: Save a Copy...",
+            Command::SaveAll => "File: Save All",
+            Command::RevertFile => "File: Revert File",
+            Command::ToggleReadOnly => "File: Toggle Read-only",
+            Command::OpenSettings => "File: Open Settings",
+            Command::RecoverUnsavedChanges => "File: Recover Unsaved Changes...",
+            Command::SaveSessionAs => "Session: Save Session As...",
+            Command::SwitchSession => "Session: Switch Session...",
+            Command::IndentLines => "Edit: Indent Lines",
+            Command::OutdentLines => "Edit: Outdent Lines",
+            Command::ConvertIndentation => "Edit: Convert Indentation",
+            Command::MoveLinesUp => "Edit: Move Lines Up",
+            Command::MoveLinesDown => "Edit: Move Lines Down",
+            Command::DuplicateLines => "Edit: Duplicate Lines",
+            Command::DeleteLines => "Edit: Delete Lines",
+            Command::JoinLines => "Edit: Join Lines",
+            Command::SortLines => "Edit: Sort Lines",
+            Command::SortLinesNatural => "Edit: Sort Lines (Natural)",
+            Command::SortLinesUnique => "Edit: Sort Lines (Unique)",
+            Command::ToggleLineComment => "Edit: Toggle Line Comment",
+            Command::ToggleBlockComment => "Edit: Toggle Block Comment",
+            Command::UpperCase => "Edit: Transform to Uppercase",
+            Command::LowerCase => "Edit: Transform to Lowercase",
+            Command::TitleCase => "Edit: Transform to Title Case",
+            Command::TrimTrailingWhitespace => "Edit: Trim Trailing Whitespace",
+            Command::FormatDocument => "Edit: Format Document",
+            Command::ToggleFormatOnSave => "File: Toggle Format on Save",
+            Command::JumpToBracket => "Go: Jump to Matching Bracket",
+            Command::FoldAtCursor => "View: Fold",
+            Command::UnfoldAtCursor => "View: Unfold",
+            Command::FoldAll => "View: Fold All",
+            Command::UnfoldAll => "View: Unfold All",
+            Command::ToggleRelativeLineNumbers => "View: Toggle Relative Line Numbers",
+            Command::ToggleMinimap => "View: Toggle Minimap",
+            Command::ToggleToolbar => "View: Toggle Toolbar",
+            Command::CycleSoftWrap => "View: Cycle Soft Wrap",
+            Command::ToggleRulers => "View: Toggle Rulers",
+            Command::ToggleWhitespace => "View: Toggle Whitespace",
+            Command::SelectColorTheme => "View: Select Color Theme",
+            Command::SelectSyntaxTheme => "View: Select Syntax Theme",
+            Command::ReloadThemes => "View: Reload Color Themes",
+            Command::SelectEditorFont => "View: Select Editor Font",
+            Command::ToggleLigatures => "View: Toggle Ligatures",
+            Command::ZoomIn => "View: Zoom In",
+            Command::ZoomOut => "View: Zoom Out",
+            Command::ResetZoom => "View: Reset Zoom",
+            Command::ToggleSidebar => "View: Toggle Sidebar",
+            Command::ToggleSidebarSide => "View: Toggle Sidebar Position",
+            Command::ToggleZenMode => "View: Toggle Zen Mode",
+            Command::ShowExplorer => "View: Show Explorer",
+            Command::ShowSearch => "View: Show Search",
+            Command::ShowSourceControl => "View: Show Source Control",
+            Command::ShowOutline => "View: Show Outline",
+            Command::SplitRight => "View: Split Editor Right",
+            Command::SplitDown => "View: Split Editor Down",
+            Command::ClosePane => "View: Close Editor Pane",
+            Command::ToggleMaximizedPane => "View: Toggle Maximized Pane",
+            Command::FocusLeftPane => "View: Focus Left Pane",
+            Command::FocusRightPane => "View: Focus Right Pane",
+            Command::FocusAbovePane => "View: Focus Pane Above",
+            Command::FocusBelowPane => "View: Focus Pane Below",
+            Command::ShowPalette => "View: Command Palette",
+            Command::ShowWelcome => "Help: Welcome",
+        }
+    }
+
+    pub fn shortcut(self) -> Option<Shortcut> {
+        let shortcut = match self {
+            Command::NewFile => Shortcut::command("n"),
+            Command::OpenFile => Shortcut::command("o"),
+            Command::OpenDirectory => Shortcut::command("o").shift(),
+            Command::OpenRecent => Shortcut::command("r"),
+            Command::SaveFile => Shortcut::command("s"),
+            Command::SaveFileAs => Shortcut::command("s").shift(),
+            Command::IndentLines => Shortcut::command("]"),
+            Command::OutdentLines => Shortcut::command("["),
+            Command::PinRecent
+            | Command::ClearRecent
+            | Command::SaveCopy
+            | Command::SaveAll
+            | Command::RevertFile
+            | Command::ToggleReadOnly
+            | Command::AddFolderToWorkspace
+            | Command::RemoveFolderFromWorkspace
+            | Command::OpenSettings
+            | Command::RecoverUnsavedChanges
+            | Command::SaveSessionAs
+            | Command::SwitchSession
+            | Command::ConvertIndentation => return None,
+            Command::MoveLinesUp => Shortcut::named(keyboard::key::Named::ArrowUp).alt(),
+            Command::MoveLinesDown => Shortcut::named(keyboard::key::Named::ArrowDown).alt(),
+            Command::DuplicateLines => Shortcut::command("d").shift(),
+            Command::DeleteLines => Shortcut::command("k").shift(),
+            Command::JoinLines => Shortcut::command("j"),
+            Command::ToggleLineComment => Shortcut::command("/"),
+            Command::ToggleBlockComment => Shortcut::command("a").shift(),
+            Command::SortLines
+            | Command::SortLinesNatural
+            | Command::SortLinesUnique
+            | Command::UpperCase
+            | Command::LowerCase
+            | Command::TitleCase
+            | Command::TrimTrailingWhitespace
+            | Command::ToggleFormatOnSave => return None,
+            Command::FormatDocument => Shortcut::command("i").shift(),
+            Command::JumpToBracket => Shortcut::command("\\").shift(),
+            Command::FoldAtCursor => Shortcut::command("[").shift(),
+            Command::UnfoldAtCursor => Shortcut::command("]").shift(),
+            Command::FoldAll => Shortcut::command("[").alt(),
+            Command::UnfoldAll => Shortcut::command("]").alt(),
+            Command::ToggleRelativeLineNumbers => Shortcut::command("l").alt(),
+            Command::ToggleMinimap => Shortcut::command("m").alt(),
+            Command::CycleSoftWrap => Shortcut::command("z").alt(),
+            Command::ToggleRulers => Shortcut::command("r").alt(),
+            Command::ToggleWhitespace => Shortcut::command("w").alt(),
+            Command::SelectColorTheme
+            | Command::SelectSyntaxTheme
+            | Command::ReloadThemes
+            | Command::SelectEditorFont
+            | Command::ToggleLigatures => return None,
+            Command::ZoomIn => Shortcut::command("="),
+            Command::ZoomOut => Shortcut::command("-"),
+            Command::ResetZoom => Shortcut::command("0"),
+            Command::ToggleSidebar => Shortcut::command("b"),
+            Command::ToggleZenMode => Shortcut::command_named(keyboard::key::Named::Enter).alt(),
+            Command::ShowExplorer => Shortcut::command("e").shift(),
+            Command::ShowSearch => Shortcut::command("f").shift(),
+            Command::ShowSourceControl => Shortcut::command("g").shift(),
+            Command::ToggleSidebarSide | Command::ShowOutline | Command::ToggleToolbar => {
+                return None;
+            }
+            Command::SplitRight => Shortcut::command("\\"),
+            Command::SplitDown => Shortcut::command("\\").alt(),
+            Command::ClosePane => Shortcut::command("w"),
+            Command::ToggleMaximizedPane => Shortcut::command("m").shift(),
+            Command::FocusLeftPane => {
+                Shortcut::command_named(keyboard::key::Named::ArrowLeft).alt()
+            }
+            Command::FocusRightPane => {
+                Shortcut::command_named(keyboard::key::Named::ArrowRight).alt()
+            }
+            Command::FocusAbovePane => Shortcut::command_named(keyboard::key::Named::ArrowUp).alt(),
+            Command::FocusBelowPane => {
+                Shortcut::command_named(keyboard::key::Named::ArrowDown).alt()
+            }
+            Command::ShowPalette => Shortcut::command("p").shift(),
+            Command::ShowWelcome => return None,
+        };
+
+        Some(shortcut)
+    }
+
+    pub fn message(self) -> Message {
+        match self {
+            Command::NewFile => Message::NewFile,
+            Command::OpenFile => Message::OpenFile,
+            Command::OpenDirectory => Message::OpenDirectory,
+            Command::OpenRecent => Message::ShowRecent,
+            Command::PinRecent => Message::PinRecent,
+            Command::ClearRecent => Message::ClearRecent,
+            Command::AddFolderToWorkspace => Message::AddFolderToWorkspace,
+            Command::RemoveFolderFromWorkspace => Message::RemoveFolderFromWorkspace,
+            Command::SaveFile => Message::SaveFile,
+            Command::SaveFileAs => Message::SaveFileAs,
+            Command::SaveCopy => Message::SaveCopy,
+            Command::SaveAll => Message::SaveAll,
+            Command::RevertFile => Message::RevertFile,
+            Command::ToggleReadOnly => Message::ToggleReadOnly,
+            Command::OpenSettings => Message::OpenSettings,
+            Command::RecoverUnsavedChanges => Message::ShowBackups,
+            Command::SaveSessionAs => Message::SaveSessionAs,
+            Command::SwitchSession => Message::SwitchSession,
+            Command::IndentLines => Message::Indent,
+            Command::OutdentLines => Message::Outdent,
+            Command::ConvertIndentation => Message::ConvertIndentation,
+            Command::MoveLinesUp => Message::MoveLinesUp,
+            Command::MoveLinesDown => Message::MoveLinesDown,
+            Command::DuplicateLines => Message::DuplicateLines,
+            Command::DeleteLines => Message::DeleteLines,
+            Command::JoinLines => Message::JoinLines,
+            Command::SortLines => Message::SortLines {
+                natural: false,
+                unique: false,
+            },
+            Command::SortLinesNatural => Message::SortLines {
+                natural: true,
+                unique: false,
+            },
+            Command::SortLinesUnique => Message::SortLines {
+                natural: false,
+                unique: true,
+            },
+            Command::ToggleLineComment => Message::ToggleLineComment,
+            Command::ToggleBlockComment => Message::ToggleBlockComment,
+            Command::UpperCase => Message::ConvertCase(Case::Upper),
+            Command::LowerCase => Message::ConvertCase(Case::Lower),
+            Command::TitleCase => Message::ConvertCase(Case::Title),
+            Command::TrimTrailingWhitespace => Message::TrimTrailingWhitespace,
+            Command::FormatDocument => Message::FormatDocument,
+            Command::ToggleFormatOnSave => Message::ToggleFormatOnSave,
+            Command::JumpToBracket => Message::JumpToBracket,
+            Command::FoldAtCursor => Message::FoldAtCursor,
+            Command::UnfoldAtCursor => Message::UnfoldAtCursor,
+            Command::FoldAll => Message::FoldAll,
+            Command::UnfoldAll => Message::UnfoldAll,
+            Command::ToggleRelativeLineNumbers => Message::ToggleRelativeLineNumbers,
+            Command::ToggleMinimap => Message::ToggleMinimap,
+            Command::ToggleToolbar => Message::ToggleToolbar,
+            Command::CycleSoftWrap => Message::CycleSoftWrap,
+            Command::ToggleRulers => Message::ToggleRulers,
+            Command::ToggleWhitespace => Message::ToggleWhitespace,
+            Command::SelectColorTheme => Message::SelectColorTheme,
+            Command::SelectSyntaxTheme => Message::SelectSyntaxTheme,
+            Command::ReloadThemes => Message::ReloadThemes,
+            Command::SelectEditorFont => Message::SelectEditorFont,
+            Command::ToggleLigatures => Message::ToggleLigatures,
+            Command::ZoomIn => Message::ZoomIn,
+            Command::ZoomOut => Message::ZoomOut,
+            Command::ResetZoom => Message::ResetZoom,
+            Command::ToggleSidebar => Message::ToggleSidebar,
+            Command::ToggleSidebarSide => Message::ToggleSidebarSide,
+            Command::ToggleZenMode => Message::ToggleZenMode,
+            Command::ShowExplorer => Message::ShowPanel(Panel::Explorer),
+            Command::ShowSearch => Message::ShowPanel(Panel::Search),
+            Command::ShowSourceControl => Message::ShowPanel(Panel::SourceControl),
+            Command::ShowOutline => Message::ShowPanel(Panel::Outline),
+            Command::SplitRight => Message::SplitPane(pane_grid::Axis::Vertical),
+            Command::SplitDown => Message::SplitPane(pane_grid::Axis::Horizontal),
+            Command::ClosePane => Message::ClosePane,
+            Command::ToggleMaximizedPane => Message::ToggleMaximizedPane,
+            Command::FocusLeftPane => Message::FocusPane(pane_grid::Direction::Left),
+            Command::FocusRightPane => Message::FocusPane(pane_grid::Direction::Right),
+            Command::FocusAbovePane => Message::FocusPane(pane_grid::Direction::Up),
+            Command::FocusBelowPane => Message::FocusPane(pane_grid::Direction::Down),
+            Command::ShowPalette => Message::TogglePalette,
+            Command::ShowWelcome => Message::ShowWelcome,
+        }
+    }
+}
+
+// The menus of the menu bar, from left to right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Menu {
+    File,
+    Edit,
+    Selection,
+    View,
+    Go,
+    Help,
+}
+
+pub const MENUS: &[Menu] = &[
+    Menu::File,
+    Menu::Edit,
+    Menu::Selection,
+    Menu::View,
+    Menu::Go,
+    Menu::Help,
+];
+
+impl fmt::Display for Menu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Menu::File => "File",
+            Menu::Edit => "Edit",
+            Menu::Selection => "Selection",
+            Menu::View => "View",
+            Menu::Go => "Go",
+            Menu::Help => "Help",
+        })
+    }
+}
+
+impl Command {
+    // The menu listing the command and its group there. Groups are separated by a line,
+    // commands keep the order of `ALL` within them.
+    pub fn menu(self) -> (Menu, u8) {
+        match self {
+            Command::NewFile | Command::OpenFile | Command::OpenDirectory => (Menu::File, 0),
+            Command::OpenRecent
+            | Command::PinRecent
+            | Command::ClearRecent
+            | Command::RecoverUnsavedChanges => (Menu::File, 1),
+            Command::AddFolderToWorkspace | Command::RemoveFolderFromWorkspace => (Menu::File, 2),
+            Command::SaveFile
+            | Command::SaveFileAs
+            | Command::SaveCopy
+            | Command::SaveAll
+            | Command::ToggleFormatOnSave => (Menu::File, 3),
+            Command::RevertFile | Command::ToggleReadOnly => (Menu::File, 4),
+            Command::SaveSessionAs | Command::SwitchSession => (Menu::File, 5),
+            Command::OpenSettings => (Menu::File, 6),
+            Command::IndentLines | Command::OutdentLines | Command::ConvertIndentation => {
+                (Menu::Edit, 0)
+            }
+            Command::ToggleLineComment | Command::ToggleBlockComment => (Menu::Edit, 1),
+            Command::TrimTrailingWhitespace | Command::FormatDocument => (Menu::Edit, 2),
+            Command::MoveLinesUp
+            | Command::MoveLinesDown
+            | Command::DuplicateLines
+            | Command::DeleteLines
+            | Command::JoinLines => (Menu::Selection, 0),
+            Command::SortLines | Command::SortLinesNatural | Command::SortLinesUnique => {
+                (Menu::Selection, 1)
+            }
+            Command::UpperCase | Command::LowerCase | Command::TitleCase => (Menu::Selection, 2),
+            Command::ShowPalette => (Menu::View, 0),
+            Command::ShowExplorer
+            | Command::ShowSearch
+            | Command::ShowSourceControl
+            | Command::ShowOutline => (Menu::View, 1),
+            Command::ToggleSidebar
+            | Command::ToggleSidebarSide
+            | Command::ToggleToolbar
+            | Command::ToggleZenMode => (Menu::View, 2),
+            Command::SplitRight
+            | Command::SplitDown
+            | Command::ClosePane
+            | Command::ToggleMaximizedPane => (Menu::View, 3),
+            Command::FoldAtCursor
+            | Command::UnfoldAtCursor
+            | Command::FoldAll
+            | Command::UnfoldAll => (Menu::View, 4),
+            Command::ToggleRelativeLineNumbers
+            | Command::ToggleMinimap
+            | Command::CycleSoftWrap
+            | Command::ToggleRulers
+            | Command::ToggleWhitespace => (Menu::View, 5),
+            Command::SelectColorTheme
+            | Command::SelectSyntaxTheme
+            | Command::ReloadThemes
+            | Command::SelectEditorFont
+            | Command::ToggleLigatures => (Menu::View, 6),
+            Command::ZoomIn | Command::ZoomOut | Command::ResetZoom => (Menu::View, 7),
+            Command::JumpToBracket => (Menu::Go, 0),
+            Command::FocusLeftPane
+            | Command::FocusRightPane
+            | Command::FocusAbovePane
+            | Command::FocusBelowPane => (Menu::Go, 1),
+            Command::ShowWelcome => (Menu::Help, 0),
+        }
+    }
+
+    // The title without its category, which the menu shows instead.
+    pub fn label(self) -> &'static str {
+        let title = self.title();
+        title.split_once(": ").map_or(title, |(_, label)| label)
+    }
+}
+
+// The commands of `menu` in their groups.
+pub fn menu_groups(menu: Menu) -> Vec<Vec<Command>> {
+    let mut groups: Vec<(u8, Vec<Command>)> = Vec::new();
+
+    for &command in ALL {
+        let (in_menu, group) = command.menu();
+        if in_menu != menu {
+            continue;
+        }
+
+        match groups.iter_mut().find(|(existing, _)| *existing == group) {
+            Some((_, commands)) => commands.push(command),
+            None => groups.push((group, vec![command])),
+        }
+    }
+
+    groups.sort_by_key(|(group, _)| *group);
+    groups.into_iter().map(|(_, commands)| commands).collect()
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.title())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Character(&'static str),
+    Named(keyboard::key::Named),
+}
+
+// A key with its modifiers. "Command" is Cmd on macOS and Ctrl elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shortcut {
+    key: Key,
+    command: bool,
+    shift: bool,
+    alt: bool,
+}
+
+impl Shortcut {
+    const fn command(key: &'static str) -> Self {
+        Self {
+            key: Key::Character(key),
+            command: true,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    // A named key without the command modifier, like Alt+Up.
+    const fn named(key: keyboard::key::Named) -> Self {
+        Self {
+            key: Key::Named(key),
+            command: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    // A named key with the command modifier, like Ctrl+Alt+Left.
+    const fn command_named(key: keyboard::key::Named) -> Self {
+        Self {
+            key: Key::Named(key),
+            command: true,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    const fn shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    const fn alt(mut self) -> Self {
+        self.alt = true;
+        self
+    }
+
+    fn matches(&self, key: keyboard::Key<&str>, modifiers: keyboard::Modifiers) -> bool {
+        let key_matches = match (self.key, key) {
+            (Key::Character(expected), keyboard::Key::Character(key)) => {
+                expected.eq_ignore_ascii_case(key)
+            }
+            (Key::Named(expected), keyboard::Key::Named(key)) => expected == key,
+            _ => false,
+        };
+
+        key_matches
+            && self.command == modifiers.command()
+            && self.shift == modifiers.shift()
+            && self.alt == modifiers.alt()
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut modifiers = Vec::new();
+
+        if self.command {
+            modifiers.push(if cfg!(target_os = "macos") {
+                "Cmd"
+            } else {
+                "Ctrl"
+            });
+        }
+
+        if self.shift {
+            modifiers.push("Shift");
+        }
+
+        if self.alt {
+            modifiers.push("Alt");
+        }
+
+        for modifier in modifiers {
+            write!(f, "{modifier}+")?;
+        }
+
+        match self.key {
+            Key::Character(key) => f.write_str(&key.to_uppercase()),
+            Key::Named(keyboard::key::Named::ArrowUp) => f.write_str("Up"),
+            Key::Named(keyboard::key::Named::ArrowDown) => f.write_str("Down"),
+            Key::Named(keyboard::key::Named::ArrowLeft) => f.write_str("Left"),
+            Key::Named(keyboard::key::Named::ArrowRight) => f.write_str("Right"),
+            Key::Named(key) => write!(f, "{key:?}"),
+        }
+    }
+}
+
+// The command bound to a key press, if any. `key` is the key without modifiers applied.
+pub fn find(key: &keyboard::Key, modifiers: keyboard::Modifiers) -> Option<Command> {
+    ALL.iter().copied().find(|command| {
+        command
+            .shortcut()
+            .is_some_and(|shortcut| shortcut.matches(key.as_ref(), modifiers))
+    })
+}
@@ -0,0 +1,235 @@
+use crate::gutter::LineMarker;
+use std::ops::Range;
+
+// Above this many cells the middle of the texts is replaced as a whole instead of
+// being diffed line by line.
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+// Lines `old` of the old text are replaced by `new`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub old: Range<usize>,
+    pub new: Vec<String>,
+}
+
+// The smallest set of line replacements turning `old` into `new`, in line order.
+pub fn lines(old: &[&str], new: &[&str]) -> Vec<Hunk> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    if old_middle.is_empty() && new_middle.is_empty() {
+        return Vec::new();
+    }
+
+    if old_middle.len() * new_middle.len() > MAX_TABLE_SIZE {
+        return vec![Hunk {
+            old: prefix..prefix + old_middle.len(),
+            new: new_middle.iter().map(|line| (*line).to_owned()).collect(),
+        }];
+    }
+
+    // Longest common subsequence lengths of every pair of suffixes.
+    let width = new_middle.len() + 1;
+    let mut table = vec![0u32; (old_middle.len() + 1) * width];
+
+    for i in (0..old_middle.len()).rev() {
+        for j in (0..new_middle.len()).rev() {
+            table[i * width + j] = if old_middle[i] == new_middle[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let mut current: Option<Hunk> = None;
+    let (mut i, mut j) = (0, 0);
+
+    while i < old_middle.len() || j < new_middle.len() {
+        if i < old_middle.len() && j < new_middle.len() && old_middle[i] == new_middle[j] {
+            hunks.extend(current.take());
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        let hunk = current.get_or_insert_with(|| Hunk {
+            old: prefix + i..prefix + i,
+            new: Vec::new(),
+        });
+
+        if j == new_middle.len()
+            || (i < old_middle.len() && table[(i + 1) * width + j] >= table[i * width + j + 1])
+        {
+            hunk.old.end += 1;
+            i += 1;
+        } else {
+            hunk.new.push(new_middle[j].to_owned());
+            j += 1;
+        }
+    }
+
+    hunks.extend(current);
+    hunks
+}
+
+// `hunks` as replacements of the whole lines `first..=last` of `old`, bottom up so the
+// line numbers of the ones above stay valid. Pure insertions and deletions take a
+// neighbouring line along, since a replacement always covers at least one whole line.
+pub fn replacements(hunks: &[Hunk], old: &[String]) -> Vec<(usize, usize, Vec<String>)> {
+    hunks
+        .iter()
+        .rev()
+        .map(|hunk| {
+            let (mut first, mut end) = (hunk.old.start, hunk.old.end);
+            let mut lines = hunk.new.clone();
+
+            if first == end || lines.is_empty() {
+                if end < old.len() {
+                    lines.push(old[end].clone());
+                    end += 1;
+                } else if first > 0 {
+                    first -= 1;
+                    lines.insert(0, old[first].clone());
+                }
+            }
+
+            (first, end - 1, lines)
+        })
+        .collect()
+}
+
+// Where `line` of the old text ends up once `hunks` are applied. Lines inside a
+// replaced range stay at the same offset into the replacement when they can.
+pub fn map_line(hunks: &[Hunk], line: usize) -> usize {
+    let mut shift = 0isize;
+
+    for hunk in hunks {
+        if line < hunk.old.start {
+            break;
+        }
+
+        if line < hunk.old.end {
+            let start = hunk.old.start.saturating_add_signed(shift);
+            let offset = (line - hunk.old.start).min(hunk.new.len().saturating_sub(1));
+            return start + offset;
+        }
+
+        shift += hunk.new.len() as isize - hunk.old.len() as isize;
+    }
+
+    line.saturating_add_signed(shift)
+}
+
+// Unchanged lines shown around each change of a unified diff.
+const CONTEXT: usize = 3;
+
+// `old` and `new` as a unified diff, like `diff -u` prints it. Empty when they are equal.
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old: Vec<&str> = old.split('\n').collect();
+    let new: Vec<&str> = new.split('\n').collect();
+    let hunks = lines(&old, &new);
+
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    // Changes with overlapping context are shown together.
+    let mut groups: Vec<&[Hunk]> = Vec::new();
+    let mut start = 0;
+    for index in 1..=hunks.len() {
+        if index == hunks.len() || hunks[index].old.start - hunks[index - 1].old.end > 2 * CONTEXT {
+            groups.push(&hunks[start..index]);
+            start = index;
+        }
+    }
+
+    let mut output = format!("--- {old_name}\n+++ {new_name}\n");
+    let mut shift = 0isize;
+
+    for group in groups {
+        let (first, last) = (&group[0], &group[group.len() - 1]);
+        let old_start = first.old.start.saturating_sub(CONTEXT);
+        let old_end = (last.old.end + CONTEXT).min(old.len());
+        let new_start = old_start.saturating_add_signed(shift);
+        let growth: isize = group
+            .iter()
+            .map(|hunk| hunk.new.len() as isize - hunk.old.len() as isize)
+            .sum();
+        let old_len = old_end - old_start;
+        let new_len = old_len.saturating_add_signed(growth);
+
+        // Empty ranges name the line before them.
+        let header = |start: usize, len: usize| {
+            format!("{},{len}", if len == 0 { start } else { start + 1 })
+        };
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            header(old_start, old_len),
+            header(new_start, new_len)
+        ));
+
+        let mut line = old_start;
+        for hunk in group {
+            for context in &old[line..hunk.old.start] {
+                output.push_str(&format!(" {context}\n"));
+            }
+            for removed in &old[hunk.old.clone()] {
+                output.push_str(&format!("-{removed}\n"));
+            }
+            for added in &hunk.new {
+                output.push_str(&format!("+{added}\n"));
+            }
+            line = hunk.old.end;
+        }
+        for context in &old[line..old_end] {
+            output.push_str(&format!(" {context}\n"));
+        }
+
+        shift += growth;
+    }
+
+    output
+}
+
+// Gutter markers for the lines of `new` that differ from `old`, like git's.
+pub fn line_markers(old: &str, new: &str) -> Vec<(usize, LineMarker)> {
+    let old: Vec<&str> = old.split('\n').collect();
+    let new: Vec<&str> = new.split('\n').collect();
+    let mut markers = Vec::new();
+    let mut shift = 0isize;
+
+    for hunk in lines(&old, &new) {
+        let start = hunk.old.start.saturating_add_signed(shift);
+
+        if hunk.new.is_empty() {
+            // Pure deletions point at the line just above the removed ones.
+            markers.push((start.saturating_sub(1), LineMarker::Deleted));
+        } else {
+            let marker = if hunk.old.is_empty() {
+                LineMarker::Added
+            } else {
+                LineMarker::Modified
+            };
+            markers.extend((start..start + hunk.new.len()).map(|line| (line, marker)));
+        }
+
+        shift += hunk.new.len() as isize - hunk.old.len() as isize;
+    }
+
+    markers
+}
@@ -0,0 +1,280 @@
+use crate::diff;
+use crate::fold::FoldRange;
+use crate::fold::Folds;
+use crate::gutter::FoldMarker;
+use crate::gutter::LineMarker;
+use crate::indent::Indentation;
+use crate::language::Language;
+use crate::layout::LineLayout;
+use iced::widget;
+use iced::widget::canvas;
+use iced::widget::scrollable;
+use iced::widget::text_editor;
+use iced::widget::text_editor::Position;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic;
+use std::sync::atomic::AtomicU64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BufferId(u64);
+
+impl BufferId {
+    pub fn unique() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+
+        Self(NEXT.fetch_add(1, atomic::Ordering::Relaxed))
+    }
+}
+
+impl fmt::Display for BufferId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// A document, shared by every pane showing it. The text itself lives in the editors,
+// each keeping its own copy in sync.
+#[derive(Debug)]
+pub struct Buffer {
+    pub path: Option<PathBuf>,
+    pub is_dirty: bool,
+    pub detected_language: Language,
+    pub language_override: Option<Language>,
+    pub fold_ranges: Vec<FoldRange>,
+    // Sorted by buffer line.
+    pub line_markers: Vec<(usize, LineMarker)>,
+    pub indentation: Indentation,
+    pub read_only: bool,
+    // Text the line markers compare against instead of the git index, for `--diff`.
+    pub diff_base: Option<String>,
+}
+
+impl Buffer {
+    pub fn new(
+        path: Option<PathBuf>,
+        detected_language: Language,
+        indentation: Indentation,
+    ) -> Self {
+        Self {
+            path,
+            is_dirty: false,
+            detected_language,
+            language_override: None,
+            fold_ranges: Vec::new(),
+            line_markers: Vec::new(),
+            indentation,
+            read_only: false,
+            diff_base: None,
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language_override.unwrap_or(self.detected_language)
+    }
+
+    pub fn line_marker(&self, line: usize) -> Option<LineMarker> {
+        self.line_markers
+            .binary_search_by_key(&line, |&(line, _)| line)
+            .ok()
+            .map(|index| self.line_markers[index].1)
+    }
+}
+
+// One view of a buffer, with its own cursor, folds and scroll position.
+pub struct Editor {
+    pub buffer: BufferId,
+    pub content: text_editor::Content,
+    pub folds: Folds,
+    pub viewport: Option<scrollable::Viewport>,
+    pub line_layout: LineLayout,
+    pub minimap_cache: canvas::Cache,
+    // Display line where a drag across the gutter started.
+    pub gutter_drag: Option<usize>,
+    pub id: widget::Id,
+    pub scrollable: widget::Id,
+}
+
+impl Editor {
+    pub fn new(
+        buffer: BufferId,
+        text: &str,
+        text_size: f32,
+        char_width: f32,
+        line_height: f32,
+    ) -> Self {
+        let content = text_editor::Content::with_text(text);
+        let line_layout = LineLayout::new(&content, None, text_size, char_width, line_height);
+
+        Self {
+            buffer,
+            content,
+            folds: Folds::default(),
+            viewport: None,
+            line_layout,
+            minimap_cache: canvas::Cache::new(),
+            gutter_drag: None,
+            id: widget::Id::unique(),
+            scrollable: widget::Id::unique(),
+        }
+    }
+
+    // Shows `buffer` from the start, with nothing folded.
+    pub fn reset(&mut self, buffer: BufferId, text: &str) {
+        self.buffer = buffer;
+        self.content = text_editor::Content::with_text(text);
+        self.folds.clear();
+        self.gutter_drag = None;
+    }
+
+    // Another view of the same buffer, with nothing folded and the cursor on the same text.
+    pub fn split(&self, text_size: f32, char_width: f32, line_height: f32) -> Self {
+        let mut editor = Self::new(
+            self.buffer,
+            &self.text(),
+            text_size,
+            char_width,
+            line_height,
+        );
+
+        let cursor = self.content.cursor();
+        let to_buffer = |position: Position| Position {
+            line: self.folds.to_buffer_line(position.line),
+            column: position.column,
+        };
+
+        editor.content.move_to(text_editor::Cursor {
+            position: to_buffer(cursor.position),
+            selection: cursor.selection.map(to_buffer),
+        });
+        editor
+    }
+
+    pub fn fold_marker(&self, line: usize, fold_ranges: &[FoldRange]) -> Option<FoldMarker> {
+        if self.folds.is_folded(line) {
+            return Some(FoldMarker::Folded);
+        }
+
+        let start = self.folds.to_buffer_line(line);
+        fold_ranges
+            .binary_search_by_key(&start, |range| range.start)
+            .ok()
+            .map(|_| FoldMarker::Open)
+    }
+
+    // The buffer text, including the folded lines.
+    pub fn text(&self) -> String {
+        self.folds.text(&self.content)
+    }
+
+    // Replaces the display lines `first..=last` with `lines`, the cursor ends up after them.
+    pub fn replace_lines(&mut self, first: usize, last: usize, lines: &[String]) {
+        let end = self
+            .content
+            .line(last)
+            .map(|line| line.text.chars().count())
+            .unwrap_or_default();
+
+        self.content.move_to(text_editor::Cursor {
+            position: Position {
+                line: last,
+                column: end,
+            },
+            selection: Some(Position {
+                line: first,
+                column: 0,
+            }),
+        });
+        self.folds.perform(
+            &mut self.content,
+            text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(lines.join("\n")))),
+        );
+    }
+
+    // Takes over `text` after it was edited in another view of the buffer. The cursor and
+    // the folds stay on the same lines as long as the text around them did not change.
+    // Only the changed lines are replaced, unless that would unfold something.
+    pub fn sync(&mut self, text: &str, fold_ranges: &[FoldRange]) {
+        let split = |text: &str| -> Vec<String> {
+            text.split('\n')
+                .map(|line| line.strip_suffix('\r').unwrap_or(line).to_owned())
+                .collect()
+        };
+
+        let old_lines = split(&self.text());
+        let new_lines = split(text);
+        let hunks = diff::lines(
+            &old_lines.iter().map(String::as_str).collect::<Vec<_>>(),
+            &new_lines.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+
+        if hunks.is_empty() {
+            return;
+        }
+
+        let cursor = self.content.cursor();
+        let to_buffer = |position: Position| Position {
+            line: diff::map_line(&hunks, self.folds.to_buffer_line(position.line)),
+            column: position.column,
+        };
+        let position = to_buffer(cursor.position);
+        let selection = cursor.selection.map(to_buffer);
+
+        let replacements = diff::replacements(&hunks, &old_lines);
+        if replacements
+            .iter()
+            .all(|(first, last, lines)| self.replaces_in_place(*first, *last, lines.len()))
+        {
+            for (first, last, lines) in replacements {
+                let first = self.folds.to_display_line(first);
+                let last = self.folds.to_display_line(last);
+                self.replace_lines(first, last, &lines);
+            }
+        } else {
+            self.rebuild(text, &hunks, fold_ranges);
+        }
+
+        let last = self.content.line_count().saturating_sub(1);
+        let to_display = |position: Position| Position {
+            line: self.folds.to_display_line(position.line).min(last),
+            column: position.column,
+        };
+
+        self.content.move_to(text_editor::Cursor {
+            position: to_display(position),
+            selection: selection.map(to_display),
+        });
+    }
+
+    // Whether the buffer lines `first..=last` can be replaced by `count` lines without
+    // unfolding anything: they are all shown and only a single line may be a fold header.
+    fn replaces_in_place(&self, first: usize, last: usize, count: usize) -> bool {
+        let start = self.folds.to_display_line(first);
+        if self.folds.to_buffer_line(start) != first {
+            return false;
+        }
+
+        let end = start + (last - first);
+        let single = first == last && count == 1;
+        single || (start..=end).all(|line| !self.folds.is_folded(line))
+    }
+
+    // Starts over from `text` and folds again what was folded before `hunks` were applied.
+    fn rebuild(&mut self, text: &str, hunks: &[diff::Hunk], fold_ranges: &[FoldRange]) {
+        // Inner folds stay hidden inside their outer fold, only the outer ones come back.
+        let folded: Vec<usize> = (0..self.content.line_count())
+            .filter(|&line| self.folds.is_folded(line))
+            .map(|line| diff::map_line(hunks, self.folds.to_buffer_line(line)))
+            .collect();
+
+        self.folds.clear();
+        self.content = text_editor::Content::with_text(text);
+
+        for start in folded {
+            if let Some(range) = fold_ranges.iter().find(|range| range.start == start) {
+                self.folds.fold(&mut self.content, *range);
+            }
+        }
+    }
+}
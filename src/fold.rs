@@ -0,0 +1,441 @@
+use crate::language::Language;
+use iced::widget::text_editor::Action;
+use iced::widget::text_editor::Content;
+use iced::widget::text_editor::Cursor;
+use iced::widget::text_editor::Edit;
+use iced::widget::text_editor::Position;
+
+const TAB_WIDTH: usize = 4;
+
+// A foldable region in buffer lines: `start` stays visible, `start + 1..=end` gets hidden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoldRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl FoldRange {
+    pub fn contains(&self, line: usize) -> bool {
+        (self.start..=self.end).contains(&line)
+    }
+}
+
+pub fn ranges(text: &str, language: &Language) -> Vec<FoldRange> {
+    let lines: Vec<&str> = text.lines().collect();
+
+    let mut ranges = if language.block_delimiters.is_empty() {
+        indent_ranges(&lines)
+    } else {
+        bracket_ranges(&lines, language.block_delimiters)
+    };
+
+    ranges.sort_by_key(|range| (range.start, std::cmp::Reverse(range.end)));
+    ranges.dedup_by_key(|range| range.start);
+    ranges
+}
+
+fn indent_width(line: &str) -> Option<usize> {
+    if line.trim().is_empty() {
+        return None;
+    }
+
+    Some(
+        line.chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+            .sum(),
+    )
+}
+
+fn indent_ranges(lines: &[&str]) -> Vec<FoldRange> {
+    let mut ranges = Vec::new();
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut last_non_blank = 0;
+
+    for (index, line) in lines.iter().enumerate() {
+        let Some(width) = indent_width(line) else {
+            continue;
+        };
+
+        while let Some(&(start, start_width)) = open.last() {
+            if start_width < width {
+                break;
+            }
+
+            open.pop();
+            if last_non_blank > start {
+                ranges.push(FoldRange {
+                    start,
+                    end: last_non_blank,
+                });
+            }
+        }
+
+        open.push((index, width));
+        last_non_blank = index;
+    }
+
+    for (start, _) in open {
+        if last_non_blank > start {
+            ranges.push(FoldRange {
+                start,
+                end: last_non_blank,
+            });
+        }
+    }
+
+    ranges
+}
+
+// Pairs up delimiters while skipping string and character literals; the line holding the
+// closing delimiter stays visible so `}` keeps lining up with its opener.
+fn bracket_ranges(lines: &[&str], delimiters: &[(char, char)]) -> Vec<FoldRange> {
+    let mut ranges = Vec::new();
+    let mut open: Vec<(char, usize)> = Vec::new();
+    let mut in_string = false;
+
+    for (index, line) in lines.iter().enumerate() {
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if in_string {
+                match c {
+                    '\\' => {
+                        chars.next();
+                    }
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match c {
+                '"' => in_string = true,
+                '\'' => {
+                    // Skip `'x'` and `'\n'` but leave lifetimes such as `'a` alone.
+                    let mut lookahead = chars.clone();
+                    let literal = match lookahead.next() {
+                        Some('\\') => lookahead.nth(1) == Some('\''),
+                        Some(_) => lookahead.next() == Some('\''),
+                        None => false,
+                    };
+                    if literal {
+                        chars = lookahead;
+                    }
+                }
+                _ => {
+                    if delimiters.iter().any(|&(opening, _)| opening == c) {
+                        open.push((c, index));
+                    } else if let Some(&(opening, _)) =
+                        delimiters.iter().find(|&&(_, closing)| closing == c)
+                        && let Some(position) = open.iter().rposition(|&(o, _)| o == opening)
+                    {
+                        let (_, start) = open[position];
+                        open.truncate(position);
+
+                        if index > start + 1 {
+                            ranges.push(FoldRange {
+                                start,
+                                end: index - 1,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    ranges
+}
+
+#[derive(Debug, Clone)]
+struct Fold {
+    // Visible line of the fold header, relative to whatever list of lines holds the fold.
+    line: usize,
+    // Lines hidden behind the header, with any inner folds still collapsed.
+    hidden: Vec<String>,
+    nested: Vec<Fold>,
+}
+
+impl Fold {
+    fn len(&self) -> usize {
+        self.hidden.len() + self.nested.iter().map(Fold::len).sum::<usize>()
+    }
+}
+
+// Folded lines are removed from the editor `Content` and kept here, so the editor only
+// ever sees the visible lines. Line numbers are either "display" lines (what the editor
+// shows) or buffer lines (what ends up on disk).
+#[derive(Debug, Default)]
+pub struct Folds {
+    folds: Vec<Fold>,
+}
+
+impl Folds {
+    pub fn clear(&mut self) {
+        self.folds.clear();
+    }
+
+    pub fn is_folded(&self, line: usize) -> bool {
+        self.folds.iter().any(|fold| fold.line == line)
+    }
+
+    pub fn to_buffer_line(&self, line: usize) -> usize {
+        line + self
+            .folds
+            .iter()
+            .take_while(|fold| fold.line < line)
+            .map(Fold::len)
+            .sum::<usize>()
+    }
+
+    // Lines hidden inside a fold map onto its header.
+    pub fn to_display_line(&self, line: usize) -> usize {
+        let mut hidden = 0;
+
+        for fold in &self.folds {
+            let header = fold.line + hidden;
+
+            if line <= header {
+                break;
+            }
+
+            if line <= header + fold.len() {
+                return fold.line;
+            }
+
+            hidden += fold.len();
+        }
+
+        line - hidden
+    }
+
+    pub fn text(&self, content: &Content) -> String {
+        if self.folds.is_empty() {
+            return content.text();
+        }
+
+        let lines = display_lines(content);
+        let mut expanded = Vec::with_capacity(lines.len());
+        expand(&mut expanded, &lines, &self.folds);
+
+        expanded.join(line_ending(content))
+    }
+
+    pub fn fold(&mut self, content: &mut Content, range: FoldRange) {
+        let header = self.to_display_line(range.start);
+        let last = self.to_display_line(range.end);
+
+        if self.to_buffer_line(header) != range.start || last <= header || self.is_folded(header) {
+            return;
+        }
+
+        let mut lines = display_lines(content);
+        let hidden: Vec<String> = lines.drain(header + 1..=last).collect();
+        let removed = hidden.len();
+
+        let mut nested = Vec::new();
+        self.folds.retain_mut(|fold| {
+            if fold.line > header && fold.line <= last {
+                fold.line -= header + 1;
+                nested.push(fold.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        for fold in &mut self.folds {
+            if fold.line > last {
+                fold.line -= removed;
+            }
+        }
+
+        let index = self.folds.partition_point(|fold| fold.line < header);
+        self.folds.insert(
+            index,
+            Fold {
+                line: header,
+                hidden,
+                nested,
+            },
+        );
+
+        let cursor = content.cursor();
+        let shift = |position: Position| {
+            if position.line > last {
+                Position {
+                    line: position.line - removed,
+                    column: position.column,
+                }
+            } else if position.line > header {
+                Position {
+                    line: header,
+                    column: lines[header].chars().count(),
+                }
+            } else {
+                position
+            }
+        };
+
+        rebuild(content, &lines, map_cursor(cursor, shift));
+    }
+
+    pub fn unfold(&mut self, content: &mut Content, line: usize) {
+        let Some(index) = self.folds.iter().position(|fold| fold.line == line) else {
+            return;
+        };
+
+        let fold = self.folds.remove(index);
+        let added = fold.hidden.len();
+
+        let mut lines = display_lines(content);
+        lines.splice(line + 1..line + 1, fold.hidden);
+
+        for other in &mut self.folds {
+            if other.line > line {
+                other.line += added;
+            }
+        }
+
+        for mut nested in fold.nested {
+            nested.line += line + 1;
+            let index = self.folds.partition_point(|fold| fold.line < nested.line);
+            self.folds.insert(index, nested);
+        }
+
+        let shift = |position: Position| {
+            if position.line > line {
+                Position {
+                    line: position.line + added,
+                    column: position.column,
+                }
+            } else {
+                position
+            }
+        };
+
+        let cursor = map_cursor(content.cursor(), shift);
+        rebuild(content, &lines, cursor);
+    }
+
+    pub fn unfold_all(&mut self, content: &mut Content) {
+        while let Some(line) = self.folds.last().map(|fold| fold.line) {
+            self.unfold(content, line);
+        }
+    }
+
+    // Unfolds every fold with its header in `first..=last`, including nested ones.
+    pub fn unfold_lines(&mut self, content: &mut Content, first: usize, last: usize) {
+        while let Some(line) = self
+            .folds
+            .iter()
+            .rev()
+            .map(|fold| fold.line)
+            .find(|line| (first..=last).contains(line))
+        {
+            self.unfold(content, line);
+        }
+    }
+
+    // Applies an editor action while keeping folds attached to their headers. Edits that
+    // split or join a folded header line unfold it first, since the hidden lines belong
+    // right after the header.
+    pub fn perform(&mut self, content: &mut Content, action: Action) {
+        let Action::Edit(edit) = &action else {
+            content.perform(action);
+            return;
+        };
+
+        if self.folds.is_empty() {
+            content.perform(action);
+            return;
+        }
+
+        let cursor = content.cursor();
+        let (start, mut end) = selection_lines(cursor);
+        let line_length = content
+            .line(cursor.position.line)
+            .map(|line| line.text.chars().count())
+            .unwrap_or_default();
+
+        let structural = start != end
+            || match edit {
+                Edit::Enter => true,
+                Edit::Paste(text) => text.contains('\n'),
+                Edit::Backspace => cursor.selection.is_none() && cursor.position.column == 0,
+                Edit::Delete => cursor.selection.is_none() && cursor.position.column >= line_length,
+                Edit::Insert(_) | Edit::Indent | Edit::Unindent => false,
+            };
+
+        if structural {
+            let first = if matches!(edit, Edit::Backspace) && cursor.selection.is_none() {
+                start.saturating_sub(1)
+            } else {
+                start
+            };
+            let last = if matches!(edit, Edit::Delete) && cursor.selection.is_none() {
+                end + 1
+            } else {
+                end
+            };
+
+            self.unfold_lines(content, first, last);
+
+            end = selection_lines(content.cursor()).1;
+        }
+
+        let before = content.line_count();
+        content.perform(action);
+        let after = content.line_count();
+
+        for fold in &mut self.folds {
+            if fold.line > end {
+                fold.line = (fold.line + after).saturating_sub(before);
+            }
+        }
+    }
+}
+
+fn selection_lines(cursor: Cursor) -> (usize, usize) {
+    let other = cursor.selection.unwrap_or(cursor.position);
+    let start = cursor.position.line.min(other.line);
+    let end = cursor.position.line.max(other.line);
+    (start, end)
+}
+
+fn expand(out: &mut Vec<String>, lines: &[String], folds: &[Fold]) {
+    let mut folds = folds.iter().peekable();
+
+    for (index, line) in lines.iter().enumerate() {
+        out.push(line.clone());
+
+        if let Some(fold) = folds.next_if(|fold| fold.line == index) {
+            expand(out, &fold.hidden, &fold.nested);
+        }
+    }
+}
+
+fn display_lines(content: &Content) -> Vec<String> {
+    content.lines().map(|line| line.text.into_owned()).collect()
+}
+
+fn line_ending(content: &Content) -> &'static str {
+    match content.line_ending() {
+        Some(ending) if !ending.as_str().is_empty() => ending.as_str(),
+        _ => "\n",
+    }
+}
+
+fn map_cursor(cursor: Cursor, shift: impl Fn(Position) -> Position) -> Cursor {
+    Cursor {
+        position: shift(cursor.position),
+        selection: cursor.selection.map(shift),
+    }
+}
+
+fn rebuild(content: &mut Content, lines: &[String], cursor: Cursor) {
+    let ending = line_ending(content);
+    *content = Content::with_text(&lines.join(ending));
+    content.move_to(cursor);
+}
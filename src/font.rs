@@ -0,0 +1,128 @@
+use iced::Font;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+// Advance of a monospace glyph relative to the text size, close enough for the common
+// monospace fonts until the editor font is measured.
+pub const DEFAULT_ADVANCE: f32 = 0.6;
+// The family cosmic-text shows for iced's default monospace font.
+const DEFAULT_MONOSPACE: &str = "Noto Sans Mono";
+
+// The icon font bundled with the editor.
+pub const ICONS: &str = "xeditor";
+
+// Zoom levels reached with Ctrl+= and Ctrl+-.
+pub const ZOOM_STEP: f32 = 0.1;
+pub const MIN_ZOOM: f32 = 0.5;
+pub const MAX_ZOOM: f32 = 3.0;
+
+// iced looks fonts up by a static name. Names are interned so reloading the settings
+// or picking fonts again does not leak the same name twice.
+pub fn named(name: &str) -> Font {
+    static NAMES: Mutex<Option<HashSet<&'static str>>> = Mutex::new(None);
+
+    let mut names = NAMES.lock().unwrap_or_else(|error| error.into_inner());
+    let names = names.get_or_insert_with(HashSet::new);
+
+    let name = match names.get(name) {
+        Some(name) => *name,
+        None => {
+            let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+            names.insert(name);
+            name
+        }
+    };
+
+    Font::with_name(name)
+}
+
+// An installed font family.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Family {
+    pub name: String,
+    pub monospace: bool,
+}
+
+// The installed font families, sorted by name.
+pub fn families() -> Vec<Family> {
+    let mut database = fontdb::Database::new();
+    database.load_system_fonts();
+
+    let mut families: Vec<Family> = database
+        .faces()
+        .filter_map(|face| {
+            let (name, _language) = face.families.first()?;
+            Some(Family {
+                name: name.clone(),
+                monospace: face.monospaced,
+            })
+        })
+        .collect();
+
+    families.sort_by_key(|family| (family.name.to_lowercase(), !family.monospace));
+    families.dedup_by(|a, b| a.name == b.name);
+    families
+}
+
+pub async fn load_families() -> Vec<Family> {
+    tokio::task::spawn_blocking(families)
+        .await
+        .unwrap_or_default()
+}
+
+// Advance of the glyphs of the family `name`, or of the default monospace font, relative to
+// the text size. Measured on the digit zero like the CSS `ch` unit, every glyph of a
+// monospace font has the same.
+fn advance(name: Option<&str>) -> Option<f32> {
+    let mut database = fontdb::Database::new();
+    database.load_system_fonts();
+    database.set_monospace_family(DEFAULT_MONOSPACE);
+
+    let family = match name {
+        Some(name) => fontdb::Family::Name(name),
+        None => fontdb::Family::Monospace,
+    };
+    let face = database.query(&fontdb::Query {
+        families: &[family],
+        ..fontdb::Query::default()
+    })?;
+
+    database.with_face_data(face, |data, index| {
+        let face = ttf_parser::Face::parse(data, index).ok()?;
+        let advance = face.glyph_hor_advance(face.glyph_index('0')?)?;
+        Some(f32::from(advance) / f32::from(face.units_per_em()))
+    })?
+}
+
+pub async fn measure(name: Option<String>) -> f32 {
+    tokio::task::spawn_blocking(move || advance(name.as_deref()))
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_ADVANCE)
+}
+
+// The installed family called `name`, ignoring case.
+pub fn find<'a>(families: &'a [Family], name: &str) -> Option<&'a str> {
+    families
+        .iter()
+        .find(|family| family.name.eq_ignore_ascii_case(name))
+        .map(|family| family.name.as_str())
+}
+
+// iced always shapes the editor text with the font's ligatures, so turning them off
+// means switching to the variant of the family built without them, when installed.
+pub fn without_ligatures<'a>(families: &'a [Family], name: &str) -> Option<&'a str> {
+    let candidates = [
+        // JetBrains Mono NL.
+        format!("{name} NL"),
+        format!("{name} No Ligatures"),
+        // Cascadia Code and Cascadia Mono, Fira Code and Fira Mono.
+        name.replace(" Code", " Mono"),
+    ];
+
+    candidates
+        .iter()
+        .filter(|candidate| !candidate.eq_ignore_ascii_case(name))
+        .find_map(|candidate| find(families, candidate))
+}
@@ -0,0 +1,96 @@
+use crate::Error;
+use crate::language::Language;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process;
+
+// Formatters taking longer than this are killed and the buffer is left alone.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Stands for the path of the buffer in formatter arguments, for tools that pick their
+// parser or configuration from it.
+const PATH_PLACEHOLDER: &str = "{path}";
+
+// An external program reading the buffer on stdin and writing it formatted to stdout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formatter {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl Formatter {
+    // Splits a command line like "rustfmt --edition 2024" on whitespace.
+    pub fn parse(command: &str) -> Option<Self> {
+        let mut words = command.split_whitespace().map(str::to_owned);
+
+        Some(Self {
+            program: words.next()?,
+            args: words.collect(),
+        })
+    }
+}
+
+// Pipes `text` through `formatter`. Runs next to the file so formatters find their
+// project configuration. Unsaved buffers get a made up name with the language's
+// extension.
+pub async fn run(
+    formatter: Formatter,
+    text: String,
+    path: Option<PathBuf>,
+    language: Language,
+) -> Result<String, Error> {
+    let path = path.unwrap_or_else(|| {
+        let extension = language.extensions.first().copied().unwrap_or("txt");
+        PathBuf::from(format!("untitled.{extension}"))
+    });
+
+    let args = formatter
+        .args
+        .iter()
+        .map(|arg| arg.replace(PATH_PLACEHOLDER, &path.to_string_lossy()));
+
+    let mut command = process::Command::new(&formatter.program);
+    command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    if let Some(directory) = path.parent().filter(|parent| parent.is_dir()) {
+        command.current_dir(directory);
+    }
+
+    let failed =
+        |reason: String| Error::FormatterFailed(format!("{}: {reason}", formatter.program));
+
+    let mut child = command.spawn().map_err(|error| failed(error.to_string()))?;
+
+    // Written from its own task, formatters may start printing before reading it all.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = tokio::spawn(async move {
+        let _ = stdin.write_all(text.as_bytes()).await;
+    });
+
+    let output = tokio::time::timeout(TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| failed(format!("timed out after {}s", TIMEOUT.as_secs())))?
+        .map_err(|error| failed(error.to_string()))?;
+
+    let _ = writer.await;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map_or_else(|| output.status.to_string(), str::to_owned);
+
+        return Err(failed(reason));
+    }
+
+    String::from_utf8(output.stdout).map_err(|_| failed(String::from("output is not UTF-8")))
+}
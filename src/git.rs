@@ -0,0 +1,134 @@
+use crate::gutter::LineMarker;
+use std::path::Path;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+// Lines changed against the index, keyed by zero-based buffer line. Files outside a
+// repository, or without git installed, simply have no markers.
+pub async fn line_changes(path: PathBuf) -> Vec<(usize, LineMarker)> {
+    let (Some(directory), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(["diff", "--no-color", "--no-ext-diff", "-U0", "--"])
+        .arg(file_name)
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_diff(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+// A changed file in the working tree, with its two letter `git status --short` code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStatus {
+    pub path: PathBuf,
+    pub code: String,
+}
+
+// Changed files of the repository containing `directory`, none outside a repository.
+pub async fn status(directory: PathBuf) -> Vec<FileStatus> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(&directory)
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .await;
+
+    let top_level = match output {
+        Ok(output) if output.status.success() => {
+            PathBuf::from(String::from_utf8_lossy(&output.stdout).trim_end())
+        }
+        _ => return Vec::new(),
+    };
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(&top_level)
+        .args(["status", "--porcelain", "-z", "--untracked-files=all"])
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_status(&top_level, &String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+// Entries are `XY path`, separated by NUL. Renames and copies are followed by their
+// original path. Paths are relative to the top level of the repository.
+fn parse_status(top_level: &Path, status: &str) -> Vec<FileStatus> {
+    let mut changes = Vec::new();
+    let mut entries = status.split('\0');
+
+    while let Some(entry) = entries.next() {
+        let (Some(code), Some(path)) = (entry.get(..2), entry.get(3..)) else {
+            continue;
+        };
+
+        if code.contains(['R', 'C']) {
+            entries.next();
+        }
+
+        changes.push(FileStatus {
+            path: top_level.join(path),
+            code: code.to_owned(),
+        });
+    }
+
+    changes
+}
+
+fn parse_diff(diff: &str) -> Vec<(usize, LineMarker)> {
+    let mut markers = Vec::new();
+
+    for header in diff.lines().filter(|line| line.starts_with("@@")) {
+        let mut ranges = header.split_whitespace().skip(1);
+        let (Some(old), Some(new)) = (ranges.next(), ranges.next()) else {
+            continue;
+        };
+
+        let (_, old_count) = parse_range(old.trim_start_matches('-'));
+        let (new_start, new_count) = parse_range(new.trim_start_matches('+'));
+
+        if new_count == 0 {
+            // Pure deletions point at the line just above the removed ones.
+            markers.push((new_start.saturating_sub(1), LineMarker::Deleted));
+            continue;
+        }
+
+        let marker = if old_count == 0 {
+            LineMarker::Added
+        } else {
+            LineMarker::Modified
+        };
+
+        let first = new_start.saturating_sub(1);
+        markers.extend((first..first + new_count).map(|line| (line, marker)));
+    }
+
+    markers
+}
+
+// `12,3` means three lines starting at line 12, a bare `12` means one line.
+fn parse_range(range: &str) -> (usize, usize) {
+    let mut parts = range.split(',');
+    let start = parts
+        .next()
+        .and_then(|start| start.parse().ok())
+        .unwrap_or(0);
+    let count = parts
+        .next()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(1);
+    (start, count)
+}
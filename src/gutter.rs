@@ -0,0 +1,177 @@
+use crate::Message;
+use crate::layout::LineLayout;
+use crate::theme::Tokens;
+use iced::Alignment;
+use iced::Color;
+use iced::Element;
+use iced::Font;
+use iced::Length;
+use iced::widget::Space;
+use iced::widget::button;
+use iced::widget::column;
+use iced::widget::container;
+use iced::widget::mouse_area;
+use iced::widget::pin;
+use iced::widget::row;
+use iced::widget::text;
+use std::ops::Range;
+
+const FOLD_COLUMN_WIDTH: f32 = 16.0;
+const MARKER_WIDTH: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldMarker {
+    Open,
+    Folded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineNumbers {
+    #[default]
+    Absolute,
+    Relative,
+}
+
+// Diagnostics are not produced yet, their variants only reserve a color in the marker column.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineMarker {
+    Added,
+    Modified,
+    Deleted,
+    Error,
+    Warning,
+}
+
+impl LineMarker {
+    pub fn color(self, tokens: Tokens) -> Color {
+        match self {
+            LineMarker::Added => tokens.added,
+            LineMarker::Modified => tokens.modified,
+            LineMarker::Deleted => tokens.deleted,
+            LineMarker::Error => tokens.error,
+            LineMarker::Warning => tokens.warning,
+        }
+    }
+}
+
+// What the gutter shows next to a single display line.
+pub struct Line {
+    pub number: usize,
+    pub fold: Option<FoldMarker>,
+    pub marker: Option<LineMarker>,
+}
+
+pub fn current_line_highlight<'a>(
+    layout: &LineLayout,
+    cursor_line: usize,
+    tokens: Tokens,
+) -> Element<'a, Message> {
+    column![
+        Space::new().height(Length::Fixed(layout.line_top(cursor_line))),
+        container(Space::new())
+            .width(Length::Fill)
+            .height(Length::Fixed(layout.line_height(cursor_line)))
+            .style(move |_theme| container::Style {
+                background: Some(tokens.current_line.into()),
+                ..container::Style::default()
+            }),
+    ]
+    .into()
+}
+
+// The gutter lives outside the editor scrollable, so it is not scrolled horizontally.
+// Only the visible rows are built and then shifted by the vertical scroll `offset`.
+#[allow(clippy::too_many_arguments)]
+pub fn view<'a>(
+    layout: &LineLayout,
+    offset: f32,
+    visible: Range<usize>,
+    cursor_line: usize,
+    numbers: LineNumbers,
+    max_number: usize,
+    tokens: Tokens,
+    icons: Font,
+    line: impl Fn(usize) -> Line,
+) -> Element<'a, Message> {
+    let top = layout.line_top(visible.start) - offset;
+    let number_width = (max_number.max(1).ilog10() + 1) as f32 * layout.char_width();
+
+    let rows = visible.map(|index| {
+        let Line {
+            number,
+            fold,
+            marker,
+        } = line(index);
+        let is_current = index == cursor_line;
+
+        let marker = container(Space::new())
+            .width(Length::Fixed(MARKER_WIDTH))
+            .height(Length::Fill)
+            .style(move |_theme| container::Style {
+                background: marker.map(|marker| marker.color(tokens).into()),
+                ..container::Style::default()
+            });
+
+        let label = match numbers {
+            LineNumbers::Relative if !is_current => index.abs_diff(cursor_line),
+            _ => number + 1,
+        };
+
+        let number = text(label)
+            .size(layout.text_size())
+            .width(Length::Fixed(number_width))
+            .align_x(Alignment::End)
+            .color(if is_current {
+                tokens.current_line_number
+            } else {
+                tokens.text_muted
+            });
+
+        let chevron: Element<'a, Message> = match fold {
+            Some(fold) => button(match fold {
+                FoldMarker::Open => crate::opened_chevron(icons),
+                FoldMarker::Folded => crate::closed_chevron(icons),
+            })
+            .on_press(Message::ToggleFold(index))
+            .padding(0)
+            .style(button::text)
+            .into(),
+            None => Space::new().into(),
+        };
+
+        // Pressing a number selects its line, dragging across numbers extends the selection.
+        let selectable = mouse_area(row![marker, number].spacing(6).height(Length::Fill))
+            .on_press(Message::GutterPressed(index))
+            .on_enter(Message::GutterHovered(index))
+            .on_release(Message::GutterReleased);
+
+        // Wrapped lines only get their number on the first row.
+        container(
+            row![
+                selectable,
+                container(chevron)
+                    .width(Length::Fixed(FOLD_COLUMN_WIDTH))
+                    .align_x(Alignment::Center)
+            ]
+            .spacing(4)
+            .height(Length::Fixed(layout.row_height()))
+            .align_y(Alignment::Center),
+        )
+        .height(Length::Fixed(layout.line_height(index)))
+        .style(move |_theme| container::Style {
+            background: is_current.then(|| tokens.current_line.into()),
+            ..container::Style::default()
+        })
+        .into()
+    });
+
+    let rows = pin(column(rows).width(Length::Shrink).padding([0, 4]))
+        .y(top)
+        .width(Length::Shrink)
+        .height(Length::Fill);
+
+    mouse_area(container(rows).height(Length::Fill).clip(true))
+        .on_scroll(Message::GutterScrolled)
+        .into()
+}
@@ -0,0 +1,207 @@
+use crate::language::Language;
+use crate::layout;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indentation {
+    Tabs,
+    Spaces(usize),
+}
+
+pub const OPTIONS: &[Indentation] = &[
+    Indentation::Spaces(2),
+    Indentation::Spaces(4),
+    Indentation::Spaces(8),
+    Indentation::Tabs,
+];
+
+// Lines looked at when guessing the indentation of a buffer.
+const DETECT_LINES: usize = 1000;
+
+impl Indentation {
+    // Columns per indentation level. Tabs follow the editor's tab stops.
+    pub fn width(self) -> usize {
+        match self {
+            Indentation::Tabs => layout::TAB_WIDTH,
+            Indentation::Spaces(width) => width,
+        }
+    }
+
+    pub fn unit(self) -> String {
+        match self {
+            Indentation::Tabs => String::from("\t"),
+            Indentation::Spaces(width) => " ".repeat(width),
+        }
+    }
+
+    // What Tab inserts at `column` when nothing spanning lines is selected.
+    pub fn insertion(self, column: usize) -> String {
+        match self {
+            Indentation::Tabs => String::from("\t"),
+            Indentation::Spaces(width) => " ".repeat(width - column % width),
+        }
+    }
+}
+
+impl fmt::Display for Indentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Indentation::Tabs => f.write_str("Tabs"),
+            Indentation::Spaces(width) => write!(f, "Spaces: {width}"),
+        }
+    }
+}
+
+// Used when the buffer has no indented lines to learn from.
+pub fn default_for(language: &Language) -> Indentation {
+    match language.name {
+        "Makefile" | "Go" => Indentation::Tabs,
+        "JSON" | "YAML" | "Nix" | "HTML" | "XML" | "JavaScript" | "TypeScript" | "TSX" | "CSS"
+        | "SCSS" | "Lua" => Indentation::Spaces(2),
+        _ => Indentation::Spaces(4),
+    }
+}
+
+// Guesses tabs versus spaces from the majority of indented lines, and the width of
+// space indentation from the most common step between consecutive lines.
+pub fn detect(text: &str) -> Option<Indentation> {
+    let mut tabs = 0;
+    let mut spaces = 0;
+    let mut steps = [0usize; 9];
+    let mut previous = 0;
+
+    for line in text.lines().take(DETECT_LINES) {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let leading = leading(line);
+
+        if leading.starts_with('\t') {
+            tabs += 1;
+            continue;
+        }
+
+        let width = leading.len();
+
+        // Continuation lines of block comments are indented by one extra space.
+        if line[width..].starts_with('*') {
+            continue;
+        }
+
+        if width > 0 {
+            spaces += 1;
+        }
+
+        if let Some(step) = width.checked_sub(previous)
+            && (2..=8).contains(&step)
+        {
+            steps[step] += 1;
+        }
+
+        previous = width;
+    }
+
+    if tabs == 0 && spaces == 0 {
+        return None;
+    }
+
+    if tabs > spaces {
+        return Some(Indentation::Tabs);
+    }
+
+    [2, 4, 8]
+        .into_iter()
+        .filter(|&width| steps[width] > 0)
+        .max_by_key(|&width| (steps[width], std::cmp::Reverse(width)))
+        .map(Indentation::Spaces)
+}
+
+pub fn leading(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+pub fn indent_line(line: &str, indentation: Indentation) -> String {
+    if line.trim().is_empty() {
+        return line.to_owned();
+    }
+
+    indentation.unit() + line
+}
+
+// Removes one level, or whatever is left of it.
+pub fn outdent_line(line: &str, indentation: Indentation) -> String {
+    let mut column = 0;
+
+    let removed = line
+        .char_indices()
+        .take_while(|&(_, c)| {
+            let fits = column < indentation.width() && (c == ' ' || c == '\t');
+            column = layout::advance(column, c);
+            fits
+        })
+        .last()
+        .map(|(index, c)| index + c.len_utf8())
+        .unwrap_or_default();
+
+    line[removed..].to_owned()
+}
+
+// Rewrites the leading whitespace of `line` from one style to another, keeping the level.
+pub fn convert_line(line: &str, from: Indentation, to: Indentation) -> String {
+    let leading = leading(line);
+    let columns = layout::columns(leading);
+    let levels = columns / from.width();
+    let rest = columns % from.width();
+
+    let whitespace = match to {
+        Indentation::Tabs => to.unit().repeat(levels) + &" ".repeat(rest),
+        Indentation::Spaces(width) => " ".repeat(levels * width + rest),
+    };
+
+    whitespace + &line[leading.len()..]
+}
+
+// Whether a new line after `before` (the text left of the cursor) should be indented
+// one level deeper than it.
+pub fn opens_block(before: &str, language: &Language) -> bool {
+    let Some(last) = before.trim_end().chars().last() else {
+        return false;
+    };
+
+    language
+        .block_delimiters
+        .iter()
+        .any(|&(open, _)| open == last)
+        || (last == ':' && matches!(language.name, "Python" | "YAML"))
+}
+
+// The line holding the bracket that `close` typed at `line` would close.
+pub fn opening_line(
+    lines: &[String],
+    line: usize,
+    close: char,
+    language: &Language,
+) -> Option<usize> {
+    let &(open, _) = language
+        .block_delimiters
+        .iter()
+        .find(|&&(_, delimiter)| delimiter == close)?;
+
+    let mut depth = 0;
+
+    for index in (0..line).rev() {
+        for c in lines[index].chars().rev() {
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                if depth == 0 {
+                    return Some(index);
+                }
+                depth -= 1;
+            }
+        }
+    }
+
+    None
+}
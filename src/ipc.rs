@@ -0,0 +1,423 @@
+// Lets a second `xeditor` hand its command line to the one already running, over a Unix
+// socket. A client sends one JSON object per line and ends with `end`:
+//
+//   {"request": "open", "path": "/src/main.rs", "line": 12, "column": 4, "wait": true}
+//   {"request": "open_folder", "path": "/src"}
+//   {"request": "open_text", "text": "read from standard input"}
+//   {"request": "diff", "paths": ["/src/old.rs", "/src/new.rs"]}
+//   {"request": "focus"}
+//   {"request": "end"}
+//
+// Lines and columns count from one, `readonly` and `wait` default to false. The editor
+// answers `{"reply": "opened"}` once it handled the requests and, if any of them waits,
+// `{"reply": "closed"}` once all the buffers they opened are closed again.
+use crate::cli::Request;
+use crate::cli::Target;
+use crate::xdg;
+use iced::futures::SinkExt;
+use iced::futures::Stream;
+use iced::widget::text_editor::Position;
+use serde_json::Value;
+use serde_json::json;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixListener;
+use tokio::net::unix::OwnedWriteHalf;
+
+const FILE_NAME: &str = "xeditor.sock";
+
+// Set to use another socket, like the tests do.
+const SOCKET_VARIABLE: &str = "XEDITOR_SOCKET";
+
+// How long to wait before accepting again after it failed, like when the editor ran out of
+// file descriptors and would fail again right away.
+const ACCEPT_RETRY: Duration = Duration::from_millis(100);
+
+pub fn socket_path() -> Option<PathBuf> {
+    std::env::var_os(SOCKET_VARIABLE)
+        .map(PathBuf::from)
+        .or_else(|| xdg::runtime_directory().map(|directory| directory.join(FILE_NAME)))
+}
+
+// The socket of the running editor, `None` when there is none.
+pub fn connect(path: &Path) -> Option<net::UnixStream> {
+    net::UnixStream::connect(path).ok()
+}
+
+// Hands `requests` to the running editor. Returns once they are handled or, when one of
+// them waits, once the buffers they opened are closed. The editor exiting closes them too.
+pub fn send(stream: net::UnixStream, requests: &[Request]) -> io::Result<()> {
+    let mut writer = &stream;
+    for request in requests {
+        writeln!(writer, "{}", to_json(request))?;
+    }
+    writeln!(writer, "{}", json!({ "request": "end" }))?;
+
+    let wait = requests.iter().any(Request::waits);
+    read_replies(&mut BufReader::new(&stream), wait)
+}
+
+// Reads replies up to `opened`, or up to `closed` when waiting.
+fn read_replies(reader: &mut impl BufRead, wait: bool) -> io::Result<()> {
+    let mut opened = false;
+
+    for line in reader.lines() {
+        let reply: Value = serde_json::from_str(&line?).unwrap_or_default();
+
+        match reply["reply"].as_str() {
+            Some("opened") if !wait => return Ok(()),
+            Some("opened") => opened = true,
+            Some("closed") => return Ok(()),
+            _ => {}
+        }
+    }
+
+    if opened {
+        Ok(())
+    } else {
+        Err(io::ErrorKind::UnexpectedEof.into())
+    }
+}
+
+// Claims the socket for this editor. A socket nobody answers on is left over from an
+// editor that crashed and is replaced.
+pub fn bind(path: &Path) -> Option<Server> {
+    if connect(path).is_some() {
+        return None;
+    }
+
+    let _ = std::fs::remove_file(path);
+    if let Some(directory) = path.parent() {
+        let _ = std::fs::create_dir_all(directory);
+    }
+
+    let listener = net::UnixListener::bind(path).ok()?;
+    listener.set_nonblocking(true).ok()?;
+
+    Some(Server {
+        path: path.to_owned(),
+        listener: Arc::new(Mutex::new(Some(listener))),
+    })
+}
+
+// The socket this editor listens on, handed to the subscription serving it.
+#[derive(Debug, Clone)]
+pub struct Server {
+    path: PathBuf,
+    // Taken by the subscription once it runs.
+    listener: Arc<Mutex<Option<net::UnixListener>>>,
+}
+
+impl Hash for Server {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+    }
+}
+
+// The other end of a connection, answered once its requests are handled.
+#[derive(Clone)]
+pub struct Client {
+    id: u64,
+    writer: Arc<tokio::sync::Mutex<OwnedWriteHalf>>,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client").field("id", &self.id).finish()
+    }
+}
+
+impl PartialEq for Client {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reply {
+    Opened,
+    Closed,
+}
+
+impl Client {
+    // A client that went away needs no answer, so failures are ignored.
+    pub async fn reply(self, reply: Reply) {
+        let reply = match reply {
+            Reply::Opened => "opened",
+            Reply::Closed => "closed",
+        };
+        let line = format!("{}\n", json!({ "reply": reply }));
+
+        let mut writer = self.writer.lock().await;
+        let _ = writer.write_all(line.as_bytes()).await;
+    }
+}
+
+// The requests of every client connecting to `server`.
+pub fn serve(server: &Server) -> impl Stream<Item = (Client, Vec<Request>)> + use<> {
+    let listener = server
+        .listener
+        .lock()
+        .ok()
+        .and_then(|mut listener| listener.take());
+
+    iced::stream::channel(16, async move |output| {
+        let Some(listener) = listener.and_then(|listener| UnixListener::from_std(listener).ok())
+        else {
+            return;
+        };
+
+        let mut next_id = 0;
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(_) => {
+                    tokio::time::sleep(ACCEPT_RETRY).await;
+                    continue;
+                }
+            };
+            let (reader, writer) = stream.into_split();
+            let client = Client {
+                id: next_id,
+                writer: Arc::new(tokio::sync::Mutex::new(writer)),
+            };
+            next_id += 1;
+
+            let mut output = output.clone();
+            tokio::spawn(async move {
+                let mut lines = tokio::io::BufReader::new(reader).lines();
+                let mut requests = Vec::new();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let Ok(json) = serde_json::from_str::<Value>(&line) else {
+                        continue;
+                    };
+                    if json["request"] == "end" {
+                        let _ = output.send((client, requests)).await;
+                        return;
+                    }
+                    requests.extend(parse(&json));
+                }
+            });
+        }
+    })
+}
+
+fn to_json(request: &Request) -> Value {
+    match request {
+        Request::Open {
+            target,
+            readonly,
+            wait,
+        } => json!({
+            "request": "open",
+            "path": target.path.to_string_lossy(),
+            "line": target.position.map(|position| position.line + 1),
+            "column": target.position.map(|position| position.column + 1),
+            "readonly": readonly,
+            "wait": wait,
+        }),
+        Request::OpenFolder(path) => json!({
+            "request": "open_folder",
+            "path": path.to_string_lossy(),
+        }),
+        Request::OpenText {
+            text,
+            readonly,
+            wait,
+        } => json!({
+            "request": "open_text",
+            "text": text,
+            "readonly": readonly,
+            "wait": wait,
+        }),
+        Request::Diff {
+            paths: (a, b),
+            readonly,
+            wait,
+        } => json!({
+            "request": "diff",
+            "paths": [a.to_string_lossy(), b.to_string_lossy()],
+            "readonly": readonly,
+            "wait": wait,
+        }),
+        Request::Focus => json!({ "request": "focus" }),
+    }
+}
+
+// Unknown requests are skipped, a newer client may know more of them.
+fn parse(json: &Value) -> Option<Request> {
+    let path = |value: &Value| value.as_str().map(PathBuf::from);
+    let readonly = json["readonly"].as_bool().unwrap_or(false);
+    let wait = json["wait"].as_bool().unwrap_or(false);
+
+    let request = match json["request"].as_str()? {
+        "open" => {
+            let number = |key: &str| {
+                json[key]
+                    .as_u64()
+                    .map(|number| (number as usize).saturating_sub(1))
+            };
+            let position = number("line").map(|line| Position {
+                line,
+                column: number("column").unwrap_or(0),
+            });
+
+            Request::Open {
+                target: Target {
+                    path: path(&json["path"])?,
+                    position,
+                },
+                readonly,
+                wait,
+            }
+        }
+        "open_folder" => Request::OpenFolder(path(&json["path"])?),
+        "open_text" => Request::OpenText {
+            text: json["text"].as_str()?.to_owned(),
+            readonly,
+            wait,
+        },
+        "diff" => Request::Diff {
+            paths: (path(&json["paths"][0])?, path(&json["paths"][1])?),
+            readonly,
+            wait,
+        },
+        "focus" => Request::Focus,
+        _ => return None,
+    };
+
+    Some(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::futures::StreamExt;
+
+    #[test]
+    fn requests_survive_the_round_trip() {
+        let requests = [
+            Request::Open {
+                target: Target {
+                    path: PathBuf::from("/src/main.rs"),
+                    position: Some(Position {
+                        line: 11,
+                        column: 3,
+                    }),
+                },
+                readonly: true,
+                wait: true,
+            },
+            Request::Open {
+                target: Target {
+                    path: PathBuf::from("/src/lib.rs"),
+                    position: None,
+                },
+                readonly: false,
+                wait: false,
+            },
+            Request::OpenFolder(PathBuf::from("/src")),
+            Request::OpenText {
+                text: String::from("piped\ntext\n"),
+                readonly: false,
+                wait: true,
+            },
+            Request::Diff {
+                paths: (PathBuf::from("/src/old.rs"), PathBuf::from("/src/new.rs")),
+                readonly: true,
+                wait: false,
+            },
+            Request::Focus,
+        ];
+
+        for request in requests {
+            assert_eq!(parse(&to_json(&request)), Some(request));
+        }
+
+        assert_eq!(parse(&json!({ "request": "unknown" })), None);
+        assert_eq!(parse(&json!({ "request": "open" })), None);
+    }
+
+    #[test]
+    fn waits_for_closed_only_when_asked_to() {
+        let replies = "{\"reply\":\"opened\"}\n{\"reply\":\"closed\"}\nrest\n";
+
+        let mut reader = replies.as_bytes();
+        read_replies(&mut reader, false).unwrap();
+        assert_eq!(reader, "{\"reply\":\"closed\"}\nrest\n".as_bytes());
+
+        let mut reader = replies.as_bytes();
+        read_replies(&mut reader, true).unwrap();
+        assert_eq!(reader, "rest\n".as_bytes());
+    }
+
+    #[test]
+    fn fails_without_a_reply() {
+        let error = read_replies(&mut "".as_bytes(), true).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        // Opened files stay open when the editor goes away, the wait is over.
+        read_replies(&mut "{\"reply\":\"opened\"}\n".as_bytes(), true).unwrap();
+    }
+
+    // Serves a second `xeditor`, the one `cargo test` builds for the integration tests
+    // next to this test binary.
+    #[tokio::test]
+    async fn serves_a_second_editor_until_its_files_are_closed() {
+        let directory = std::env::temp_dir().join(format!("xeditor-serve-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let socket = directory.join(FILE_NAME);
+        let server = bind(&socket).unwrap();
+        let mut clients = Box::pin(serve(&server));
+
+        let xeditor = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .and_then(Path::parent)
+            .unwrap()
+            .join("xeditor");
+        let mut process = tokio::process::Command::new(xeditor)
+            .args(["--wait", "a.txt:3"])
+            .current_dir(&directory)
+            .env(SOCKET_VARIABLE, &socket)
+            .spawn()
+            .unwrap();
+
+        let (client, requests) = clients.next().await.unwrap();
+        assert_eq!(
+            requests.first(),
+            Some(&Request::Open {
+                target: Target {
+                    path: directory.join("a.txt"),
+                    position: Some(Position { line: 2, column: 0 }),
+                },
+                readonly: false,
+                wait: true,
+            })
+        );
+
+        client.clone().reply(Reply::Opened).await;
+        assert!(process.try_wait().unwrap().is_none());
+
+        client.reply(Reply::Closed).await;
+        assert!(process.wait().await.unwrap().success());
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,640 @@
+use std::fmt;
+use std::path::Path;
+
+// How many lines at the top and bottom of a buffer are scanned for modelines.
+const MODELINE_SCAN_LINES: usize = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Language {
+    pub name: &'static str,
+    // Token handed to the highlighter, it must be an extension or name known to syntect.
+    pub token: &'static str,
+    pub file_names: &'static [&'static str],
+    pub extensions: &'static [&'static str],
+    pub globs: &'static [&'static str],
+    pub interpreters: &'static [&'static str],
+    // Names used by vim `ft=` and emacs `mode:` modelines.
+    pub aliases: &'static [&'static str],
+    // Delimiters used for syntax-aware folding, languages without any fold by indentation.
+    pub block_delimiters: &'static [(char, char)],
+    // Pairs closed automatically when the opening side is typed. Pairs with distinct
+    // sides are also highlighted and jumped between as brackets.
+    pub pairs: &'static [(char, char)],
+    pub line_comment: Option<&'static str>,
+    pub block_comment: Option<(&'static str, &'static str)>,
+}
+
+impl PartialEq for Language {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+const fn language(name: &'static str, token: &'static str) -> Language {
+    Language {
+        name,
+        token,
+        file_names: &[],
+        extensions: &[],
+        globs: &[],
+        interpreters: &[],
+        aliases: &[],
+        block_delimiters: &[],
+        pairs: PAIRS,
+        line_comment: None,
+        block_comment: None,
+    }
+}
+
+const C_BLOCK_COMMENT: Option<(&str, &str)> = Some(("/*", "*/"));
+const MARKUP_COMMENT: Option<(&str, &str)> = Some(("<!--", "-->"));
+
+const BRACKETS: &[(char, char)] = &[('{', '}'), ('[', ']'), ('(', ')')];
+const PAIRS: &[(char, char)] = &[('{', '}'), ('[', ']'), ('(', ')'), ('"', '"'), ('\'', '\'')];
+// Single quotes are lifetimes, primes or apostrophes in these.
+const PAIRS_WITHOUT_SINGLE_QUOTE: &[(char, char)] =
+    &[('{', '}'), ('[', ']'), ('(', ')'), ('"', '"')];
+const PAIRS_WITH_BACKTICK: &[(char, char)] = &[
+    ('{', '}'),
+    ('[', ']'),
+    ('(', ')'),
+    ('"', '"'),
+    ('\'', '\''),
+    ('`', '`'),
+];
+const MARKUP_PAIRS: &[(char, char)] = &[
+    ('<', '>'),
+    ('{', '}'),
+    ('[', ']'),
+    ('(', ')'),
+    ('"', '"'),
+    ('\'', '\''),
+];
+
+pub const PLAIN_TEXT: Language = Language {
+    extensions: &["txt", "text"],
+    aliases: &["text", "plain", "fundamental"],
+    pairs: PAIRS_WITHOUT_SINGLE_QUOTE,
+    ..language("Plain Text", "txt")
+};
+
+pub static LANGUAGES: &[Language] = &[
+    PLAIN_TEXT,
+    Language {
+        extensions: &["rs"],
+        interpreters: &["rust-script"],
+        aliases: &["rust"],
+        block_delimiters: BRACKETS,
+        pairs: PAIRS_WITHOUT_SINGLE_QUOTE,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("Rust", "rs")
+    },
+    Language {
+        file_names: &["Cargo.lock", "Pipfile", "uv.lock", "poetry.lock"],
+        extensions: &["toml"],
+        aliases: &["toml", "conf-toml"],
+        line_comment: Some("#"),
+        ..language("TOML", "toml")
+    },
+    Language {
+        file_names: &["flake.lock", "composer.lock", ".prettierrc", ".eslintrc"],
+        extensions: &["json", "jsonc", "json5", "ipynb"],
+        aliases: &["json", "js-json"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("JSON", "json")
+    },
+    Language {
+        extensions: &["nix"],
+        aliases: &["nix"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("#"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("Nix", "nix")
+    },
+    Language {
+        file_names: &[
+            ".bashrc",
+            ".bash_profile",
+            ".bash_login",
+            ".bash_logout",
+            ".bash_aliases",
+            ".profile",
+            ".zshrc",
+            ".zshenv",
+            ".zprofile",
+            ".zlogin",
+            ".zlogout",
+            ".envrc",
+            "PKGBUILD",
+            "APKBUILD",
+        ],
+        extensions: &["sh", "bash", "zsh", "ksh", "ebuild", "eclass"],
+        globs: &["*.bashrc", "*.zshrc", ".bashrc.*", ".zshrc.*"],
+        interpreters: &["sh", "bash", "zsh", "ksh", "dash", "ash"],
+        aliases: &["sh", "bash", "zsh", "shell", "shell-script"],
+        pairs: PAIRS_WITH_BACKTICK,
+        line_comment: Some("#"),
+        ..language("Shell", "sh")
+    },
+    Language {
+        extensions: &["fish"],
+        interpreters: &["fish"],
+        aliases: &["fish"],
+        line_comment: Some("#"),
+        ..language("Fish", "fish")
+    },
+    Language {
+        file_names: &[".env"],
+        globs: &[".env.*"],
+        aliases: &["dotenv", "env"],
+        line_comment: Some("#"),
+        ..language("DotENV", ".env")
+    },
+    Language {
+        file_names: &["Makefile", "makefile", "GNUmakefile"],
+        extensions: &["mk", "mak", "make"],
+        globs: &["Makefile.*", "*.mk.in"],
+        interpreters: &["make"],
+        aliases: &["make", "makefile", "makefile-gmake"],
+        line_comment: Some("#"),
+        ..language("Makefile", "make")
+    },
+    Language {
+        file_names: &["Dockerfile", "Containerfile"],
+        extensions: &["dockerfile"],
+        globs: &["Dockerfile.*", "*.Dockerfile", "Containerfile.*"],
+        aliases: &["dockerfile", "docker"],
+        line_comment: Some("#"),
+        ..language("Dockerfile", "Dockerfile")
+    },
+    Language {
+        file_names: &["CMakeLists.txt"],
+        extensions: &["cmake"],
+        aliases: &["cmake"],
+        line_comment: Some("#"),
+        ..language("CMake", "cmake")
+    },
+    Language {
+        file_names: &["README", "CHANGELOG"],
+        extensions: &["md", "markdown", "mdown", "mkd"],
+        aliases: &["markdown", "md", "gfm"],
+        pairs: &[('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('`', '`')],
+        block_comment: MARKUP_COMMENT,
+        ..language("Markdown", "md")
+    },
+    Language {
+        extensions: &["yaml", "yml"],
+        globs: &[".clang-format", ".clang-tidy"],
+        aliases: &["yaml"],
+        line_comment: Some("#"),
+        ..language("YAML", "yaml")
+    },
+    Language {
+        file_names: &[".editorconfig", ".gitconfig", ".hgrc"],
+        extensions: &[
+            "ini", "cfg", "conf", "desktop", "service", "timer", "socket",
+        ],
+        aliases: &["ini", "dosini", "conf", "systemd"],
+        line_comment: Some(";"),
+        ..language("INI", "ini")
+    },
+    Language {
+        file_names: &[".gitignore", ".dockerignore", ".ignore"],
+        globs: &["*.gitignore"],
+        aliases: &["gitignore"],
+        line_comment: Some("#"),
+        ..language("Git Ignore", "gitignore")
+    },
+    Language {
+        file_names: &["COMMIT_EDITMSG", "MERGE_MSG", "TAG_EDITMSG"],
+        aliases: &["gitcommit"],
+        pairs: PAIRS_WITHOUT_SINGLE_QUOTE,
+        line_comment: Some("#"),
+        ..language("Git Commit", "COMMIT_EDITMSG")
+    },
+    Language {
+        file_names: &["git-rebase-todo"],
+        aliases: &["gitrebase"],
+        line_comment: Some("#"),
+        ..language("Git Rebase", "git-rebase-todo")
+    },
+    Language {
+        extensions: &["diff", "patch", "rej"],
+        aliases: &["diff", "patch"],
+        ..language("Diff", "diff")
+    },
+    Language {
+        extensions: &["c", "h"],
+        aliases: &["c"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("C", "c")
+    },
+    Language {
+        extensions: &["cpp", "cc", "cxx", "c++", "hpp", "hh", "hxx", "ipp", "inl"],
+        aliases: &["cpp", "c++"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("C++", "cpp")
+    },
+    Language {
+        extensions: &["go"],
+        aliases: &["go"],
+        block_delimiters: BRACKETS,
+        pairs: PAIRS_WITH_BACKTICK,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("Go", "go")
+    },
+    Language {
+        file_names: &[
+            "SConstruct",
+            "SConscript",
+            "Snakefile",
+            "BUILD.bazel",
+            "WORKSPACE",
+        ],
+        extensions: &["py", "pyi", "pyw", "bzl"],
+        interpreters: &["python", "python2", "python3", "pypy", "pypy3"],
+        aliases: &["python", "py"],
+        line_comment: Some("#"),
+        ..language("Python", "py")
+    },
+    Language {
+        file_names: &["Gemfile", "Rakefile", "Vagrantfile", "Brewfile"],
+        extensions: &["rb", "rake", "gemspec"],
+        interpreters: &["ruby"],
+        aliases: &["ruby", "rb"],
+        line_comment: Some("#"),
+        ..language("Ruby", "rb")
+    },
+    Language {
+        extensions: &["pl", "pm", "t"],
+        interpreters: &["perl"],
+        aliases: &["perl", "cperl"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("#"),
+        ..language("Perl", "pl")
+    },
+    Language {
+        extensions: &["lua"],
+        interpreters: &["lua", "luajit"],
+        aliases: &["lua"],
+        line_comment: Some("--"),
+        block_comment: Some(("--[[", "]]")),
+        ..language("Lua", "lua")
+    },
+    Language {
+        extensions: &["js", "mjs", "cjs"],
+        interpreters: &["node", "nodejs", "deno", "bun"],
+        aliases: &["javascript", "js"],
+        block_delimiters: BRACKETS,
+        pairs: PAIRS_WITH_BACKTICK,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("JavaScript", "js")
+    },
+    Language {
+        extensions: &["ts", "mts", "cts"],
+        interpreters: &["ts-node"],
+        aliases: &["typescript", "ts"],
+        block_delimiters: BRACKETS,
+        pairs: PAIRS_WITH_BACKTICK,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("TypeScript", "ts")
+    },
+    Language {
+        extensions: &["tsx"],
+        aliases: &["typescriptreact", "tsx"],
+        block_delimiters: BRACKETS,
+        pairs: PAIRS_WITH_BACKTICK,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("TSX", "tsx")
+    },
+    Language {
+        extensions: &["html", "htm", "xhtml"],
+        aliases: &["html", "mhtml"],
+        pairs: MARKUP_PAIRS,
+        block_comment: MARKUP_COMMENT,
+        ..language("HTML", "html")
+    },
+    Language {
+        extensions: &["css"],
+        aliases: &["css"],
+        block_delimiters: BRACKETS,
+        block_comment: C_BLOCK_COMMENT,
+        ..language("CSS", "css")
+    },
+    Language {
+        extensions: &["scss"],
+        aliases: &["scss"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("SCSS", "scss")
+    },
+    Language {
+        extensions: &["xml", "xsd", "xsl", "xslt", "svg", "plist"],
+        aliases: &["xml", "nxml"],
+        pairs: MARKUP_PAIRS,
+        block_comment: MARKUP_COMMENT,
+        ..language("XML", "xml")
+    },
+    Language {
+        extensions: &["java"],
+        aliases: &["java"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("Java", "java")
+    },
+    Language {
+        extensions: &["kt", "kts"],
+        aliases: &["kotlin"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("Kotlin", "kt")
+    },
+    Language {
+        extensions: &["swift"],
+        aliases: &["swift"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("Swift", "swift")
+    },
+    Language {
+        extensions: &["zig", "zon"],
+        aliases: &["zig"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("//"),
+        ..language("Zig", "zig")
+    },
+    Language {
+        extensions: &["hs"],
+        interpreters: &["runghc", "runhaskell"],
+        aliases: &["haskell"],
+        pairs: PAIRS_WITHOUT_SINGLE_QUOTE,
+        line_comment: Some("--"),
+        block_comment: Some(("{-", "-}")),
+        ..language("Haskell", "hs")
+    },
+    Language {
+        extensions: &["sql"],
+        aliases: &["sql"],
+        line_comment: Some("--"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("SQL", "sql")
+    },
+    Language {
+        file_names: &[".vimrc", ".gvimrc", "_vimrc"],
+        extensions: &["vim"],
+        aliases: &["vim"],
+        pairs: &[('{', '}'), ('[', ']'), ('(', ')'), ('\'', '\'')],
+        line_comment: Some("\""),
+        ..language("Vim Script", "vim")
+    },
+    Language {
+        extensions: &["tf", "tfvars", "hcl"],
+        aliases: &["terraform", "hcl"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("#"),
+        block_comment: C_BLOCK_COMMENT,
+        ..language("Terraform", "tf")
+    },
+    Language {
+        extensions: &["awk"],
+        interpreters: &["awk", "gawk", "mawk"],
+        aliases: &["awk"],
+        block_delimiters: BRACKETS,
+        line_comment: Some("#"),
+        ..language("AWK", "awk")
+    },
+    Language {
+        extensions: &["csv", "tsv"],
+        aliases: &["csv"],
+        ..language("CSV", "csv")
+    },
+];
+
+pub fn detect(path: Option<&Path>, text: &str) -> Language {
+    if let Some(language) = from_modeline(text) {
+        return language;
+    }
+
+    let from_path = path.and_then(|path| {
+        let file_name = path.file_name()?.to_str()?;
+
+        by_file_name(file_name)
+            .or_else(|| by_extension(file_name))
+            .or_else(|| by_glob(file_name))
+    });
+
+    from_path
+        .or_else(|| from_shebang(text))
+        .unwrap_or(PLAIN_TEXT)
+}
+
+pub fn by_name(name: &str) -> Option<Language> {
+    LANGUAGES
+        .iter()
+        .find(|language| {
+            language.name.eq_ignore_ascii_case(name)
+                || language
+                    .aliases
+                    .iter()
+                    .any(|alias| alias.eq_ignore_ascii_case(name))
+        })
+        .copied()
+}
+
+fn by_file_name(file_name: &str) -> Option<Language> {
+    LANGUAGES
+        .iter()
+        .find(|language| language.file_names.contains(&file_name))
+        .copied()
+}
+
+// Tries the longest compound extension first, so `foo.tar.gz` checks `tar.gz` before `gz`.
+fn by_extension(file_name: &str) -> Option<Language> {
+    let file_name = file_name.strip_prefix('.').unwrap_or(file_name);
+
+    file_name.match_indices('.').find_map(|(index, _)| {
+        let extension = &file_name[index + 1..];
+
+        LANGUAGES
+            .iter()
+            .find(|language| {
+                language
+                    .extensions
+                    .iter()
+                    .any(|candidate| candidate.eq_ignore_ascii_case(extension))
+            })
+            .copied()
+    })
+}
+
+fn by_glob(file_name: &str) -> Option<Language> {
+    LANGUAGES
+        .iter()
+        .find(|language| {
+            language
+                .globs
+                .iter()
+                .any(|pattern| glob_matches(pattern, file_name))
+        })
+        .copied()
+}
+
+fn from_shebang(text: &str) -> Option<Language> {
+    let first_line = text.lines().next()?;
+    let command = first_line.strip_prefix("#!")?;
+    let mut words = command.split_whitespace();
+    let program = words.next()?;
+    let mut interpreter = program.rsplit('/').next()?;
+
+    // `#!/usr/bin/env -S python3 -u` names the interpreter in a later argument.
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-'))?;
+    }
+
+    // Accept versioned binaries such as `python3.12` or `lua5.4`.
+    let trimmed = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    LANGUAGES
+        .iter()
+        .find(|language| {
+            language.interpreters.contains(&interpreter) || language.interpreters.contains(&trimmed)
+        })
+        .copied()
+}
+
+fn from_modeline(text: &str) -> Option<Language> {
+    let lines: Vec<&str> = text.lines().collect();
+    let head = lines.iter().take(MODELINE_SCAN_LINES);
+    let tail = lines
+        .iter()
+        .skip(MODELINE_SCAN_LINES)
+        .rev()
+        .take(MODELINE_SCAN_LINES);
+
+    head.chain(tail)
+        .find_map(|line| emacs_mode(line).or_else(|| vim_filetype(line)))
+        .and_then(by_name)
+}
+
+// Handles `-*- rust -*-` and `-*- mode: rust; coding: utf-8 -*-`.
+fn emacs_mode(line: &str) -> Option<&str> {
+    let start = line.find("-*-")? + 3;
+    let end = start + line[start..].find("-*-")?;
+    let inner = line[start..end].trim();
+
+    if !inner.contains(':') {
+        return Some(inner);
+    }
+
+    inner.split(';').find_map(|variable| {
+        let (key, value) = variable.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case("mode")
+            .then(|| value.trim())
+    })
+}
+
+// Handles `vim: ft=rust`, `vim: set filetype=rust :` and the `vi:`/`ex:` spellings.
+fn vim_filetype(line: &str) -> Option<&str> {
+    let start = ["vim:", "vi:", "ex:"]
+        .iter()
+        .filter_map(|marker| {
+            line.find(marker)
+                .filter(|&index| index == 0 || line[..index].ends_with(char::is_whitespace))
+                .map(|index| index + marker.len())
+        })
+        .min()?;
+
+    line[start..]
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .find_map(|option| {
+            option
+                .strip_prefix("ft=")
+                .or_else(|| option.strip_prefix("filetype="))
+                .or_else(|| option.strip_prefix("syntax="))
+                .or_else(|| option.strip_prefix("syn="))
+        })
+        .filter(|name| !name.is_empty())
+}
+
+// Minimal glob support: `*` matches any run of characters and `?` matches one.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The syntaxes iced highlights with, anything else is shown as plain text.
+    #[test]
+    fn every_token_has_a_syntax() {
+        let syntaxes = two_face::syntax::extra_no_newlines();
+
+        for language in LANGUAGES {
+            assert!(
+                syntaxes.find_syntax_by_token(language.token).is_some(),
+                "{} has no syntax for {:?}",
+                language.name,
+                language.token
+            );
+        }
+    }
+
+    #[test]
+    fn envrc_is_a_shell_script() {
+        let language = detect(Some(Path::new("/project/.envrc")), "use flake\n");
+        assert_eq!(language.name, "Shell");
+
+        let language = detect(Some(Path::new("/project/.env")), "KEY=value\n");
+        assert_eq!(language.name, "DotENV");
+    }
+}
@@ -0,0 +1,217 @@
+use iced::Rectangle;
+use iced::widget::scrollable::Viewport;
+use iced::widget::text_editor;
+use std::ops::Range;
+
+// The editor is laid out with iced's defaults, everything drawn next to or under it
+// (gutter, rulers, whitespace, minimap) has to match these exactly.
+pub const PADDING: f32 = 5.0;
+// cosmic-text expands tabs to the next multiple of eight columns.
+pub const TAB_WIDTH: usize = 8;
+
+// Fallback used before the editor scrollable reported its first viewport.
+const DEFAULT_VIEWPORT_HEIGHT: f32 = 1080.0;
+
+pub fn columns(text: &str) -> usize {
+    text.chars().fold(0, advance)
+}
+
+pub fn advance(column: usize, c: char) -> usize {
+    if c == '\t' {
+        (column / TAB_WIDTH + 1) * TAB_WIDTH
+    } else {
+        column + 1
+    }
+}
+
+// Char indices where wrapped continuation rows start. Mirrors word-or-glyph wrapping for a
+// monospace font: words move to the next row as a whole unless they are wider than a row.
+pub fn wrap_points(text: &str, width: usize) -> Vec<usize> {
+    let width = width.max(1);
+    let mut points = Vec::new();
+    let mut row_start_column = 0;
+    let mut column = 0;
+    let mut word_start: Option<(usize, usize)> = None;
+
+    for (index, c) in text.chars().enumerate() {
+        if c.is_whitespace() {
+            word_start = None;
+            column = advance(column, c);
+            continue;
+        }
+
+        let (start_index, start_column) = *word_start.get_or_insert((index, column));
+        let next = advance(column, c);
+
+        if next - row_start_column > width {
+            if start_column > row_start_column {
+                // Move the whole word to a new row.
+                points.push(start_index);
+                row_start_column = start_column;
+            }
+
+            if next - row_start_column > width {
+                // The word alone is still too wide, break it at this glyph.
+                points.push(index);
+                row_start_column = column;
+                word_start = Some((index, column));
+            }
+        }
+
+        column = next;
+    }
+
+    points.dedup();
+    points
+}
+
+// Vertical geometry of the display lines, accounting for soft wrapping.
+#[derive(Debug, Clone)]
+pub struct LineLayout {
+    // First row of every line plus the total row count, only present when wrapping.
+    starts: Option<Vec<usize>>,
+    line_count: usize,
+    wrap_columns: Option<usize>,
+    // Width of the longest line, used to size the editor when wrapping is off.
+    max_columns: usize,
+    text_size: f32,
+    // Advance of a glyph of the editor font at the text size.
+    char_width: f32,
+    // Relative to the text size.
+    line_height: f32,
+}
+
+impl LineLayout {
+    pub fn new(
+        content: &text_editor::Content,
+        wrap_columns: Option<usize>,
+        text_size: f32,
+        char_width: f32,
+        line_height: f32,
+    ) -> Self {
+        let line_count = content.line_count();
+        let mut max_columns = 0;
+
+        let starts = match wrap_columns {
+            Some(columns) => {
+                let mut starts = Vec::with_capacity(line_count + 1);
+                let mut row = 0;
+
+                for line in content.lines() {
+                    starts.push(row);
+                    row += wrap_points(&line.text, columns).len() + 1;
+                }
+
+                starts.push(row);
+                Some(starts)
+            }
+            None => {
+                max_columns = content
+                    .lines()
+                    .map(|line| self::columns(&line.text))
+                    .max()
+                    .unwrap_or_default();
+                None
+            }
+        };
+
+        Self {
+            starts,
+            line_count,
+            wrap_columns,
+            max_columns,
+            text_size,
+            char_width,
+            line_height,
+        }
+    }
+
+    pub fn text_size(&self) -> f32 {
+        self.text_size
+    }
+
+    // Height of one display row.
+    pub fn row_height(&self) -> f32 {
+        self.text_size * self.line_height
+    }
+
+    pub fn char_width(&self) -> f32 {
+        self.char_width
+    }
+
+    pub fn wrap_columns(&self) -> Option<usize> {
+        self.wrap_columns
+    }
+
+    pub fn max_columns(&self) -> usize {
+        self.max_columns
+    }
+
+    fn first_row(&self, line: usize) -> usize {
+        match &self.starts {
+            Some(starts) => starts[line.min(starts.len() - 1)],
+            None => line,
+        }
+    }
+
+    pub fn rows(&self, line: usize) -> usize {
+        self.first_row(line + 1) - self.first_row(line)
+    }
+
+    pub fn line_top(&self, line: usize) -> f32 {
+        PADDING + self.first_row(line) as f32 * self.row_height()
+    }
+
+    pub fn line_height(&self, line: usize) -> f32 {
+        self.rows(line) as f32 * self.row_height()
+    }
+
+    // Bounds of the glyph at char `column` of `line`, `text` being the text of that line.
+    pub fn glyph_bounds(&self, line: usize, text: &str, column: usize) -> Rectangle {
+        let wraps = self
+            .wrap_columns
+            .map(|columns| wrap_points(text, columns))
+            .unwrap_or_default();
+        let row = wraps.partition_point(|&point| point <= column);
+        let row_start = row.checked_sub(1).map_or(0, |row| wraps[row]);
+
+        let row_start_column: usize = columns(&text.chars().take(row_start).collect::<String>());
+        let start = columns(&text.chars().take(column).collect::<String>());
+        let end = text
+            .chars()
+            .nth(column)
+            .map_or(start + 1, |c| advance(start, c));
+
+        Rectangle {
+            x: PADDING + (start - row_start_column) as f32 * self.char_width(),
+            y: self.line_top(line) + row as f32 * self.row_height(),
+            width: (end - start) as f32 * self.char_width(),
+            height: self.row_height(),
+        }
+    }
+
+    pub fn line_at(&self, y: f32) -> usize {
+        let row = ((y - PADDING) / self.row_height()).floor().max(0.0) as usize;
+
+        let line = match &self.starts {
+            Some(starts) => starts
+                .partition_point(|&start| start <= row)
+                .saturating_sub(1),
+            None => row,
+        };
+
+        line.min(self.line_count.saturating_sub(1))
+    }
+
+    // Only the lines inside the viewport (plus a little slack) need to be built.
+    pub fn visible_lines(&self, viewport: Option<&Viewport>) -> Range<usize> {
+        let (offset, height) = viewport
+            .map(|viewport| (viewport.absolute_offset().y, viewport.bounds().height))
+            .unwrap_or((0.0, DEFAULT_VIEWPORT_HEIGHT));
+
+        let first = self.line_at(offset);
+        let last = self.line_at(offset + height) + 2;
+
+        first..last.min(self.line_count)
+    }
+}
@@ -0,0 +1,222 @@
+use crate::indent;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Upper,
+    Lower,
+    Title,
+}
+
+pub fn sort(lines: &mut Vec<String>, natural: bool, unique: bool) {
+    if natural {
+        lines.sort_by(|a, b| natural_cmp(a, b));
+    } else {
+        lines.sort();
+    }
+
+    if unique {
+        lines.dedup();
+    }
+}
+
+// Compares runs of digits by their value, so "file2" sorts before "file10".
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x: String = std::iter::from_fn(|| a.next_if(char::is_ascii_digit)).collect();
+                let y: String = std::iter::from_fn(|| b.next_if(char::is_ascii_digit)).collect();
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x
+                    .to_lowercase()
+                    .cmp(y.to_lowercase())
+                    .then_with(|| x.cmp(&y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+// Joins lines with a single space, dropping the indentation of the joined ones.
+pub fn join(lines: &[String]) -> String {
+    let mut joined = lines.first().cloned().unwrap_or_default();
+
+    for line in &lines[1.min(lines.len())..] {
+        let line = line.trim_start();
+        if line.is_empty() {
+            continue;
+        }
+
+        let trimmed = joined.trim_end().len();
+        joined.truncate(trimmed);
+
+        if !joined.is_empty() {
+            joined.push(' ');
+        }
+        joined.push_str(line);
+    }
+
+    joined
+}
+
+// Comments every non-blank line at their shared indentation, or uncomments them when
+// they all are commented already.
+pub fn toggle_line_comment(lines: &[String], token: &str) -> Vec<String> {
+    let content = || lines.iter().filter(|line| !line.trim().is_empty());
+
+    let commented = content().all(|line| line.trim_start().starts_with(token));
+
+    if commented {
+        return lines
+            .iter()
+            .map(|line| {
+                if line.trim().is_empty() {
+                    return line.clone();
+                }
+
+                let leading = indent::leading(line);
+                let rest = &line[leading.len() + token.len()..];
+                let rest = rest.strip_prefix(' ').unwrap_or(rest);
+                format!("{leading}{rest}")
+            })
+            .collect();
+    }
+
+    // The indentation all lines share, character by character. With tabs on some lines and
+    // spaces on others the token goes in front of the whole indentation.
+    let indentation = content()
+        .map(|line| indent::leading(line))
+        .reduce(common_prefix)
+        .unwrap_or_default()
+        .len();
+
+    lines
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                line.clone()
+            } else {
+                format!("{}{token} {}", &line[..indentation], &line[indentation..])
+            }
+        })
+        .collect()
+}
+
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let length = a
+        .chars()
+        .zip(b.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+
+    &a[..length]
+}
+
+// Wraps `text` in the block comment delimiters, or unwraps it when it is wrapped already.
+pub fn toggle_block_comment(text: &str, (open, close): (&str, &str)) -> String {
+    let trimmed = text.trim();
+
+    if let Some(inner) = trimmed
+        .strip_prefix(open)
+        .and_then(|rest| rest.strip_suffix(close))
+    {
+        let start = text.len() - text.trim_start().len();
+        let end = text.trim_end().len();
+        let inner = inner.strip_prefix(' ').unwrap_or(inner);
+        let inner = inner.strip_suffix(' ').unwrap_or(inner);
+
+        return format!("{}{inner}{}", &text[..start], &text[end..]);
+    }
+
+    format!("{open} {text} {close}")
+}
+
+pub fn convert_case(text: &str, case: Case) -> String {
+    match case {
+        Case::Upper => text.to_uppercase(),
+        Case::Lower => text.to_lowercase(),
+        Case::Title => {
+            let mut at_word_start = true;
+
+            text.chars()
+                .flat_map(|c| {
+                    let converted: Vec<char> = if at_word_start {
+                        c.to_uppercase().collect()
+                    } else {
+                        c.to_lowercase().collect()
+                    };
+
+                    at_word_start = !c.is_alphanumeric() && c != '\'';
+                    converted
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| (*line).to_owned()).collect()
+    }
+
+    #[test]
+    fn comments_at_the_shared_indentation() {
+        assert_eq!(
+            toggle_line_comment(&lines(&["    a", "", "        b"]), "//"),
+            lines(&["    // a", "", "    //     b"])
+        );
+    }
+
+    #[test]
+    fn comments_lines_indented_with_tabs_and_spaces_in_front() {
+        assert_eq!(
+            toggle_line_comment(&lines(&["\ta", "    b", "\t  c"]), "#"),
+            lines(&["# \ta", "#     b", "# \t  c"])
+        );
+        assert_eq!(
+            toggle_line_comment(&lines(&["\t a", "\t\tb"]), "#"),
+            lines(&["\t#  a", "\t# \tb"])
+        );
+    }
+
+    #[test]
+    fn comments_lines_indented_with_other_whitespace() {
+        let commented = toggle_line_comment(&lines(&["\u{a0}\u{a0}a", "\u{3000}b", " c"]), "//");
+        assert_eq!(
+            commented,
+            lines(&["// \u{a0}\u{a0}a", "// \u{3000}b", "//  c"])
+        );
+        assert_eq!(
+            toggle_line_comment(&commented, "//"),
+            lines(&["\u{a0}\u{a0}a", "\u{3000}b", " c"])
+        );
+
+        assert_eq!(
+            toggle_line_comment(&lines(&["\u{3000}\u{3000}a", "\u{3000}b"]), "//"),
+            lines(&["\u{3000}// \u{3000}a", "\u{3000}// b"])
+        );
+    }
+}
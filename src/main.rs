@@ -7,26 +7,101 @@ use iced::Length;
 use iced::Length::Fill;
 use iced::Length::FillPortion;
 use iced::Settings;
+use iced::Size;
+use iced::Subscription;
 use iced::border;
+use iced::event;
 use iced::highlighter;
 use iced::keyboard;
+use iced::mouse;
 use iced::task::Task;
-use iced::theme::Base;
+use iced::theme::Mode;
 use iced::theme::Theme;
 use iced::widget::Space;
 use iced::widget::button;
+use iced::widget::canvas;
 use iced::widget::container;
+use iced::widget::operation;
 use iced::widget::pane_grid;
+use iced::widget::pick_list;
+use iced::widget::pin;
+use iced::widget::scrollable;
+use iced::widget::stack;
 use iced::widget::text;
+use iced::widget::text::Wrapping;
 use iced::widget::text_editor;
 use iced::widget::text_editor::Position;
 use iced::widget::{column, row};
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::io::ErrorKind;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
 use tokio::fs;
 
+mod backup;
+mod bracket;
+mod cli;
+mod command;
+mod diff;
+mod editor;
+mod fold;
+mod font;
+mod format;
+mod git;
+mod gutter;
+mod indent;
+mod ipc;
+mod language;
+mod layout;
+mod lines;
+mod menu;
+mod minimap;
+mod notification;
+mod palette;
+mod privileged;
+mod recent;
+mod session;
+mod settings;
+mod sidebar;
+mod theme;
+mod view_options;
+mod welcome;
+mod whitespace;
+mod workspace;
+mod xdg;
+
+use command::Command;
+use editor::Buffer;
+use editor::BufferId;
+use editor::Editor;
+use gutter::LineMarker;
+use gutter::LineNumbers;
+use indent::Indentation;
+use language::Language;
+use layout::LineLayout;
+use lines::Case;
+use minimap::Minimap;
+use notification::Notifications;
+use palette::Palette;
+use session::Session;
+use settings::AutoSave;
+use settings::Scope;
+use sidebar::Panel;
+use sidebar::Side;
+use theme::Appearance;
+use theme::CustomTheme;
+use theme::Tokens;
+use view_options::SoftWrap;
+use view_options::ViewOptions;
+use whitespace::Whitespace;
+use workspace::Workspace;
+
 #[derive(Debug, Clone)]
 pub enum FileNode {
     File {
@@ -42,18 +117,112 @@ pub enum FileNode {
 }
 
 struct Xeditor {
-    content: text_editor::Content,
+    // The editor of the focused pane. The editors of the other panes are parked in
+    // their pane until it gets the focus.
+    editor: Editor,
+    buffers: HashMap<BufferId, Buffer>,
     tree_content: Vec<FileNode>,
     panes: pane_grid::State<PaneKind>,
+    focus: pane_grid::Pane,
     error: Option<Error>,
-    path: Option<PathBuf>,
-    is_dirty: bool,
+    line_numbers: LineNumbers,
+    show_minimap: bool,
+    show_toolbar: bool,
+    // The menu of the menu bar that is open.
+    menu: Option<command::Menu>,
+    // Options changed at runtime, keyed by language name. Languages without an entry
+    // use their defaults.
+    view_options: HashMap<&'static str, ViewOptions>,
+    palette: Option<Palette>,
+    // Set when toggled at runtime, otherwise the setting of the language applies.
+    format_on_save: Option<bool>,
+    notifications: Notifications,
+    settings: settings::Settings,
+    // Modification time of each settings file when it was last loaded.
+    settings_modified: HashMap<Scope, Option<SystemTime>>,
+    workspace: Workspace,
+    // Shown in the focused pane until something is opened.
+    welcome: bool,
+    // Recently opened files and workspaces.
+    recent: recent::Recent,
+    sidebar: sidebar::Layout,
+    search_query: String,
+    // Changed files of the repository of the opened folder.
+    git_status: Vec<git::FileStatus>,
+    // Custom themes from the themes directory.
+    themes: Vec<CustomTheme>,
+    appearance: Appearance,
+    // Picked from the palette for this session, otherwise the settings apply.
+    theme_override: Option<String>,
+    syntax_theme_override: Option<highlighter::Theme>,
+    // The desktop's light or dark preference.
+    system_mode: Mode,
+    // Installed font families, empty until they are loaded.
+    font_families: Vec<font::Family>,
+    // Picked from the palette for this session, otherwise the settings apply.
+    editor_font_override: Option<String>,
+    ligatures: Option<bool>,
+    editor_font: Font,
+    // Advance of the editor font's glyphs relative to the text size, and the font it was
+    // measured for.
+    font_advance: f32,
+    measured_font: Option<Font>,
+    icon_font: Font,
+    zoom: f32,
+    // Name of the session saved on exit, none when started without restoring one.
+    session: Option<String>,
+    window_size: Option<Size>,
+    // Hash of the text of each unsaved buffer when it was last backed up.
+    backed_up: HashMap<BufferId, u64>,
+    // Counts the edits, so saving after a delay waits for the last one.
+    edits: u64,
+    // Unsaved changes left behind by editors that exited, until recovered or discarded.
+    backups: Vec<backup::Backup>,
+    // What `--wait` waits for, by whoever waits.
+    waiting: Vec<Waiting>,
+    // The socket other `xeditor`s hand their command line to.
+    server: Option<ipc::Server>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum PaneKind {
-    Explorer,
+// Who waits for buffers to be closed.
+#[derive(Debug, Clone, PartialEq)]
+enum Waiter {
+    // This editor, started with `--wait`, exits.
     Editor,
+    // Another `xeditor` started with `--wait` is told.
+    Client(ipc::Client),
+}
+
+// The buffers a waiter waits for, and the files still read for it.
+struct Waiting {
+    waiter: Waiter,
+    buffers: HashSet<BufferId>,
+    reading: usize,
+    // An editor waiting for files that all failed to open stays open.
+    opened: bool,
+}
+
+// Everything read before the window opens.
+#[derive(Clone)]
+struct Launch {
+    settings: settings::Settings,
+    themes: Vec<CustomTheme>,
+    layout: sidebar::Layout,
+    workspace: Workspace,
+    recent: recent::Recent,
+    // Whether the session is saved, not with `--no-restore` or when opening files.
+    restore: bool,
+    session: Option<Session>,
+    // What the command line asks to open.
+    requests: Vec<cli::Request>,
+    server: Option<ipc::Server>,
+    problems: Vec<String>,
+}
+
+enum PaneKind {
+    Sidebar,
+    // `None` for the focused pane, see `Xeditor::editor`.
+    Editor(Option<Box<Editor>>),
 }
 
 #[allow(unused)]
@@ -61,181 +230,4495 @@ enum PaneKind {
 enum Message {
     ActionPerformed(text_editor::Action),
     PaneResized(pane_grid::ResizeEvent),
+    PaneClicked(pane_grid::Pane),
+    ToggleSidebar,
+    ToggleSidebarSide,
+    ToggleZenMode,
+    ShowPanel(Panel),
+    ActivitySelected(Panel),
+    SearchQueryChanged(String),
+    // A buffer line of the focused editor.
+    GoToLine(usize),
+    RefreshGitStatus,
+    GitStatusLoaded(Vec<git::FileStatus>),
+    WindowCloseRequested(iced::window::Id),
+    WindowResized(Size),
+    SaveSession,
+    SaveSessionAs,
+    SessionNamed(String),
+    SwitchSession,
+    SessionsListed(Vec<String>),
+    SessionSelected(String),
+    SessionFileRead(session::View, Result<(Arc<String>, PathBuf), Error>),
+    ShowWelcome,
+    ShowRecent,
+    RecentSelected(recent::Item),
+    RecentFileRead(PathBuf, Result<(Arc<String>, PathBuf), Error>),
+    // The latest session of the workspace with the roots, if it has one.
+    RecentWorkspaceRead(Vec<PathBuf>, Option<Session>),
+    PinRecent,
+    RecentPinToggled(recent::Item),
+    ClearRecent,
+    ClientRequested(ipc::Client, Vec<cli::Request>),
+    CommandLineFileRead {
+        pane: pane_grid::Pane,
+        target: cli::Target,
+        readonly: bool,
+        wait: Option<Waiter>,
+        result: Result<(Arc<String>, PathBuf), Error>,
+    },
+    // Both files of `--diff`, each shown in its pane.
+    CommandLineDiffRead {
+        panes: (pane_grid::Pane, pane_grid::Pane),
+        readonly: bool,
+        wait: Option<Waiter>,
+        result: Result<[(Arc<String>, PathBuf); 2], Error>,
+    },
+    BackupBuffers,
+    BackupsFound(Vec<backup::Backup>),
+    ShowBackups,
+    // Backups are named by their file.
+    BackupSelected(PathBuf),
+    RecoverBackup(PathBuf),
+    CompareBackup(PathBuf),
+    DiscardBackup(PathBuf),
+    DiscardBackups,
+    PaneDragged(pane_grid::DragEvent),
+    // Runs the message with the pane focused, for the widgets inside a pane.
+    InPane(pane_grid::Pane, Box<Message>),
+    // Runs the message in a pane showing the buffer, for results of background work.
+    InBuffer(BufferId, Box<Message>),
+    SplitPane(pane_grid::Axis),
+    ClosePane,
+    PaneCloseConfirmed(pane_grid::Pane, bool),
+    ToggleMaximizedPane,
+    FocusPane(pane_grid::Direction),
     OpenFile,
     OpenedFile(Result<(Arc<String>, PathBuf), Error>),
     OpenedTreeFile(Result<(Arc<String>, PathBuf), Error>),
     NewFile,
     OpenDirectory,
-    OpenedDirectory(Result<Vec<FileNode>, Error>),
+    OpenedDirectory(Result<(Vec<FileNode>, PathBuf), Error>),
+    AddFolderToWorkspace,
+    AddedFolder(Result<(Vec<FileNode>, PathBuf), Error>),
+    RemoveFolderFromWorkspace,
+    RemovedFolderSelected(PathBuf),
+    WorkspaceRead(Vec<FileNode>),
     OpenChildDirectory(PathBuf),
     OpenedChildDirectory(Result<(Vec<FileNode>, PathBuf), Error>),
     OpenTreeFile(PathBuf),
     SaveFile,
-    SavedFile(Result<PathBuf, Error>),
+    // Saved files carry the hash of the text written.
+    SavedFile(Result<(PathBuf, u64), Error>),
+    SaveFileAs,
+    SavedFileAs(Result<(PathBuf, u64), Error>),
+    SaveCopy,
+    SavedCopy(Result<PathBuf, Error>),
+    SaveAll,
+    SavedAll(Vec<(BufferId, PathBuf, Result<u64, Error>)>),
+    RevertFile,
+    Reverted(Result<(Arc<String>, PathBuf), Error>),
+    // Carries the edit count when the delay started.
+    AutoSaveDue(u64),
+    AutoSave,
+    AutoSaved(Vec<(BufferId, PathBuf, Result<u64, Error>)>),
+    // Whether the file of the focused buffer can be written.
+    PermissionsChecked(bool),
+    ToggleReadOnly,
+    SaveWithPrivileges(privileged::Method),
+    SavedWithPrivileges(Result<(PathBuf, u64), Error>),
+    OpenSettings,
+    LanguageSelected(Language),
+    EditorScrolled(scrollable::Viewport),
+    ToggleFold(usize),
+    FoldAtCursor,
+    UnfoldAtCursor,
+    FoldAll,
+    UnfoldAll,
+    GutterPressed(usize),
+    GutterHovered(usize),
+    GutterReleased,
+    ToggleRelativeLineNumbers,
+    LineMarkersLoaded(Vec<(usize, LineMarker)>),
+    ToggleMinimap,
+    ToggleToolbar,
+    MenuToggled(command::Menu),
+    MenuHovered(command::Menu),
+    MenuClosed,
+    MinimapScrolled(usize),
+    GutterScrolled(mouse::ScrollDelta),
+    CycleSoftWrap,
+    ToggleRulers,
+    ToggleWhitespace,
+    TogglePalette,
+    PaletteQueryChanged(String),
+    PaletteMoved(isize),
+    PaletteSubmitted,
+    PaletteClosed,
+    CommandRun(Command),
+    Indent,
+    Outdent,
+    IndentationSelected(Indentation),
+    ConvertIndentation,
+    JumpToBracket,
+    MoveLinesUp,
+    MoveLinesDown,
+    DuplicateLines,
+    DeleteLines,
+    JoinLines,
+    SortLines {
+        natural: bool,
+        unique: bool,
+    },
+    ToggleLineComment,
+    ToggleBlockComment,
+    ConvertCase(Case),
+    TrimTrailingWhitespace,
+    FormatDocument,
+    Formatted {
+        original: String,
+        result: Result<String, Error>,
+        save: bool,
+    },
+    ToggleFormatOnSave,
+    NotificationDismissed(u64),
+    SelectColorTheme,
+    SelectSyntaxTheme,
+    ColorThemeSelected(String),
+    SyntaxThemeSelected(highlighter::Theme),
+    ReloadThemes,
+    ThemesLoaded((Vec<CustomTheme>, Vec<String>)),
+    SystemThemeChanged(Mode),
+    SelectEditorFont,
+    EditorFontSelected(String),
+    FontsLoaded(Vec<font::Family>),
+    FontMeasured(Font, f32),
+    ToggleLigatures,
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    SettingsPolled,
+    SettingsLoaded(
+        Scope,
+        PathBuf,
+        // Boxed, the layer is far larger than any other message.
+        Result<(Box<settings::Layer>, Vec<settings::Problem>), settings::Problem>,
+    ),
+}
+
+impl Message {
+    // Whether the message changes the text of the focused buffer.
+    fn is_edit(&self) -> bool {
+        match self {
+            Message::ActionPerformed(action) => action.is_edit(),
+            Message::Indent
+            | Message::Outdent
+            | Message::ConvertIndentation
+            | Message::MoveLinesUp
+            | Message::MoveLinesDown
+            | Message::DuplicateLines
+            | Message::DeleteLines
+            | Message::JoinLines
+            | Message::SortLines { .. }
+            | Message::ToggleLineComment
+            | Message::ToggleBlockComment
+            | Message::ConvertCase(_)
+            | Message::TrimTrailingWhitespace
+            | Message::FormatDocument => true,
+            _ => false,
+        }
+    }
 }
 
+const SESSION_INTERVAL: Duration = Duration::from_secs(30);
+const BACKUP_INTERVAL: Duration = Duration::from_secs(5);
+
 const PLACEHOLDER_TEXT: &str = "Type something,
 
 Shortcuts:
   Ctrl+S         Save
+  Ctrl+Shift+S   Save as
   Ctrl+O         Open file
   Ctrl+Shift+O   Open directory
-  Ctrl+N         New file";
+  Ctrl+R         Open recent
+  Ctrl+N         New file
+  Ctrl+]         Indent lines
+  Ctrl+[         Outdent lines
+  Alt+Up/Down    Move lines
+  Ctrl+Shift+D   Duplicate lines
+  Ctrl+Shift+K   Delete lines
+  Ctrl+J         Join lines
+  Ctrl+/         Toggle line comment
+  Ctrl+Shift+A   Toggle block comment
+  Ctrl+Shift+I   Format document
+  Ctrl+Shift+\\   Jump to matching bracket
+  Ctrl+Shift+[   Fold
+  Ctrl+Shift+]   Unfold
+  Ctrl+Alt+[     Fold all
+  Ctrl+Alt+]     Unfold all
+  Ctrl+Alt+L     Toggle relative line numbers
+  Ctrl+Alt+M     Toggle minimap
+  Ctrl+Alt+Z     Cycle soft wrap
+  Ctrl+Alt+R     Toggle rulers
+  Ctrl+Alt+W     Toggle whitespace
+  Ctrl+B         Toggle sidebar
+  Ctrl+\\         Split editor right
+  Ctrl+W         Close editor pane
+  Ctrl+Shift+P   Command palette";
 
 impl Xeditor {
-    fn new() -> (Self, Task<Message>) {
-        let (mut panes, explorer) = pane_grid::State::new(PaneKind::Explorer);
-        if let Some((_editor, split)) =
-            panes.split(pane_grid::Axis::Vertical, explorer, PaneKind::Editor)
-        {
-            // Give the explorer a sensible starting width.
-            panes.resize(split, 0.22);
-        }
+    fn new(launch: Launch) -> (Self, Task<Message>) {
+        let Launch {
+            settings,
+            themes,
+            layout,
+            workspace,
+            recent,
+            restore,
+            session,
+            requests,
+            server,
+            problems,
+        } = launch;
+        let (panes, focus) = pane_grid::State::new(PaneKind::Editor(None));
+
+        let settings_modified = [Scope::User, Scope::Project]
+            .into_iter()
+            .map(|scope| {
+                let modified = settings::path(scope, workspace.primary())
+                    .and_then(|path| settings::modified(&path));
+                (scope, modified)
+            })
+            .collect();
+
+        let buffer = BufferId::unique();
+        let editor = Editor::new(
+            buffer,
+            "",
+            settings.font_size,
+            settings.font_size * font::DEFAULT_ADVANCE,
+            settings.line_height,
+        );
+        let line_numbers = if settings.relative_line_numbers {
+            LineNumbers::Relative
+        } else {
+            LineNumbers::Absolute
+        };
+        let mut untitled = Buffer::new(
+            None,
+            language::PLAIN_TEXT,
+            settings.editor(&language::PLAIN_TEXT).indentation,
+        );
+        untitled.is_dirty = true;
+
+        // Nothing asked for and nothing to pick up again.
+        let welcome = session.is_none()
+            && requests
+                .iter()
+                .all(|request| *request == cli::Request::Focus);
+
+        let mut xeditor = Self {
+            editor,
+            buffers: HashMap::from([(buffer, untitled)]),
+            tree_content: Vec::new(),
+            panes,
+            focus,
+            error: None,
+            line_numbers,
+            show_minimap: settings.minimap,
+            show_toolbar: settings.toolbar,
+            menu: None,
+            view_options: HashMap::new(),
+            palette: None,
+            format_on_save: None,
+            notifications: Notifications::default(),
+            settings,
+            settings_modified,
+            workspace,
+            welcome,
+            recent,
+            sidebar: layout,
+            search_query: String::new(),
+            git_status: Vec::new(),
+            themes,
+            appearance: Appearance::default(),
+            theme_override: None,
+            syntax_theme_override: None,
+            system_mode: Mode::None,
+            font_families: Vec::new(),
+            editor_font_override: None,
+            ligatures: None,
+            editor_font: Font::MONOSPACE,
+            font_advance: font::DEFAULT_ADVANCE,
+            measured_font: None,
+            icon_font: font::named(font::ICONS),
+            zoom: 1.0,
+            session: restore.then(|| String::from(session::DEFAULT_NAME)),
+            window_size: session.as_ref().and_then(|session| session.window),
+            backed_up: HashMap::new(),
+            edits: 0,
+            backups: Vec::new(),
+            waiting: Vec::new(),
+            server,
+        };
+
+        let tree = match session {
+            Some(session) => xeditor.restore(session),
+            None => {
+                if xeditor.sidebar.visible {
+                    xeditor.show_sidebar();
+                }
+                Task::perform(
+                    read_workspace(xeditor.workspace.roots.clone(), Vec::new()),
+                    Message::WorkspaceRead,
+                )
+            }
+        };
+        let git_status = xeditor.update(Message::RefreshGitStatus);
+        let remember = match xeditor.workspace.is_empty() {
+            true => Task::none(),
+            false => xeditor.remember(recent::Item::Workspace(xeditor.workspace.roots.clone())),
+        };
+
+        let files = xeditor.open_requests(requests, Waiter::Editor);
+        let appearance = xeditor.refresh_appearance();
+        let fonts = xeditor.refresh_fonts();
+        let notify = problems
+            .into_iter()
+            .map(|problem| {
+                xeditor
+                    .notifications
+                    .push(notification::Level::Error, problem)
+            })
+            .collect::<Vec<_>>();
 
         (
-            Self {
-                content: text_editor::Content::new(),
-                tree_content: vec![FileNode::File {
-                    name: String::from("New File"),
-                    path: None,
-                }],
-                panes,
-                error: None,
-                path: None,
-                is_dirty: true,
-            },
-            Task::perform(
-                read_directory(default_directory()),
-                Message::OpenedDirectory,
-            ),
+            xeditor,
+            Task::batch(notify.into_iter().chain([
+                appearance,
+                fonts,
+                Task::perform(font::load_families(), Message::FontsLoaded),
+                iced::system::theme().map(Message::SystemThemeChanged),
+                tree,
+                files,
+                git_status,
+                Task::perform(backup::list(), Message::BackupsFound),
+                remember,
+            ])),
         )
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
+        if self.buffer().read_only && message.is_edit() {
+            return Task::none();
+        }
+
         match message {
             Message::ActionPerformed(content) => {
-                self.is_dirty = self.is_dirty || content.is_edit();
+                let is_edit = content.is_edit();
+                self.buffer_mut().is_dirty = self.buffer().is_dirty || is_edit;
 
                 self.error = None;
 
-                self.content.perform(content);
+                let follow_cursor = !matches!(
+                    content,
+                    text_editor::Action::Scroll { .. } | text_editor::Action::Drag(_)
+                );
 
-                Task::none()
+                match content {
+                    text_editor::Action::Edit(text_editor::Edit::Enter) => self.insert_newline(),
+                    text_editor::Action::Edit(text_editor::Edit::Insert(c)) => {
+                        match bracket::closing(&self.language(), c) {
+                            Some(close) if self.editor.content.selection().is_some() => {
+                                self.surround(c, close);
+                            }
+                            _ if self.editor.content.selection().is_none() => self.insert_char(c),
+                            _ => self.editor.folds.perform(&mut self.editor.content, content),
+                        }
+                    }
+                    text_editor::Action::Edit(text_editor::Edit::Backspace) => self.backspace(),
+                    content => self.editor.folds.perform(&mut self.editor.content, content),
+                }
+
+                let auto_save = if is_edit {
+                    // Shebangs and modelines live near the edges of the buffer, so only
+                    // edits there can change the detected language.
+                    let line = self.editor.content.cursor().position.line;
+                    if line < 5 || line + 5 >= self.editor.content.line_count() {
+                        self.detect_language();
+                    }
+
+                    self.refresh_fold_ranges();
+                    self.sync_views();
+                    self.auto_save_later()
+                } else {
+                    Task::none()
+                };
+
+                if follow_cursor {
+                    Task::batch([self.scroll_to_cursor(), auto_save])
+                } else {
+                    auto_save
+                }
             }
 
-            Message::PaneResized(event) => {
-                self.panes.resize(event.split, event.ratio);
+            Message::EditorScrolled(viewport) => {
+                let resized = self
+                    .editor
+                    .viewport
+                    .is_none_or(|previous| previous.bounds().size() != viewport.bounds().size());
+
+                self.editor.viewport = Some(viewport);
+
+                if resized {
+                    self.refresh_layout();
+                }
                 Task::none()
             }
 
-            Message::OpenedFile(content) => match content {
-                Ok(content) => {
-                    self.content = text_editor::Content::with_text(&content.0);
-                    self.path = Some(content.1);
-                    self.is_dirty = false;
-
-                    let file_name = self
-                        .path
-                        .as_ref()
-                        .and_then(|path| {
-                            path.file_name()
-                                .map(|name| String::from(name.to_string_lossy()))
-                        })
-                        .unwrap_or_else(|| String::from("Default"));
-
-                    self.tree_content = vec![FileNode::File {
-                        name: file_name,
-                        path: self.path.clone(),
-                    }];
+            Message::ToggleFold(line) => {
+                if self.editor.folds.is_folded(line) {
+                    self.editor.folds.unfold(&mut self.editor.content, line);
+                } else {
+                    let start = self.editor.folds.to_buffer_line(line);
+                    let range = self
+                        .buffer()
+                        .fold_ranges
+                        .iter()
+                        .find(|range| range.start == start)
+                        .copied();
 
-                    Task::none()
+                    if let Some(range) = range {
+                        self.editor.folds.fold(&mut self.editor.content, range);
+                    }
                 }
-                Err(e) => {
-                    self.error = Some(e);
-                    Task::none()
+                self.refresh_layout();
+                Task::none()
+            }
+
+            Message::FoldAtCursor => {
+                let line = self
+                    .editor
+                    .folds
+                    .to_buffer_line(self.editor.content.cursor().position.line);
+
+                // The innermost range around the cursor that is not folded already.
+                let range = self
+                    .buffer()
+                    .fold_ranges
+                    .iter()
+                    .rev()
+                    .filter(|range| range.contains(line))
+                    .find(|range| {
+                        let header = self.editor.folds.to_display_line(range.start);
+                        self.editor.folds.to_buffer_line(header) == range.start
+                            && !self.editor.folds.is_folded(header)
+                    })
+                    .copied();
+
+                if let Some(range) = range {
+                    self.editor.folds.fold(&mut self.editor.content, range);
                 }
-            },
+                self.refresh_layout();
+                self.scroll_to_cursor()
+            }
 
-            Message::OpenedTreeFile(content) => match content {
-                Ok(content) => {
-                    self.content = text_editor::Content::with_text(&content.0);
-                    self.path = Some(content.1);
-                    self.is_dirty = false;
-                    Task::none()
+            Message::UnfoldAtCursor => {
+                let line = self.editor.content.cursor().position.line;
+                self.editor.folds.unfold(&mut self.editor.content, line);
+                self.refresh_layout();
+                Task::none()
+            }
+
+            Message::FoldAll => {
+                // Innermost first, so unfolding an outer region keeps its children folded.
+                for range in self.buffer().fold_ranges.clone().into_iter().rev() {
+                    self.editor.folds.fold(&mut self.editor.content, range);
                 }
-                Err(e) => {
-                    self.error = Some(e);
-                    Task::none()
+                self.refresh_layout();
+                self.scroll_to_cursor()
+            }
+
+            Message::UnfoldAll => {
+                self.editor.folds.unfold_all(&mut self.editor.content);
+                self.refresh_layout();
+                self.scroll_to_cursor()
+            }
+
+            Message::GutterPressed(line) => {
+                self.editor.gutter_drag = Some(line);
+                self.select_lines(line, line);
+                Task::none()
+            }
+
+            Message::GutterHovered(line) => {
+                if let Some(anchor) = self.editor.gutter_drag {
+                    self.select_lines(anchor, line);
+                    return self.scroll_to_cursor();
                 }
-            },
+                Task::none()
+            }
 
-            Message::OpenFile => Task::perform(pick_file(), Message::OpenedFile),
+            Message::GutterReleased => {
+                self.editor.gutter_drag = None;
+                Task::none()
+            }
 
-            Message::OpenTreeFile(path) => Task::perform(read_file(path), Message::OpenedTreeFile),
+            Message::ToggleRelativeLineNumbers => {
+                self.line_numbers = match self.line_numbers {
+                    LineNumbers::Absolute => LineNumbers::Relative,
+                    LineNumbers::Relative => LineNumbers::Absolute,
+                };
+                Task::none()
+            }
 
-            Message::SaveFile => {
-                let text = self.content.text();
-                Task::perform(save_file(self.path.clone(), text), Message::SavedFile)
+            Message::LineMarkersLoaded(markers) => {
+                self.buffer_mut().line_markers = markers;
+                Task::none()
             }
 
-            Message::SavedFile(Ok(path)) => {
-                self.path = Some(path);
-                self.is_dirty = false;
+            Message::ToggleMinimap => {
+                self.show_minimap = !self.show_minimap;
+                Task::none()
+            }
 
+            Message::ToggleToolbar => {
+                self.show_toolbar = !self.show_toolbar;
                 Task::none()
             }
-            Message::SavedFile(Err(error)) => {
-                self.error = Some(error);
+
+            Message::MenuToggled(menu) => {
+                self.menu = (self.menu != Some(menu)).then_some(menu);
                 Task::none()
             }
 
-            Message::NewFile => {
-                self.content = text_editor::Content::new();
-                self.path = None;
-                self.is_dirty = true;
-                self.tree_content = vec![FileNode::File {
-                    name: String::from("New File"),
-                    path: None,
-                }];
+            Message::MenuHovered(menu) => {
+                if self.menu.is_some() {
+                    self.menu = Some(menu);
+                }
                 Task::none()
             }
 
-            Message::OpenDirectory => Task::perform(pick_directory(), Message::OpenedDirectory),
+            Message::MenuClosed => {
+                self.menu = None;
+                Task::none()
+            }
 
-            Message::OpenedDirectory(dir_list) => match dir_list {
-                Ok(contents) => {
-                    self.tree_content = contents;
-                    Task::none()
+            Message::MinimapScrolled(line) => {
+                let Some(viewport) = self.editor.viewport else {
+                    return Task::none();
+                };
+
+                // Center the clicked line in the editor.
+                let target =
+                    self.editor.line_layout.line_top(line) - viewport.bounds().height / 2.0;
+
+                operation::scroll_to(
+                    self.editor.scrollable.clone(),
+                    operation::AbsoluteOffset {
+                        x: None,
+                        y: Some(target.max(0.0)),
+                    },
+                )
+            }
+
+            Message::GutterScrolled(delta) => {
+                // Same speed as scrolling the editor itself.
+                let y = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y * 60.0,
+                    mouse::ScrollDelta::Pixels { y, .. } => y,
+                };
+
+                operation::scroll_by(
+                    self.editor.scrollable.clone(),
+                    operation::AbsoluteOffset { x: 0.0, y: -y },
+                )
+            }
+
+            Message::CycleSoftWrap => {
+                let options = self.view_options_mut();
+                options.soft_wrap = options.soft_wrap.next(options.wrap_column);
+                // Every editor showing the language wraps the same.
+                self.for_each_editor(Self::refresh_layout);
+
+                operation::scroll_to(
+                    self.editor.scrollable.clone(),
+                    operation::AbsoluteOffset {
+                        x: Some(0.0),
+                        y: None,
+                    },
+                )
+                .chain(self.scroll_to_cursor())
+            }
+
+            Message::ToggleRulers => {
+                let options = self.view_options_mut();
+                options.show_rulers = !options.show_rulers;
+                Task::none()
+            }
+
+            Message::ToggleWhitespace => {
+                let options = self.view_options_mut();
+                options.show_whitespace = !options.show_whitespace;
+                Task::none()
+            }
+
+            Message::TogglePalette => {
+                if self.palette.take().is_some() {
+                    return operation::focus(self.editor.id.clone());
                 }
-                Err(e) => {
-                    self.error = Some(e);
-                    Task::none()
+
+                self.palette = Some(Palette::commands());
+                operation::focus(palette::INPUT)
+            }
+
+            Message::PaletteQueryChanged(query) => {
+                if let Some(palette) = &mut self.palette {
+                    palette.set_query(query);
+                }
+                Task::none()
+            }
+
+            Message::PaletteMoved(delta) => {
+                if let Some(palette) = &mut self.palette {
+                    palette.move_selection(delta);
                 }
+                Task::none()
+            }
+
+            Message::PaletteSubmitted => match self.palette.as_ref().and_then(Palette::selected) {
+                Some(message) => self.update(message),
+                None => Task::none(),
             },
-            Message::OpenChildDirectory(path) => {
-                if toggle_dir_expanded(&mut self.tree_content, &path) {
-                    Task::none()
+
+            Message::PaletteClosed => {
+                self.palette = None;
+                operation::focus(self.editor.id.clone())
+            }
+
+            Message::CommandRun(command) => {
+                self.palette = None;
+                self.menu = None;
+                let task = self.update(command.message());
+
+                operation::focus(self.editor.id.clone()).chain(task)
+            }
+
+            Message::Indent => {
+                let cursor = self.editor.content.cursor();
+                let spans_lines = cursor
+                    .selection
+                    .is_some_and(|anchor| anchor.line != cursor.position.line);
+
+                if spans_lines {
+                    let indentation = self.buffer().indentation;
+                    let (first, last) = self.selected_lines();
+                    let indented = self.edit_lines(first, last, |lines| {
+                        lines
+                            .iter()
+                            .map(|line| indent::indent_line(line, indentation))
+                            .collect()
+                    });
+                    if !indented {
+                        return Task::none();
+                    }
+                } else {
+                    let text: String = self
+                        .editor
+                        .content
+                        .line(cursor.position.line)
+                        .map(|line| line.text.chars().take(cursor.position.column).collect())
+                        .unwrap_or_default();
+                    let insertion = self.buffer().indentation.insertion(layout::columns(&text));
+
+                    self.editor.folds.perform(
+                        &mut self.editor.content,
+                        text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(insertion))),
+                    );
+                }
+
+                self.edited()
+            }
+
+            Message::Outdent => {
+                let indentation = self.buffer().indentation;
+                let (first, last) = self.selected_lines();
+                let outdented = self.edit_lines(first, last, |lines| {
+                    lines
+                        .iter()
+                        .map(|line| indent::outdent_line(line, indentation))
+                        .collect()
+                });
+                if !outdented {
+                    return Task::none();
+                }
+
+                self.edited()
+            }
+
+            Message::IndentationSelected(indentation) => {
+                self.buffer_mut().indentation = indentation;
+                Task::none()
+            }
+
+            Message::ConvertIndentation => {
+                // Convert from what the buffer actually uses to the selected style.
+                let text = self.text();
+                let from = indent::detect(&text).unwrap_or(self.buffer().indentation);
+                let to = self.buffer().indentation;
+
+                if text
+                    .lines()
+                    .all(|line| indent::convert_line(line, from, to) == line)
+                {
+                    return Task::none();
+                }
+
+                self.editor.folds.unfold_all(&mut self.editor.content);
+                let last = self.editor.content.line_count().saturating_sub(1);
+                self.edit_lines(0, last, |lines| {
+                    lines
+                        .iter()
+                        .map(|line| indent::convert_line(line, from, to))
+                        .collect()
+                });
+
+                self.edited()
+            }
+
+            Message::JumpToBracket => {
+                let cursor = self.editor.content.cursor().position;
+                let Some((_, other)) =
+                    bracket::matching(&self.editor.content, cursor, &self.language())
+                else {
+                    return Task::none();
+                };
+
+                self.editor.content.move_to(text_editor::Cursor {
+                    position: other,
+                    selection: None,
+                });
+                self.scroll_to_cursor()
+            }
+
+            Message::MoveLinesUp | Message::MoveLinesDown => {
+                self.move_lines(matches!(message, Message::MoveLinesUp));
+                self.edited()
+            }
+
+            Message::DuplicateLines => {
+                let (first, last) = self.selected_lines();
+                let lines = self.lines(first, last);
+                let count = lines.len();
+
+                // The cursor ends up on the copy.
+                let shift = |position: Position| Position {
+                    line: position.line + count,
+                    column: position.column,
+                };
+                let cursor = self.editor.content.cursor();
+
+                self.replace_lines(
+                    first,
+                    last,
+                    &[lines.clone(), lines].concat(),
+                    text_editor::Cursor {
+                        position: shift(cursor.position),
+                        selection: cursor.selection.map(shift),
+                    },
+                );
+                self.edited()
+            }
+
+            Message::DeleteLines => {
+                let (first, last) = self.selected_lines();
+                let column = self.editor.content.cursor().position.column;
+                let line_count = self.editor.content.line_count();
+
+                // The line below, or above at the end of the buffer, takes their place.
+                let (range_first, range_last, kept) = if last + 1 < line_count {
+                    (first, last + 1, Some(last + 1))
+                } else if first > 0 {
+                    (first - 1, last, Some(first - 1))
+                } else {
+                    (first, last, None)
+                };
+
+                let kept = match kept {
+                    Some(line) => self.lines(line, line),
+                    None => vec![String::new()],
+                };
+
+                self.replace_lines(
+                    range_first,
+                    range_last,
+                    &kept,
+                    text_editor::Cursor {
+                        position: Position {
+                            line: range_first,
+                            column: column.min(kept[0].chars().count()),
+                        },
+                        selection: None,
+                    },
+                );
+                self.edited()
+            }
+
+            Message::JoinLines => {
+                let (first, mut last) = self.selected_lines();
+
+                if first == last {
+                    if last + 1 >= self.editor.content.line_count() {
+                        return Task::none();
+                    }
+                    last += 1;
+                }
+
+                let lines = self.lines(first, last);
+                let joined = lines::join(&lines);
+                // The cursor goes where the last line was joined.
+                let column = lines::join(&lines[..lines.len() - 1]).chars().count();
+
+                self.replace_lines(
+                    first,
+                    last,
+                    &[joined],
+                    text_editor::Cursor {
+                        position: Position {
+                            line: first,
+                            column,
+                        },
+                        selection: None,
+                    },
+                );
+                self.edited()
+            }
+
+            Message::SortLines { natural, unique } => {
+                let (first, last) = self.selected_lines();
+                if first == last {
+                    return Task::none();
+                }
+
+                let mut lines = self.lines(first, last);
+                lines::sort(&mut lines, natural, unique);
+
+                let end = Position {
+                    line: first + lines.len() - 1,
+                    column: lines
+                        .last()
+                        .map(|line| line.chars().count())
+                        .unwrap_or_default(),
+                };
+
+                self.replace_lines(
+                    first,
+                    last,
+                    &lines,
+                    text_editor::Cursor {
+                        position: end,
+                        selection: Some(Position {
+                            line: first,
+                            column: 0,
+                        }),
+                    },
+                );
+                self.edited()
+            }
+
+            Message::ToggleLineComment => {
+                let language = self.language();
+
+                match (language.line_comment, language.block_comment) {
+                    (Some(token), _) => {
+                        let (first, last) = self.selected_lines();
+                        self.edit_lines(first, last, |lines| {
+                            lines::toggle_line_comment(lines, token)
+                        });
+                    }
+                    (None, Some(delimiters)) => self.toggle_block_comment(delimiters),
+                    (None, None) => return Task::none(),
+                }
+                self.edited()
+            }
+
+            Message::ToggleBlockComment => {
+                let language = self.language();
+
+                match (language.block_comment, language.line_comment) {
+                    (Some(delimiters), _) => self.toggle_block_comment(delimiters),
+                    (None, Some(_)) => return self.update(Message::ToggleLineComment),
+                    (None, None) => return Task::none(),
+                }
+                self.edited()
+            }
+
+            Message::ConvertCase(case) => {
+                if self.editor.content.selection().is_none() {
+                    self.editor.content.perform(text_editor::Action::SelectWord);
+                }
+
+                self.selected_lines();
+                let Some(text) = self.editor.content.selection() else {
+                    return Task::none();
+                };
+
+                self.replace_selection(lines::convert_case(&text, case));
+                self.edited()
+            }
+
+            Message::TrimTrailingWhitespace => {
+                if self.text().lines().all(|line| line.trim_end() == line) {
+                    return Task::none();
+                }
+
+                self.editor.folds.unfold_all(&mut self.editor.content);
+                let last = self.editor.content.line_count().saturating_sub(1);
+                self.edit_lines(0, last, |lines| {
+                    lines
+                        .iter()
+                        .map(|line| line.trim_end().to_owned())
+                        .collect()
+                });
+                self.edited()
+            }
+
+            Message::PaneResized(event) => {
+                self.panes.resize(event.split, event.ratio);
+
+                if let Some((_, split)) = self.sidebar_pane()
+                    && split == event.split
+                {
+                    self.sidebar.width = Some(match self.sidebar.side {
+                        Side::Left => event.ratio,
+                        Side::Right => 1.0 - event.ratio,
+                    });
+                }
+                Task::none()
+            }
+
+            Message::ToggleSidebar => {
+                if self.sidebar.visible {
+                    self.hide_sidebar();
                 } else {
-                    Task::perform(read_child_directory(path), Message::OpenedChildDirectory)
+                    self.show_sidebar();
+                }
+                Task::none()
+            }
+
+            Message::ToggleSidebarSide => {
+                self.sidebar.side = match self.sidebar.side {
+                    Side::Left => Side::Right,
+                    Side::Right => Side::Left,
+                };
+
+                if self.sidebar.visible {
+                    self.hide_sidebar();
+                    self.show_sidebar();
+                }
+                Task::none()
+            }
+
+            Message::ToggleZenMode => {
+                self.sidebar.zen = !self.sidebar.zen;
+                operation::focus(self.editor.id.clone())
+            }
+
+            Message::ShowPanel(panel) => {
+                self.sidebar.panel = panel;
+                self.sidebar.zen = false;
+                self.show_sidebar();
+
+                match panel {
+                    Panel::Search => operation::focus(sidebar::SEARCH_INPUT),
+                    Panel::SourceControl => self.update(Message::RefreshGitStatus),
+                    Panel::Explorer | Panel::Outline => Task::none(),
+                }
+            }
+
+            // Clicking the panel on display hides the sidebar.
+            Message::ActivitySelected(panel) => {
+                if self.sidebar.visible && self.sidebar.panel == panel {
+                    self.hide_sidebar();
+                    return Task::none();
                 }
+                self.update(Message::ShowPanel(panel))
+            }
+
+            Message::SearchQueryChanged(query) => {
+                self.search_query = query;
+                Task::none()
             }
 
-            Message::OpenedChildDirectory(Ok((child_node, path))) => {
-                set_dir_children(&mut self.tree_content, &path, child_node);
-                Task::none()
-            }
+            Message::GoToLine(line) => {
+                let line = self.editor.folds.to_display_line(line);
+                self.editor.content.move_to(text_editor::Cursor {
+                    position: Position { line, column: 0 },
+                    selection: None,
+                });
+
+                Task::batch([
+                    operation::focus(self.editor.id.clone()),
+                    self.scroll_to_cursor(),
+                ])
+            }
+
+            Message::RefreshGitStatus => Task::perform(
+                git_status(self.workspace.roots.clone()),
+                Message::GitStatusLoaded,
+            ),
+
+            Message::GitStatusLoaded(changes) => {
+                self.git_status = changes;
+                Task::none()
+            }
+
+            Message::WindowCloseRequested(window) => {
+                let layout = self.sidebar;
+                let session = self.session();
+                // Unsaved changes are kept for the next start instead of asking about them.
+                let snapshots = self.snapshots();
+                let keep = snapshots.iter().map(|snapshot| snapshot.buffer).collect();
+
+                Task::future(async move {
+                    sidebar::save(layout).await;
+                    if let Some(session) = session {
+                        session::save(session).await;
+                    }
+                    backup::save(snapshots, keep).await;
+                })
+                .then(move |()| iced::window::close(window))
+            }
+
+            Message::WindowResized(size) => {
+                self.window_size = Some(size);
+                Task::none()
+            }
+
+            Message::SaveSession => match self.session() {
+                Some(session) => Task::future(session::save(session)).discard(),
+                None => Task::none(),
+            },
+
+            Message::SaveSessionAs => {
+                self.palette = Some(Palette::prompt("Name the session", Message::SessionNamed));
+                operation::focus(palette::INPUT)
+            }
+
+            Message::SessionNamed(name) => {
+                self.palette = None;
+                self.session = Some(name.clone());
+
+                Task::batch([
+                    operation::focus(self.editor.id.clone()),
+                    self.update(Message::SaveSession),
+                    self.notifications
+                        .push(notification::Level::Info, format!("Saved session `{name}`")),
+                ])
+            }
+
+            Message::SwitchSession => {
+                let primary = self.workspace.primary().map(Path::to_path_buf);
+                Task::perform(session::list(primary), Message::SessionsListed)
+            }
+
+            Message::SessionsListed(names) => {
+                if names.is_empty() {
+                    return self.notifications.push(
+                        notification::Level::Info,
+                        "No saved sessions for this folder",
+                    );
+                }
+
+                let entries = names
+                    .into_iter()
+                    .map(|name| palette::Entry {
+                        detail: if self.session.as_ref() == Some(&name) {
+                            String::from("current")
+                        } else {
+                            String::new()
+                        },
+                        message: Message::SessionSelected(name.clone()),
+                        title: name,
+                    })
+                    .collect();
+
+                self.palette = Some(Palette::new("Select a session", entries));
+                operation::focus(palette::INPUT)
+            }
+
+            Message::SessionSelected(name) => {
+                self.palette = None;
+
+                if self.has_unsaved_changes() {
+                    return self.notifications.push(
+                        notification::Level::Error,
+                        "Save or close the modified files before switching sessions",
+                    );
+                }
+
+                let Some(session) = session::read(self.workspace.primary(), &name) else {
+                    return self.notifications.push(
+                        notification::Level::Error,
+                        format!("Session `{name}` could not be read"),
+                    );
+                };
+
+                // The session being left is saved first, as it was.
+                let save = self.update(Message::SaveSession);
+                Task::batch([save, self.restore(session)])
+            }
+
+            Message::ShowWelcome => {
+                self.welcome = true;
+                self.forget_missing()
+            }
+
+            Message::ShowRecent => {
+                let forget = self.forget_missing();
+                if self.recent.is_empty() {
+                    return Task::batch([
+                        forget,
+                        self.notifications
+                            .push(notification::Level::Info, "Nothing was opened recently"),
+                    ]);
+                }
+
+                let entries = self
+                    .recent
+                    .entries()
+                    .iter()
+                    .map(|entry| palette::Entry {
+                        title: entry.item.title(),
+                        detail: recent_detail(entry),
+                        message: Message::RecentSelected(entry.item.clone()),
+                    })
+                    .collect();
+
+                self.palette = Some(Palette::new("Open a recent file or folder", entries));
+                Task::batch([forget, operation::focus(palette::INPUT)])
+            }
+
+            Message::RecentSelected(recent::Item::File(path)) => {
+                self.palette = None;
+                Task::perform(read_file(path.clone()), move |result| {
+                    Message::RecentFileRead(path.clone(), result)
+                })
+            }
+
+            Message::RecentFileRead(_, Ok(file)) => self.update(Message::OpenedFile(Ok(file))),
+
+            Message::RecentFileRead(path, Err(error)) => {
+                // Only files that are gone drop out, one that could not be read this time stays.
+                let forget = match error {
+                    Error::IoError(ErrorKind::NotFound) => {
+                        self.forget(&recent::Item::File(path.clone()))
+                    }
+                    _ => Task::none(),
+                };
+                Task::batch([
+                    forget,
+                    self.notifications.push(
+                        notification::Level::Error,
+                        format!("Could not open {}: {}", path.display(), describe(&error)),
+                    ),
+                ])
+            }
+
+            Message::RecentSelected(recent::Item::Workspace(roots)) => {
+                self.palette = None;
+
+                if self.has_unsaved_changes() {
+                    return self.notifications.push(
+                        notification::Level::Error,
+                        "Save or close the modified files before opening another workspace",
+                    );
+                }
+
+                let Some(primary) = roots.first().cloned() else {
+                    return Task::none();
+                };
+                Task::perform(session::latest(primary), move |session| {
+                    Message::RecentWorkspaceRead(roots.clone(), session)
+                })
+            }
+
+            Message::RecentWorkspaceRead(roots, session) => {
+                let item = recent::Item::Workspace(roots.clone());
+                if roots.iter().any(|root| !root.is_dir()) {
+                    let forget = self.forget(&item);
+                    return Task::batch([
+                        forget,
+                        self.notifications.push(
+                            notification::Level::Error,
+                            format!("{} no longer exists", item.title()),
+                        ),
+                    ]);
+                }
+
+                // A workspace without a session starts out with an empty editor.
+                let session = session.unwrap_or_else(|| Session {
+                    name: String::from(session::DEFAULT_NAME),
+                    roots: roots.clone(),
+                    expanded: Vec::new(),
+                    window: self.window_size,
+                    panes: session::Panes::Editor(session::View {
+                        path: None,
+                        cursor: Position { line: 0, column: 0 },
+                        scroll: 0.0,
+                    }),
+                    focus: 0,
+                });
+
+                // The session being left is saved first, as it was.
+                let save = self.update(Message::SaveSession);
+                Task::batch([save, self.restore(Session { roots, ..session })])
+            }
+
+            Message::PinRecent => {
+                let entries = self
+                    .recent
+                    .entries()
+                    .iter()
+                    .map(|entry| palette::Entry {
+                        title: entry.item.title(),
+                        detail: recent_detail(entry),
+                        message: Message::RecentPinToggled(entry.item.clone()),
+                    })
+                    .collect();
+
+                self.palette = Some(Palette::new("Pin or unpin a recent item", entries));
+                operation::focus(palette::INPUT)
+            }
+
+            Message::RecentPinToggled(item) => {
+                self.palette = None;
+                self.recent.toggle_pinned(&item);
+                Task::future(recent::save(self.recent.clone())).discard()
+            }
+
+            Message::ClearRecent => {
+                self.recent.clear();
+                Task::batch([
+                    Task::future(recent::save(self.recent.clone())).discard(),
+                    self.notifications.push(
+                        notification::Level::Info,
+                        "Cleared recently opened, pinned items are kept",
+                    ),
+                ])
+            }
+
+            Message::BackupBuffers => {
+                let snapshots = self.snapshots();
+                let hashes: HashMap<BufferId, u64> = snapshots
+                    .iter()
+                    .map(|snapshot| (snapshot.buffer, hash(&snapshot.text)))
+                    .collect();
+
+                if hashes == self.backed_up {
+                    return Task::none();
+                }
+
+                let keep = hashes.keys().copied().collect();
+                let changed = snapshots
+                    .into_iter()
+                    .filter(|snapshot| {
+                        self.backed_up.get(&snapshot.buffer) != hashes.get(&snapshot.buffer)
+                    })
+                    .collect();
+                self.backed_up = hashes;
+
+                Task::future(backup::save(changed, keep)).discard()
+            }
+
+            Message::BackupsFound(backups) => {
+                self.backups = backups;
+
+                if self.backups.is_empty() || self.palette.is_some() {
+                    return Task::none();
+                }
+                self.update(Message::ShowBackups)
+            }
+
+            Message::ShowBackups => {
+                if self.backups.is_empty() {
+                    return self
+                        .notifications
+                        .push(notification::Level::Info, "No unsaved changes to recover");
+                }
+
+                let entries = self
+                    .backups
+                    .iter()
+                    .map(|backup| palette::Entry {
+                        title: backup.name(),
+                        detail: match (&backup.path, &backup.disk) {
+                            (Some(_), None) => format!("deleted, {}", backup::ago(backup.saved)),
+                            _ if backup.changed_on_disk => {
+                                format!("changed on disk, {}", backup::ago(backup.saved))
+                            }
+                            _ => backup::ago(backup.saved),
+                        },
+                        message: Message::BackupSelected(backup.file.clone()),
+                    })
+                    .chain([palette::Entry {
+                        title: String::from("Discard All"),
+                        detail: String::new(),
+                        message: Message::DiscardBackups,
+                    }])
+                    .collect();
+
+                self.palette = Some(Palette::new(
+                    "Recover unsaved changes from a previous run",
+                    entries,
+                ));
+                operation::focus(palette::INPUT)
+            }
+
+            Message::BackupSelected(file) => {
+                let Some(backup) = self.backups.iter().find(|backup| backup.file == file) else {
+                    return Task::none();
+                };
+                let name = backup.name();
+
+                let mut entries = vec![palette::Entry {
+                    title: format!("Recover {name}"),
+                    detail: String::new(),
+                    message: Message::RecoverBackup(file.clone()),
+                }];
+                if backup.disk.is_some() {
+                    entries.push(palette::Entry {
+                        title: format!("Compare {name} with the File on Disk"),
+                        detail: String::new(),
+                        message: Message::CompareBackup(file.clone()),
+                    });
+                }
+                entries.push(palette::Entry {
+                    title: format!("Discard {name}"),
+                    detail: String::new(),
+                    message: Message::DiscardBackup(file),
+                });
+
+                self.palette = Some(Palette::new("Recover, compare or discard", entries));
+                operation::focus(palette::INPUT)
+            }
+
+            Message::RecoverBackup(file) => {
+                self.palette = None;
+                let Some(index) = self.backups.iter().position(|backup| backup.file == file) else {
+                    return Task::none();
+                };
+                let backup = self.backups.remove(index);
+                let name = backup.name();
+
+                let split = self.split_if_unsaved();
+                let open = match backup.path {
+                    Some(path) => self.open(&backup.text, path),
+                    None => {
+                        self.new_file();
+                        self.editor.reset(self.editor.buffer, &backup.text);
+                        self.detect_language();
+                        self.detect_indentation();
+                        self.refresh_fold_ranges();
+                        Task::none()
+                    }
+                };
+
+                // A file shown in another pane keeps its buffer, which gets the backup.
+                if self.text() != backup.text {
+                    let fold_ranges = self.buffer().fold_ranges.clone();
+                    self.editor.sync(&backup.text, &fold_ranges);
+                    self.refresh_fold_ranges();
+                    self.sync_views();
+                }
+                self.buffer_mut().is_dirty = true;
+
+                Task::batch([
+                    split,
+                    open,
+                    Task::future(backup::remove(file)).discard(),
+                    operation::focus(self.editor.id.clone()),
+                    self.notifications.push(
+                        notification::Level::Info,
+                        format!("Recovered unsaved changes of {name}"),
+                    ),
+                ])
+            }
+
+            Message::CompareBackup(file) => {
+                self.palette = None;
+                let Some(backup) = self.backups.iter().find(|backup| backup.file == file) else {
+                    return Task::none();
+                };
+                let Some(disk) = &backup.disk else {
+                    return Task::none();
+                };
+
+                let name = backup.name();
+                let diff = diff::unified(
+                    disk,
+                    &backup.text,
+                    &format!("{name} (on disk)"),
+                    &format!("{name} (unsaved)"),
+                );
+
+                let split = self.split_if_unsaved();
+                self.new_file();
+                self.editor.reset(self.editor.buffer, &diff);
+                let buffer = self.buffer_mut();
+                buffer.is_dirty = false;
+                buffer.language_override = language::by_name("diff");
+                self.refresh_fold_ranges();
+
+                // The backup stays listed to be recovered or discarded after reading the diff.
+                Task::batch([split, operation::focus(self.editor.id.clone())])
+            }
+
+            Message::DiscardBackup(file) => {
+                self.palette = None;
+                self.backups.retain(|backup| backup.file != file);
+
+                let remove = Task::future(backup::remove(file)).discard();
+                if self.backups.is_empty() {
+                    return remove;
+                }
+                Task::batch([remove, self.update(Message::ShowBackups)])
+            }
+
+            Message::DiscardBackups => {
+                self.palette = None;
+
+                Task::batch(
+                    std::mem::take(&mut self.backups)
+                        .into_iter()
+                        .map(|backup| Task::future(backup::remove(backup.file)).discard()),
+                )
+            }
+
+            Message::SessionFileRead(view, result) => match result {
+                Ok((text, path)) => {
+                    let task = self.open(&text, path);
+                    self.move_cursor(view.cursor);
+
+                    let scroll = operation::scroll_to(
+                        self.editor.scrollable.clone(),
+                        operation::AbsoluteOffset {
+                            x: None,
+                            y: Some(view.scroll),
+                        },
+                    );
+                    Task::batch([task, scroll])
+                }
+                Err(error) => {
+                    let path = view.path.as_deref().unwrap_or(Path::new("")).display();
+
+                    self.notifications.push(
+                        notification::Level::Error,
+                        format!("Could not reopen {path}: {}", describe(&error)),
+                    )
+                }
+            },
+
+            Message::ClientRequested(client, requests) => {
+                let open = self.open_requests(requests, Waiter::Client(client.clone()));
+
+                Task::batch([
+                    open,
+                    Task::future(client.reply(ipc::Reply::Opened)).discard(),
+                ])
+            }
+
+            Message::CommandLineFileRead {
+                pane,
+                target,
+                readonly,
+                wait,
+                result,
+            } => {
+                self.finish_reading(wait.as_ref());
+                self.in_pane_with(pane, |xeditor| {
+                    xeditor.open_command_line_file(target, readonly, wait, result)
+                })
+            }
+
+            Message::CommandLineDiffRead {
+                panes: (left, right),
+                readonly,
+                wait,
+                result,
+            } => {
+                self.finish_reading(wait.as_ref());
+                let [(original, original_path), (modified, modified_path)] = match result {
+                    Ok(files) => files,
+                    Err(error) => {
+                        return self.notifications.push(
+                            notification::Level::Error,
+                            format!("Could not compare the files: {}", describe(&error)),
+                        );
+                    }
+                };
+
+                let focus = self.focus;
+                let mut tasks = Vec::new();
+
+                if self.focus_pane(left) {
+                    tasks.push(self.open(&original, original_path));
+                    // The original side is only there to compare against.
+                    self.buffer_mut().read_only = true;
+                }
+
+                if self.focus_pane(right) {
+                    // Replaces the git markers `open` asked for.
+                    let _ = self.open(&modified, modified_path);
+                    self.buffer_mut().read_only = readonly;
+                    self.buffer_mut().diff_base = Some(original.to_string());
+                    self.wait_for_buffer(wait);
+                    tasks.push(self.refresh_line_markers());
+                }
+
+                self.focus_pane(focus);
+                Task::batch(tasks)
+            }
+
+            Message::PaneClicked(pane) => {
+                if pane == self.focus || !self.focus_pane(pane) {
+                    return Task::none();
+                }
+                operation::focus(self.editor.id.clone())
+            }
+
+            Message::PaneDragged(pane_grid::DragEvent::Dropped { pane, target }) => {
+                self.panes.drop(pane, target);
+                Task::none()
+            }
+            Message::PaneDragged(_) => Task::none(),
+
+            Message::InPane(pane, message) => self.in_pane(pane, *message),
+
+            Message::InBuffer(buffer, message) => {
+                if buffer == self.editor.buffer {
+                    return self.update(*message);
+                }
+
+                let pane = self
+                    .editors()
+                    .find(|(_, editor)| editor.buffer == buffer)
+                    .map(|(pane, _)| pane);
+
+                // Nothing to do once the last view of the buffer is closed.
+                match pane {
+                    Some(pane) => self.in_pane(pane, *message),
+                    None => Task::none(),
+                }
+            }
+
+            Message::SplitPane(axis) => {
+                let editor = self.editor.split(
+                    self.text_size(),
+                    self.char_width(),
+                    self.settings.line_height,
+                );
+
+                let Some((pane, _)) =
+                    self.panes
+                        .split(axis, self.focus, PaneKind::Editor(Some(Box::new(editor))))
+                else {
+                    return Task::none();
+                };
+
+                self.panes.restore();
+                self.focus_pane(pane);
+                self.refresh_layout();
+                operation::focus(self.editor.id.clone())
+            }
+
+            Message::ClosePane => {
+                let pane = self.focus;
+                let buffer = self.editor.buffer;
+                let views = self
+                    .editors()
+                    .filter(|(_, editor)| editor.buffer == buffer)
+                    .count();
+                let untouched = self.buffer().path.is_none() && self.text().is_empty();
+
+                if views > 1 || !self.buffer().is_dirty || untouched {
+                    return self.close_pane(pane);
+                }
+
+                let name = self
+                    .buffer()
+                    .path
+                    .as_deref()
+                    .and_then(Path::file_name)
+                    .map_or_else(
+                        || String::from("New File"),
+                        |name| name.to_string_lossy().into_owned(),
+                    );
+
+                Task::perform(confirm_discard(name), move |confirmed| {
+                    Message::PaneCloseConfirmed(pane, confirmed)
+                })
+            }
+
+            Message::PaneCloseConfirmed(pane, confirmed) => {
+                if confirmed && self.panes.get(pane).is_some() {
+                    self.close_pane(pane)
+                } else {
+                    Task::none()
+                }
+            }
+
+            Message::ToggleMaximizedPane => {
+                if self.panes.maximized().is_some() {
+                    self.panes.restore();
+                } else {
+                    self.panes.maximize(self.focus);
+                }
+                Task::none()
+            }
+
+            Message::FocusPane(direction) => {
+                // Hidden panes can't take the focus.
+                if self.panes.maximized().is_some() {
+                    return Task::none();
+                }
+
+                let mut pane = self.focus;
+                while let Some(next) = self.panes.adjacent(pane, direction) {
+                    if self.focus_pane(next) {
+                        return operation::focus(self.editor.id.clone());
+                    }
+                    pane = next;
+                }
+                Task::none()
+            }
+
+            Message::OpenedFile(content) => match content {
+                Ok((text, path)) => {
+                    let split = self.split_if_unsaved();
+                    Task::batch([split, self.open(&text, path)])
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    Task::none()
+                }
+            },
+
+            Message::OpenedTreeFile(content) => match content {
+                Ok((text, path)) => {
+                    let split = self.split_if_unsaved();
+                    Task::batch([split, self.open(&text, path)])
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    Task::none()
+                }
+            },
+
+            Message::OpenFile => {
+                Task::perform(pick_file(self.dialog_directory()), Message::OpenedFile)
+            }
+
+            Message::OpenTreeFile(path) => Task::perform(read_file(path), Message::OpenedTreeFile),
+
+            Message::SaveFile => {
+                // Read-only buffers are saved as they are.
+                if !self.buffer().read_only
+                    && self.format_on_save()
+                    && let Some(task) = self.format(true)
+                {
+                    return task;
+                }
+
+                self.save()
+            }
+
+            Message::FormatDocument => match self.format(false) {
+                Some(task) => task,
+                None => self.notifications.push(
+                    notification::Level::Info,
+                    format!("No formatter configured for {}", self.language().name),
+                ),
+            },
+
+            Message::Formatted {
+                original,
+                result,
+                save,
+            } => {
+                let task = match result {
+                    // Edits made while the formatter ran win over its output, and so does
+                    // making the buffer read-only, which still lets the save go ahead.
+                    Ok(formatted) if self.text() == original && !self.buffer().read_only => {
+                        self.apply_formatting(&formatted)
+                    }
+                    Ok(_) => Task::none(),
+                    Err(Error::FormatterFailed(reason)) => self.notifications.push(
+                        notification::Level::Error,
+                        format!("Formatting failed: {reason}"),
+                    ),
+                    Err(_) => Task::none(),
+                };
+
+                if save {
+                    Task::batch([task, self.save()])
+                } else {
+                    task
+                }
+            }
+
+            Message::ToggleFormatOnSave => {
+                let format_on_save = !self.format_on_save();
+                self.format_on_save = Some(format_on_save);
+                let state = if format_on_save { "on" } else { "off" };
+                self.notifications.push(
+                    notification::Level::Info,
+                    format!("Format on save is {state}"),
+                )
+            }
+
+            Message::NotificationDismissed(id) => {
+                self.notifications.dismiss(id);
+                Task::none()
+            }
+
+            Message::OpenSettings => match settings::path(Scope::User, None) {
+                Some(path) => Task::perform(open_settings(path), Message::OpenedTreeFile),
+                None => Task::none(),
+            },
+
+            Message::SelectColorTheme => {
+                let current = self.appearance.theme.to_string();
+                let entries = theme::names(&self.themes)
+                    .into_iter()
+                    .map(|name| palette::Entry {
+                        detail: if name == current {
+                            String::from("current")
+                        } else {
+                            String::new()
+                        },
+                        message: Message::ColorThemeSelected(name.clone()),
+                        title: name,
+                    })
+                    .collect();
+
+                self.palette = Some(Palette::new("Select a color theme", entries));
+                operation::focus(palette::INPUT)
+            }
+
+            Message::SelectSyntaxTheme => {
+                let entries = highlighter::Theme::ALL
+                    .iter()
+                    .map(|&syntax_theme| palette::Entry {
+                        title: syntax_theme.to_string(),
+                        detail: if syntax_theme == self.appearance.syntax_theme {
+                            String::from("current")
+                        } else {
+                            String::new()
+                        },
+                        message: Message::SyntaxThemeSelected(syntax_theme),
+                    })
+                    .collect();
+
+                self.palette = Some(Palette::new("Select a syntax theme", entries));
+                operation::focus(palette::INPUT)
+            }
+
+            Message::ColorThemeSelected(name) => {
+                self.palette = None;
+                self.theme_override = Some(name);
+                // A syntax theme picked for the previous theme may not suit this one.
+                self.syntax_theme_override = None;
+                let appearance = self.refresh_appearance();
+
+                Task::batch([operation::focus(self.editor.id.clone()), appearance])
+            }
+
+            Message::SyntaxThemeSelected(syntax_theme) => {
+                self.palette = None;
+                self.syntax_theme_override = Some(syntax_theme);
+                let appearance = self.refresh_appearance();
+
+                Task::batch([operation::focus(self.editor.id.clone()), appearance])
+            }
+
+            Message::ReloadThemes => Task::perform(theme::load_all(), Message::ThemesLoaded),
+
+            Message::ThemesLoaded((themes, problems)) => {
+                self.themes = themes;
+                let appearance = self.refresh_appearance();
+
+                Task::batch(
+                    problems
+                        .into_iter()
+                        .map(|problem| self.notifications.push(notification::Level::Error, problem))
+                        .chain([appearance])
+                        .collect::<Vec<_>>(),
+                )
+            }
+
+            Message::SystemThemeChanged(mode) => {
+                self.system_mode = mode;
+                self.refresh_appearance()
+            }
+
+            Message::SelectEditorFont => {
+                let current = self.editor_font_name();
+                let entries = self
+                    .font_families
+                    .iter()
+                    .filter(|family| family.monospace)
+                    .map(|family| palette::Entry {
+                        title: family.name.clone(),
+                        detail: if Some(family.name.as_str()) == current {
+                            String::from("current")
+                        } else {
+                            String::new()
+                        },
+                        message: Message::EditorFontSelected(family.name.clone()),
+                    })
+                    .collect();
+
+                self.palette = Some(Palette::new("Select an editor font", entries));
+                operation::focus(palette::INPUT)
+            }
+
+            Message::EditorFontSelected(name) => {
+                self.palette = None;
+                self.editor_font_override = Some(name);
+                let fonts = self.refresh_fonts();
+
+                Task::batch([operation::focus(self.editor.id.clone()), fonts])
+            }
+
+            Message::FontsLoaded(families) => {
+                self.font_families = families;
+                self.refresh_fonts()
+            }
+
+            // Measured for a font picked since is stale.
+            Message::FontMeasured(font, advance) if font == self.editor_font => {
+                self.font_advance = advance;
+                self.measured_font = Some(font);
+                self.for_each_editor(Self::refresh_layout);
+                Task::none()
+            }
+            Message::FontMeasured(..) => Task::none(),
+
+            Message::ToggleLigatures => {
+                let ligatures = !self.ligatures.unwrap_or(self.settings.ligatures);
+                self.ligatures = Some(ligatures);
+                let state = if ligatures { "on" } else { "off" };
+
+                Task::batch([
+                    self.notifications
+                        .push(notification::Level::Info, format!("Ligatures are {state}")),
+                    self.refresh_fonts(),
+                ])
+            }
+
+            Message::ZoomIn => {
+                self.set_zoom(self.zoom + font::ZOOM_STEP);
+                Task::none()
+            }
+
+            Message::ZoomOut => {
+                self.set_zoom(self.zoom - font::ZOOM_STEP);
+                Task::none()
+            }
+
+            Message::ResetZoom => {
+                self.set_zoom(1.0);
+                Task::none()
+            }
+
+            Message::SettingsPolled => self.poll_settings(),
+
+            Message::SettingsLoaded(scope, path, result) => match result {
+                Ok((layer, problems)) => {
+                    let settings = self.settings.with(scope, *layer);
+                    let appearance = self.apply_settings(settings);
+
+                    Task::batch(
+                        problems
+                            .iter()
+                            .map(|problem| {
+                                self.notifications.push(
+                                    notification::Level::Error,
+                                    settings::describe(&path, problem),
+                                )
+                            })
+                            .chain([appearance])
+                            .collect::<Vec<_>>(),
+                    )
+                }
+                // Keep the previous settings until the file is fixed.
+                Err(problem) => self.notifications.push(
+                    notification::Level::Error,
+                    settings::describe(&path, &problem),
+                ),
+            },
+
+            Message::SavedFile(Ok((path, saved))) => self.saved(path, saved),
+            Message::SavedFile(Err(Error::IoError(ErrorKind::PermissionDenied))) => {
+                let buffer = self.editor.buffer;
+                let name = buffer_name(self.buffer());
+
+                // Files picked in the dialog are not known here, they can only go elsewhere.
+                let retries = privileged::available()
+                    .into_iter()
+                    .filter(|_| self.buffer().path.is_some())
+                    .map(|method| palette::Entry {
+                        title: String::from(method.title()),
+                        detail: String::new(),
+                        message: Message::InBuffer(
+                            buffer,
+                            Box::new(Message::SaveWithPrivileges(method)),
+                        ),
+                    });
+
+                let entries = retries
+                    .chain([palette::Entry {
+                        title: String::from("Save As..."),
+                        detail: String::new(),
+                        message: Message::InBuffer(buffer, Box::new(Message::SaveFileAs)),
+                    }])
+                    .collect();
+
+                self.palette = Some(Palette::new(
+                    "No permission to save, retry or save elsewhere",
+                    entries,
+                ));
+                Task::batch([
+                    operation::focus(palette::INPUT),
+                    self.notifications.push(
+                        notification::Level::Error,
+                        format!("No permission to save {name}"),
+                    ),
+                ])
+            }
+            Message::SavedFile(Err(error)) => {
+                self.error = Some(error);
+                Task::none()
+            }
+
+            Message::SaveWithPrivileges(method) => {
+                self.palette = None;
+                let Some(path) = self.buffer().path.clone() else {
+                    return Task::none();
+                };
+
+                let text = self.text();
+                let saved = hash(&text);
+                self.in_buffer(Task::perform(
+                    privileged::write(method, path, text),
+                    move |result| Message::SavedWithPrivileges(result.map(|path| (path, saved))),
+                ))
+            }
+            Message::SavedWithPrivileges(Ok((path, saved))) => {
+                let message = format!("Saved {} as administrator", path.display());
+                Task::batch([
+                    self.saved(path, saved),
+                    self.notifications.push(notification::Level::Info, message),
+                ])
+            }
+            Message::SavedWithPrivileges(Err(error)) => self.notifications.push(
+                notification::Level::Error,
+                format!("Could not save: {}", describe(&error)),
+            ),
+
+            // Files opened without permission to write them start out read-only.
+            Message::PermissionsChecked(writable) => {
+                if writable || self.buffer().read_only {
+                    return Task::none();
+                }
+
+                self.buffer_mut().read_only = true;
+                self.notifications.push(
+                    notification::Level::Info,
+                    format!(
+                        "{} is read-only, you have no permission to write it",
+                        buffer_name(self.buffer())
+                    ),
+                )
+            }
+
+            Message::ToggleReadOnly => {
+                let read_only = !self.buffer().read_only;
+                self.buffer_mut().read_only = read_only;
+
+                let name = buffer_name(self.buffer());
+                let message = if read_only {
+                    format!("{name} is read-only")
+                } else {
+                    format!("{name} can be edited")
+                };
+                self.notifications.push(notification::Level::Info, message)
+            }
+
+            Message::SaveFileAs => {
+                let text = self.text();
+                let saved = hash(&text);
+                self.in_buffer(Task::perform(
+                    save_file(None, text, self.dialog_directory()),
+                    move |result| Message::SavedFileAs(result.map(|path| (path, saved))),
+                ))
+            }
+            Message::SavedFileAs(Ok((path, saved))) => {
+                let message = format!("Saved as {}", path.display());
+                Task::batch([
+                    self.saved(path, saved),
+                    self.notifications.push(notification::Level::Info, message),
+                ])
+            }
+            Message::SavedFileAs(Err(Error::DialogClosed)) => Task::none(),
+            Message::SavedFileAs(Err(error)) => self.notifications.push(
+                notification::Level::Error,
+                format!("Could not save: {}", describe(&error)),
+            ),
+
+            // The buffer stays with its file, only the copy goes elsewhere.
+            Message::SaveCopy => Task::perform(
+                save_file(None, self.text(), self.dialog_directory()),
+                Message::SavedCopy,
+            ),
+            Message::SavedCopy(Ok(path)) => self.notifications.push(
+                notification::Level::Info,
+                format!("Saved a copy to {}", path.display()),
+            ),
+            Message::SavedCopy(Err(Error::DialogClosed)) => Task::none(),
+            Message::SavedCopy(Err(error)) => self.notifications.push(
+                notification::Level::Error,
+                format!("Could not save a copy: {}", describe(&error)),
+            ),
+
+            // Format on save is left to Save: formatting buffers out of view, or the one being
+            // typed in when auto-saving, would move their text without anyone looking.
+            Message::SaveAll => {
+                let files = self.unsaved_files();
+                if files.is_empty() && self.untitled_count() == 0 {
+                    return self
+                        .notifications
+                        .push(notification::Level::Info, "No unsaved changes");
+                }
+
+                Task::perform(write_files(files), Message::SavedAll)
+            }
+            Message::SavedAll(results) => {
+                let (saved, task) = self.saved_all(results);
+                let files = if saved == 1 {
+                    String::from("1 file")
+                } else {
+                    format!("{saved} files")
+                };
+
+                // New files are left to Save, which asks for their names one at a time.
+                let message = match self.untitled_count() {
+                    0 => format!("Saved {files}"),
+                    1 => format!("Saved {files}, 1 new file needs a name first"),
+                    untitled => format!("Saved {files}, {untitled} new files need a name first"),
+                };
+
+                Task::batch([
+                    task,
+                    self.notifications.push(notification::Level::Info, message),
+                ])
+            }
+
+            Message::RevertFile => match self.buffer().path.clone() {
+                Some(path) => self.in_buffer(Task::perform(read_file(path), Message::Reverted)),
+                None => Task::none(),
+            },
+            Message::Reverted(Ok((text, path))) => {
+                // Keeps the cursor on the same text, like formatting does.
+                let task = self.apply_formatting(&text);
+                self.buffer_mut().is_dirty = false;
+                self.detect_language();
+
+                Task::batch([
+                    task,
+                    self.refresh_line_markers(),
+                    self.notifications.push(
+                        notification::Level::Info,
+                        format!("Reverted {}", path.display()),
+                    ),
+                ])
+            }
+            Message::Reverted(Err(error)) => self.notifications.push(
+                notification::Level::Error,
+                format!("Could not revert: {}", describe(&error)),
+            ),
+
+            Message::AutoSaveDue(edits) if edits == self.edits => self.update(Message::AutoSave),
+            Message::AutoSaveDue(_) => Task::none(),
+            Message::AutoSave => {
+                let files = self.unsaved_files();
+                if files.is_empty() {
+                    return Task::none();
+                }
+
+                Task::perform(write_files(files), Message::AutoSaved)
+            }
+            // Quiet unless something could not be saved.
+            Message::AutoSaved(results) => self.saved_all(results).1,
+
+            Message::NewFile => {
+                let split = self.split_if_unsaved();
+                self.new_file();
+                split
+            }
+
+            Message::OpenDirectory => Task::perform(
+                pick_directory(self.dialog_directory()),
+                Message::OpenedDirectory,
+            ),
+
+            // The folder replaces the whole workspace.
+            Message::OpenedDirectory(dir_list) => match dir_list {
+                Ok((contents, path)) => {
+                    self.tree_content = vec![root_node(path.clone(), contents)];
+                    self.welcome = false;
+
+                    if self.workspace.roots == [path.clone()] {
+                        return Task::none();
+                    }
+
+                    self.workspace.roots = vec![path];
+                    self.workspace_changed()
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    Task::none()
+                }
+            },
+
+            Message::AddFolderToWorkspace => Task::perform(
+                pick_directory(self.dialog_directory()),
+                Message::AddedFolder,
+            ),
+
+            Message::AddedFolder(dir_list) => match dir_list {
+                Ok((contents, path)) => {
+                    self.welcome = false;
+
+                    if !self.workspace.add(path.clone()) {
+                        return Task::none();
+                    }
+
+                    self.tree_content.push(root_node(path, contents));
+                    self.workspace_changed()
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    Task::none()
+                }
+            },
+
+            Message::RemoveFolderFromWorkspace => {
+                if self.workspace.is_empty() {
+                    return self
+                        .notifications
+                        .push(notification::Level::Info, "There is no folder to remove");
+                }
+
+                let entries = self
+                    .workspace
+                    .roots
+                    .iter()
+                    .map(|root| palette::Entry {
+                        title: workspace::folder_name(root),
+                        detail: root.display().to_string(),
+                        message: Message::RemovedFolderSelected(root.clone()),
+                    })
+                    .collect();
+
+                self.palette = Some(Palette::new("Select a folder to remove", entries));
+                operation::focus(palette::INPUT)
+            }
+
+            Message::RemovedFolderSelected(root) => {
+                self.palette = None;
+
+                if !self.workspace.remove(&root) {
+                    return Task::none();
+                }
+
+                self.tree_content.retain(
+                    |node| !matches!(node, FileNode::Directory { path, .. } if *path == root),
+                );
+
+                Task::batch([
+                    operation::focus(self.editor.id.clone()),
+                    self.workspace_changed(),
+                ])
+            }
+
+            Message::WorkspaceRead(nodes) => {
+                self.tree_content = nodes;
+                Task::none()
+            }
+            Message::OpenChildDirectory(path) => {
+                if toggle_dir_expanded(&mut self.tree_content, &path) {
+                    Task::none()
+                } else {
+                    Task::perform(read_directory(path), Message::OpenedChildDirectory)
+                }
+            }
+
+            Message::OpenedChildDirectory(Ok((child_node, path))) => {
+                set_dir_children(&mut self.tree_content, &path, child_node);
+                Task::none()
+            }
+
+            Message::OpenedChildDirectory(Err(error)) => {
+                self.error = Some(error);
+                Task::none()
+            }
+
+            Message::LanguageSelected(language) => {
+                self.buffer_mut().language_override = Some(language);
+                self.language_changed();
+                Task::none()
+            }
+        }
+    }
+
+    // The full buffer, including lines hidden inside folds.
+    fn text(&self) -> String {
+        self.editor.folds.text(&self.editor.content)
+    }
+
+    fn detect_language(&mut self) {
+        self.buffer_mut().detected_language =
+            language::detect(self.buffer().path.as_deref(), &self.text());
+    }
+
+    // Refreshes what the language of the focused buffer decides and is not looked up on
+    // every use: the indentation and the folds.
+    fn language_changed(&mut self) {
+        self.detect_indentation();
+        self.refresh_fold_ranges();
+    }
+
+    fn save(&self) -> Task<Message> {
+        let text = self.text();
+        let saved = hash(&text);
+        self.in_buffer(Task::perform(
+            save_file(self.buffer().path.clone(), text, self.dialog_directory()),
+            move |result| Message::SavedFile(result.map(|path| (path, saved))),
+        ))
+    }
+
+    // Bookkeeping after the focused buffer was written to `path`. Edits made while it was
+    // written, which `saved` does not hash to, keep it unsaved.
+    fn saved(&mut self, path: PathBuf, saved: u64) -> Task<Message> {
+        self.buffer_mut().path = Some(path.clone());
+        if hash(&self.text()) == saved {
+            self.buffer_mut().is_dirty = false;
+        }
+
+        let language = self.language().name;
+        self.detect_language();
+        if self.language().name == language {
+            self.refresh_fold_ranges();
+        } else {
+            self.language_changed();
+        }
+
+        Task::batch([
+            self.refresh_line_markers(),
+            self.update(Message::RefreshGitStatus),
+            self.remember(recent::Item::File(path)),
+        ])
+    }
+
+    // The files with unsaved changes that can be saved without asking for a name.
+    fn unsaved_files(&self) -> Vec<(BufferId, PathBuf, String)> {
+        let mut seen = HashSet::new();
+
+        self.editors()
+            .filter(|(_, editor)| seen.insert(editor.buffer))
+            .filter_map(|(_, editor)| {
+                let buffer = &self.buffers[&editor.buffer];
+                let path = buffer.path.clone()?;
+
+                (buffer.is_dirty && !buffer.read_only).then(|| (editor.buffer, path, editor.text()))
+            })
+            .collect()
+    }
+
+    // New files with changes, which need a name before they can be saved.
+    fn untitled_count(&self) -> usize {
+        let mut seen = HashSet::new();
+
+        self.editors()
+            .filter(|(_, editor)| seen.insert(editor.buffer))
+            .filter(|(_, editor)| {
+                let buffer = &self.buffers[&editor.buffer];
+                buffer.is_dirty && buffer.path.is_none() && !editor.text().is_empty()
+            })
+            .count()
+    }
+
+    // Marks the written buffers as saved and reports those that could not be. Returns
+    // how many were saved.
+    fn saved_all(
+        &mut self,
+        results: Vec<(BufferId, PathBuf, Result<u64, Error>)>,
+    ) -> (usize, Task<Message>) {
+        let mut saved = 0;
+        let mut tasks = Vec::new();
+
+        for (buffer, path, result) in results {
+            let task = match result {
+                Ok(hash) => {
+                    saved += 1;
+                    self.update(Message::InBuffer(
+                        buffer,
+                        Box::new(Message::SavedFile(Ok((path, hash)))),
+                    ))
+                }
+                Err(error) => self.notifications.push(
+                    notification::Level::Error,
+                    format!("Could not save {}: {}", path.display(), describe(&error)),
+                ),
+            };
+            tasks.push(task);
+        }
+
+        (saved, Task::batch(tasks))
+    }
+
+    // Routes the messages of `task` to the focused buffer, wherever it is shown by then.
+    fn in_buffer(&self, task: Task<Message>) -> Task<Message> {
+        let buffer = self.editor.buffer;
+        task.map(move |message| Message::InBuffer(buffer, Box::new(message)))
+    }
+
+    // Pipes the buffer through the formatter of its language, `None` when there is none.
+    fn format(&self, save: bool) -> Option<Task<Message>> {
+        let language = self.language();
+        let formatter = self.settings.editor(&language).formatter?;
+        let original = self.text();
+
+        Some(self.in_buffer(Task::perform(
+            format::run(
+                formatter,
+                original.clone(),
+                self.buffer().path.clone(),
+                language,
+            ),
+            move |result| Message::Formatted {
+                original,
+                result,
+                save,
+            },
+        )))
+    }
+
+    // Replaces only the lines the formatter changed, so the cursor stays on the same text.
+    fn apply_formatting(&mut self, formatted: &str) -> Task<Message> {
+        let split = |text: &str| -> Vec<String> {
+            text.split('\n')
+                .map(|line| line.strip_suffix('\r').unwrap_or(line).to_owned())
+                .collect()
+        };
+
+        let old = split(&self.text());
+        let new = split(formatted);
+        let hunks = diff::lines(
+            &old.iter().map(String::as_str).collect::<Vec<_>>(),
+            &new.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+
+        if hunks.is_empty() {
+            return Task::none();
+        }
+
+        // Inner folds stay hidden inside their outer fold, only the outer ones come back.
+        let folded: Vec<usize> = (0..self.editor.content.line_count())
+            .filter(|&line| self.editor.folds.is_folded(line))
+            .map(|line| diff::map_line(&hunks, self.editor.folds.to_buffer_line(line)))
+            .collect();
+        self.editor.folds.unfold_all(&mut self.editor.content);
+
+        let cursor = self.editor.content.cursor();
+        let map = |position: Position| {
+            let line = diff::map_line(&hunks, position.line).min(new.len() - 1);
+            let length = new.get(line).map_or(0, |line| line.chars().count());
+
+            Position {
+                line,
+                column: position.column.min(length),
+            }
+        };
+        let mapped = text_editor::Cursor {
+            position: map(cursor.position),
+            selection: cursor.selection.map(map),
+        };
+
+        for (first, last, lines) in diff::replacements(&hunks, &old) {
+            self.replace_lines(first, last, &lines, cursor);
+        }
+
+        self.editor.content.move_to(mapped);
+
+        // Folds whose header line is still the start of a fold range are folded again.
+        self.refresh_fold_ranges();
+        for start in folded {
+            if let Some(range) = self
+                .buffer()
+                .fold_ranges
+                .iter()
+                .find(|range| range.start == start)
+                .copied()
+            {
+                self.editor.folds.fold(&mut self.editor.content, range);
+            }
+        }
+
+        self.edited()
+    }
+
+    // Bookkeeping after a command changed the buffer text.
+    fn edited(&mut self) -> Task<Message> {
+        self.buffer_mut().is_dirty = true;
+        self.refresh_fold_ranges();
+        self.sync_views();
+        Task::batch([self.scroll_to_cursor(), self.auto_save_later()])
+    }
+
+    // Saves once no other edit follows for the delay, when saving after a delay.
+    fn auto_save_later(&mut self) -> Task<Message> {
+        if self.settings.auto_save != AutoSave::AfterDelay {
+            return Task::none();
+        }
+
+        self.edits += 1;
+        let edits = self.edits;
+        Task::perform(
+            tokio::time::sleep(self.settings.auto_save_delay),
+            move |()| Message::AutoSaveDue(edits),
+        )
+    }
+
+    fn detect_indentation(&mut self) {
+        let settings = self.settings.editor(&self.language());
+
+        self.buffer_mut().indentation = settings
+            .detect_indentation
+            .then(|| indent::detect(&self.text()))
+            .flatten()
+            .unwrap_or(settings.indentation);
+    }
+
+    fn format_on_save(&self) -> bool {
+        self.format_on_save
+            .unwrap_or_else(|| self.settings.editor(&self.language()).format_on_save)
+    }
+
+    // Loads the settings of the new primary root right away and the changes of the new
+    // roots.
+    fn workspace_changed(&mut self) -> Task<Message> {
+        self.settings_modified.remove(&Scope::Project);
+
+        let remember = if self.workspace.is_empty() {
+            Task::none()
+        } else {
+            self.remember(recent::Item::Workspace(self.workspace.roots.clone()))
+        };
+        Task::batch([
+            self.poll_settings(),
+            self.update(Message::RefreshGitStatus),
+            remember,
+        ])
+    }
+
+    // Puts `item` on top of the recently opened.
+    fn remember(&mut self, item: recent::Item) -> Task<Message> {
+        self.recent.add(item);
+        Task::future(recent::save(self.recent.clone())).discard()
+    }
+
+    fn forget(&mut self, item: &recent::Item) -> Task<Message> {
+        self.recent.remove(item);
+        Task::future(recent::save(self.recent.clone())).discard()
+    }
+
+    fn forget_missing(&mut self) -> Task<Message> {
+        if self.recent.remove_missing() {
+            Task::future(recent::save(self.recent.clone())).discard()
+        } else {
+            Task::none()
+        }
+    }
+
+    // Whether the menus and the toolbar offer `command` now.
+    fn is_enabled(&self, command: Command) -> bool {
+        let buffer = self.buffer();
+
+        match command {
+            Command::SaveFile => buffer.is_dirty && !buffer.read_only,
+            // Checked every frame, so without building the text of every buffer.
+            Command::SaveAll => self.editors().any(|(_, editor)| {
+                let buffer = &self.buffers[&editor.buffer];
+                self.is_modified(editor) && !(buffer.read_only && buffer.path.is_some())
+            }),
+            Command::RevertFile => buffer.path.is_some(),
+            Command::RemoveFolderFromWorkspace => !self.workspace.is_empty(),
+            Command::OpenRecent | Command::PinRecent | Command::ClearRecent => {
+                !self.recent.is_empty()
+            }
+            Command::RecoverUnsavedChanges => !self.backups.is_empty(),
+            Command::ResetZoom => self.zoom != 1.0,
+            Command::FoldAtCursor
+            | Command::UnfoldAtCursor
+            | Command::FoldAll
+            | Command::UnfoldAll => !buffer.fold_ranges.is_empty(),
+            Command::ToggleMaximizedPane
+            | Command::FocusLeftPane
+            | Command::FocusRightPane
+            | Command::FocusAbovePane
+            | Command::FocusBelowPane => self.editors().nth(1).is_some(),
+            _ => !(buffer.read_only && command.message().is_edit()),
+        }
+    }
+
+    // Where file dialogs start: the folder of the focused file, else of the workspace.
+    fn dialog_directory(&self) -> PathBuf {
+        self.buffer()
+            .path
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(self.workspace.directory())
+            .to_owned()
+    }
+
+    // Reloads every settings file whose modification time changed since it was loaded.
+    fn poll_settings(&mut self) -> Task<Message> {
+        let mut tasks = Vec::new();
+
+        for scope in [Scope::User, Scope::Project] {
+            let Some(path) = settings::path(scope, self.workspace.primary()) else {
+                continue;
+            };
+
+            let modified = settings::modified(&path);
+            if self.settings_modified.insert(scope, modified) == Some(modified) {
+                continue;
+            }
+
+            tasks.push(Task::perform(settings::load(path.clone()), move |result| {
+                let result = result.map(|(layer, problems)| (Box::new(layer), problems));
+                Message::SettingsLoaded(scope, path.clone(), result)
+            }));
+        }
+
+        Task::batch(tasks)
+    }
+
+    // Switches to new settings. Options toggled at runtime are kept unless the setting
+    // behind them changed.
+    fn apply_settings(&mut self, settings: settings::Settings) -> Task<Message> {
+        let resize_sidebar = settings.explorer_width != self.settings.explorer_width;
+
+        if settings.minimap != self.settings.minimap {
+            self.show_minimap = settings.minimap;
+        }
+
+        if settings.toolbar != self.settings.toolbar {
+            self.show_toolbar = settings.toolbar;
+        }
+
+        if settings.relative_line_numbers != self.settings.relative_line_numbers {
+            self.line_numbers = if settings.relative_line_numbers {
+                LineNumbers::Relative
+            } else {
+                LineNumbers::Absolute
+            };
+        }
+
+        // The window was created with the UI font.
+        let restart = if settings.ui_font != self.settings.ui_font {
+            self.notifications.push(
+                notification::Level::Info,
+                "The UI font applies after a restart",
+            )
+        } else {
+            Task::none()
+        };
+
+        let previous = std::mem::replace(&mut self.settings, settings);
+        if resize_sidebar {
+            self.sidebar.width = None;
+            self.resize_sidebar();
+        }
+
+        let languages: HashMap<&str, Language> = self
+            .buffers
+            .values()
+            .map(|buffer| (buffer.language().name, buffer.language()))
+            .collect();
+        self.view_options.retain(|name, _| {
+            languages.get(name).is_some_and(|language| {
+                previous.editor(language).view_options
+                    == self.settings.editor(language).view_options
+            })
+        });
+        if languages.values().any(|language| {
+            previous.editor(language).format_on_save
+                != self.settings.editor(language).format_on_save
+        }) {
+            self.format_on_save = None;
+        }
+
+        if (&previous.theme, &previous.light_theme, &previous.dark_theme)
+            != (
+                &self.settings.theme,
+                &self.settings.light_theme,
+                &self.settings.dark_theme,
+            )
+            || previous.follow_system_theme != self.settings.follow_system_theme
+        {
+            self.theme_override = None;
+        }
+        if previous.syntax_theme != self.settings.syntax_theme {
+            self.syntax_theme_override = None;
+        }
+        if previous.font != self.settings.font {
+            self.editor_font_override = None;
+        }
+        if previous.ligatures != self.settings.ligatures {
+            self.ligatures = None;
+        }
+
+        // An indentation picked from the status bar stays, unless its settings changed.
+        self.for_each_editor(|xeditor| {
+            let language = xeditor.language();
+            let before = previous.editor(&language);
+            let after = xeditor.settings.editor(&language);
+            if (before.indentation, before.detect_indentation)
+                != (after.indentation, after.detect_indentation)
+            {
+                xeditor.detect_indentation();
+            }
+            xeditor.refresh_layout();
+        });
+
+        Task::batch([self.refresh_appearance(), self.refresh_fonts(), restart])
+    }
+
+    fn editor_font_name(&self) -> Option<&str> {
+        self.editor_font_override
+            .as_deref()
+            .or(self.settings.font.as_deref())
+    }
+
+    // Resolves the configured font names against the installed fonts. Missing fonts fall
+    // back to the defaults with a notification, nothing is checked until the installed
+    // fonts are loaded.
+    fn refresh_fonts(&mut self) -> Task<Message> {
+        let ligatures = self.ligatures.unwrap_or(self.settings.ligatures);
+        let mut missing = Vec::new();
+
+        let mut resolve = |name: Option<&str>| {
+            let name = name?;
+            if self.font_families.is_empty() {
+                return Some(name.to_owned());
+            }
+
+            match font::find(&self.font_families, name) {
+                Some(family) => Some(family.to_owned()),
+                None => {
+                    missing.push(name.to_owned());
+                    None
+                }
+            }
+        };
+
+        let editor = resolve(self.editor_font_name());
+        let icons = resolve(self.settings.icon_font.as_deref());
+        // The UI font is only checked, the window was created with it.
+        resolve(self.settings.ui_font.as_deref());
+
+        self.editor_font = match editor {
+            Some(name) if !ligatures => {
+                font::named(font::without_ligatures(&self.font_families, &name).unwrap_or(&name))
+            }
+            Some(name) => font::named(&name),
+            None => Font::MONOSPACE,
+        };
+        self.icon_font = font::named(icons.as_deref().unwrap_or(font::ICONS));
+
+        // The gutter, rulers, whitespace and wrapping are laid out with the font's advance.
+        let font = self.editor_font;
+        let name = match font.family {
+            iced::font::Family::Name(name) => Some(name.to_owned()),
+            _ => None,
+        };
+        let measure = if self.measured_font == Some(font) {
+            Task::none()
+        } else {
+            Task::perform(font::measure(name), move |advance| {
+                Message::FontMeasured(font, advance)
+            })
+        };
+
+        Task::batch(
+            missing
+                .into_iter()
+                .map(|name| {
+                    self.notifications.push(
+                        notification::Level::Error,
+                        format!("Font `{name}` is not installed, using the default"),
+                    )
+                })
+                .chain([measure])
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    // Advance of a glyph of the editor font at the text size.
+    fn char_width(&self) -> f32 {
+        self.text_size() * self.font_advance
+    }
+
+    fn text_size(&self) -> f32 {
+        if self.settings.zoom_scales_ui {
+            self.settings.font_size
+        } else {
+            self.settings.font_size * self.zoom
+        }
+    }
+
+    fn scale_factor(&self) -> f32 {
+        if self.settings.zoom_scales_ui {
+            self.zoom
+        } else {
+            1.0
+        }
+    }
+
+    fn set_zoom(&mut self, zoom: f32) {
+        // Rounded so repeated steps land on whole percentages again.
+        self.zoom = ((zoom / font::ZOOM_STEP).round() * font::ZOOM_STEP)
+            .clamp(font::MIN_ZOOM, font::MAX_ZOOM);
+        self.for_each_editor(Self::refresh_layout);
+    }
+
+    // The color theme in use: picked in the palette, or from the settings.
+    fn theme_name(&self) -> &str {
+        if let Some(name) = &self.theme_override {
+            return name;
+        }
+
+        if self.settings.follow_system_theme {
+            return match self.system_mode {
+                Mode::Light => &self.settings.light_theme,
+                Mode::Dark | Mode::None => &self.settings.dark_theme,
+            };
+        }
+
+        &self.settings.theme
+    }
+
+    // Resolves the theme and the colors derived from it. Unknown names fall back to the
+    // default theme with a notification.
+    fn refresh_appearance(&mut self) -> Task<Message> {
+        let name = self.theme_name().to_owned();
+        let (mut appearance, task) = match Appearance::find(&name, &self.themes) {
+            Some(appearance) => (appearance, Task::none()),
+            None => (
+                Appearance::default(),
+                self.notifications.push(
+                    notification::Level::Error,
+                    format!("Unknown color theme `{name}`"),
+                ),
+            ),
+        };
+
+        if let Some(syntax_theme) = self.syntax_theme_override.or(self.settings.syntax_theme) {
+            appearance.syntax_theme = syntax_theme;
+        }
+
+        if let Some(border) = self.settings.border_color {
+            appearance.tokens.border = border;
+        }
+
+        self.appearance = appearance;
+        self.for_each_editor(|xeditor| xeditor.editor.minimap_cache.clear());
+        task
+    }
+
+    // Enter keeps the indentation of the current line, one level deeper after an opening
+    // bracket. Between a pair of brackets the closing one moves to its own line.
+    fn insert_newline(&mut self) {
+        let Position { line, column } = self.editor.content.cursor().position;
+        let text = self
+            .editor
+            .content
+            .line(line)
+            .map(|line| line.text.into_owned())
+            .unwrap_or_default();
+
+        let before: String = text.chars().take(column).collect();
+        let after: String = text.chars().skip(column).collect();
+        let language = self.language();
+
+        let base = indent::leading(&before).to_owned();
+        let opens_block = indent::opens_block(&before, &language);
+        let closes_block = opens_block
+            && after.trim_start().chars().next().is_some_and(|c| {
+                language
+                    .block_delimiters
+                    .iter()
+                    .any(|&(open, close)| close == c && before.trim_end().ends_with(open))
+            });
+
+        let mut indentation = base.clone();
+        if opens_block {
+            indentation.push_str(&self.buffer().indentation.unit());
+        }
+
+        let perform =
+            |this: &mut Self, action| this.editor.folds.perform(&mut this.editor.content, action);
+
+        perform(self, text_editor::Action::Edit(text_editor::Edit::Enter));
+        perform(
+            self,
+            text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(indentation))),
+        );
+
+        if closes_block {
+            perform(
+                self,
+                text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(format!("\n{base}")))),
+            );
+            perform(self, text_editor::Action::Move(text_editor::Motion::Up));
+            perform(self, text_editor::Action::Move(text_editor::Motion::End));
+        }
+    }
+
+    // Typing steps over a closing character that is already there, closes pairs, and
+    // lines a closing bracket on a blank line up with its opening bracket.
+    fn insert_char(&mut self, c: char) {
+        let Position { line, column } = self.editor.content.cursor().position;
+        let language = self.language();
+        let is_closing = language
+            .block_delimiters
+            .iter()
+            .any(|&(_, close)| close == c);
+
+        let text: Vec<char> = self
+            .editor
+            .content
+            .line(line)
+            .map(|line| line.text.chars().collect())
+            .unwrap_or_default();
+        let before: String = text.iter().take(column).collect();
+        let previous = column
+            .checked_sub(1)
+            .and_then(|column| text.get(column).copied());
+        let next = text.get(column).copied();
+
+        if bracket::overtypes(&language, c, next) {
+            self.editor.folds.perform(
+                &mut self.editor.content,
+                text_editor::Action::Move(text_editor::Motion::Right),
+            );
+            return;
+        }
+
+        if is_closing && column > 0 && before.trim().is_empty() {
+            let lines: Vec<String> = self
+                .editor
+                .content
+                .lines()
+                .take(line)
+                .map(|line| line.text.into_owned())
+                .collect();
+
+            let indentation = match indent::opening_line(&lines, line, c, &language) {
+                Some(opening) => indent::leading(&lines[opening]).to_owned(),
+                None => indent::outdent_line(&before, self.buffer().indentation),
+            };
+
+            self.editor.content.move_to(text_editor::Cursor {
+                position: Position { line, column: 0 },
+                selection: Some(Position { line, column }),
+            });
+            self.editor.folds.perform(
+                &mut self.editor.content,
+                text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(indentation))),
+            );
+        }
+
+        self.editor.folds.perform(
+            &mut self.editor.content,
+            text_editor::Action::Edit(text_editor::Edit::Insert(c)),
+        );
+
+        if let Some(close) = bracket::auto_close(&language, c, previous, next) {
+            self.editor.folds.perform(
+                &mut self.editor.content,
+                text_editor::Action::Edit(text_editor::Edit::Insert(close)),
+            );
+            self.editor.folds.perform(
+                &mut self.editor.content,
+                text_editor::Action::Move(text_editor::Motion::Left),
+            );
+        }
+    }
+
+    // Backspace between the two sides of an empty pair removes both.
+    fn backspace(&mut self) {
+        let cursor = self.editor.content.cursor();
+
+        if cursor.selection.is_none() {
+            let Position { line, column } = cursor.position;
+            let text: Vec<char> = self
+                .editor
+                .content
+                .line(line)
+                .map(|line| line.text.chars().collect())
+                .unwrap_or_default();
+            let previous = column
+                .checked_sub(1)
+                .and_then(|column| text.get(column).copied());
+
+            if bracket::is_empty_pair(&self.language(), previous, text.get(column).copied()) {
+                self.editor.folds.perform(
+                    &mut self.editor.content,
+                    text_editor::Action::Edit(text_editor::Edit::Delete),
+                );
+            }
+        }
+
+        self.editor.folds.perform(
+            &mut self.editor.content,
+            text_editor::Action::Edit(text_editor::Edit::Backspace),
+        );
+    }
+
+    // Wraps the selection in `open` and `close`, keeping the wrapped text selected.
+    fn surround(&mut self, open: char, close: char) {
+        self.selected_lines();
+
+        let cursor = self.editor.content.cursor();
+        let (Some(anchor), Some(selection)) = (cursor.selection, self.editor.content.selection())
+        else {
+            return;
+        };
+        let (start, end) = ordered(anchor, cursor.position);
+
+        self.editor.folds.perform(
+            &mut self.editor.content,
+            text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(format!(
+                "{open}{selection}{close}"
+            )))),
+        );
+
+        self.editor.content.move_to(text_editor::Cursor {
+            position: Position {
+                line: end.line,
+                column: end.column + usize::from(end.line == start.line),
+            },
+            selection: Some(Position {
+                line: start.line,
+                column: start.column + 1,
+            }),
+        });
+    }
+
+    // Lines touched by the selection with every fold inside them opened. A selection
+    // ending at the start of a line does not include that line.
+    fn selected_lines(&mut self) -> (usize, usize) {
+        loop {
+            let cursor = self.editor.content.cursor();
+            let anchor = cursor.selection.unwrap_or(cursor.position);
+            let (start, end) = ordered(anchor, cursor.position);
+
+            let last = if end.column == 0 && end.line > start.line {
+                end.line - 1
+            } else {
+                end.line
+            };
+
+            let line_count = self.editor.content.line_count();
+            self.editor
+                .folds
+                .unfold_lines(&mut self.editor.content, start.line, last);
+
+            if self.editor.content.line_count() == line_count {
+                return (start.line, last);
+            }
+        }
+    }
+
+    fn lines(&self, first: usize, last: usize) -> Vec<String> {
+        self.editor
+            .content
+            .lines()
+            .skip(first)
+            .take(last + 1 - first)
+            .map(|line| line.text.into_owned())
+            .collect()
+    }
+
+    // Replaces lines `first..=last` with `lines` and moves the cursor to `cursor` afterwards.
+    fn replace_lines(
+        &mut self,
+        first: usize,
+        last: usize,
+        lines: &[String],
+        cursor: text_editor::Cursor,
+    ) {
+        self.editor.replace_lines(first, last, lines);
+
+        let last = self.editor.content.line_count().saturating_sub(1);
+        let clamp = |position: Position| Position {
+            line: position.line.min(last),
+            column: position.column,
+        };
+
+        self.editor.content.move_to(text_editor::Cursor {
+            position: clamp(cursor.position),
+            selection: cursor.selection.map(clamp),
+        });
+    }
+
+    // Replaces lines `first..=last` with the same number of lines produced by `edit`,
+    // keeping the cursor and selection on the same text. The range must not contain folds.
+    // Returns whether `edit` changed anything, the lines are left alone otherwise.
+    fn edit_lines(
+        &mut self,
+        first: usize,
+        last: usize,
+        edit: impl FnOnce(&[String]) -> Vec<String>,
+    ) -> bool {
+        let old = self.lines(first, last);
+        let new = edit(&old);
+        if new == old {
+            return false;
+        }
+
+        let shift = |position: Position| {
+            if !(first..=last).contains(&position.line) {
+                return position;
+            }
+
+            let index = position.line - first;
+            let old_length = old[index].chars().count() as isize;
+            let new_length = new[index].chars().count() as isize;
+
+            Position {
+                line: position.line,
+                column: position
+                    .column
+                    .saturating_add_signed(new_length - old_length)
+                    .min(new_length as usize),
+            }
+        };
+
+        let cursor = self.editor.content.cursor();
+        let cursor = text_editor::Cursor {
+            position: shift(cursor.position),
+            selection: cursor.selection.map(shift),
+        };
+
+        self.replace_lines(first, last, &new, cursor);
+        true
+    }
+
+    // Replaces the selection with `text` and selects what was inserted.
+    fn replace_selection(&mut self, text: String) {
+        let cursor = self.editor.content.cursor();
+        let start = cursor
+            .selection
+            .map_or(cursor.position, |anchor| ordered(anchor, cursor.position).0);
+
+        let rows: Vec<&str> = text.split('\n').collect();
+        let last = rows
+            .last()
+            .map(|row| row.chars().count())
+            .unwrap_or_default();
+        let end = Position {
+            line: start.line + rows.len() - 1,
+            column: if rows.len() == 1 {
+                start.column + last
+            } else {
+                last
+            },
+        };
+
+        self.editor.folds.perform(
+            &mut self.editor.content,
+            text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(text))),
+        );
+        self.editor.content.move_to(text_editor::Cursor {
+            position: end,
+            selection: Some(start),
+        });
+    }
+
+    // Swaps the selected lines with the line above or below them.
+    fn move_lines(&mut self, up: bool) {
+        let (first, last) = self.selected_lines();
+        let neighbor = if up {
+            first.checked_sub(1)
+        } else {
+            Some(last + 1).filter(|&line| line < self.editor.content.line_count())
+        };
+
+        let Some(neighbor) = neighbor else {
+            return;
+        };
+
+        // A folded neighbor is opened, lines move past it one at a time.
+        self.editor
+            .folds
+            .unfold_lines(&mut self.editor.content, neighbor, neighbor);
+        let (first, last) = self.selected_lines();
+
+        let (range_first, range_last) = if up {
+            (first - 1, last)
+        } else {
+            (first, last + 1)
+        };
+
+        let mut lines = self.lines(range_first, range_last);
+        if up {
+            lines.rotate_left(1);
+        } else {
+            lines.rotate_right(1);
+        }
+
+        let shift = |position: Position| Position {
+            line: if up {
+                position.line - 1
+            } else {
+                position.line + 1
+            },
+            column: position.column,
+        };
+        let cursor = self.editor.content.cursor();
+
+        self.replace_lines(
+            range_first,
+            range_last,
+            &lines,
+            text_editor::Cursor {
+                position: shift(cursor.position),
+                selection: cursor.selection.map(shift),
+            },
+        );
+    }
+
+    fn toggle_block_comment(&mut self, delimiters: (&str, &str)) {
+        if self.editor.content.selection().is_some() {
+            self.selected_lines();
+            let text = self.editor.content.selection().unwrap_or_default();
+            self.replace_selection(lines::toggle_block_comment(&text, delimiters));
+            return;
+        }
+
+        let line = self.editor.content.cursor().position.line;
+        self.edit_lines(line, line, |lines| {
+            lines
+                .iter()
+                .map(|line| {
+                    let leading = indent::leading(line);
+                    if line.trim().is_empty() {
+                        line.clone()
+                    } else {
+                        let rest = lines::toggle_block_comment(&line[leading.len()..], delimiters);
+                        format!("{leading}{rest}")
+                    }
+                })
+                .collect()
+        });
+    }
+
+    // Called whenever the buffer text changes.
+    fn refresh_fold_ranges(&mut self) {
+        self.buffer_mut().fold_ranges = fold::ranges(&self.text(), &self.language());
+        self.refresh_layout();
+    }
+
+    // Called whenever the display lines, their wrapping or the viewport size change.
+    fn refresh_layout(&mut self) {
+        self.editor.line_layout = LineLayout::new(
+            &self.editor.content,
+            self.wrap_columns(),
+            self.text_size(),
+            self.char_width(),
+            self.settings.line_height,
+        );
+        self.editor.minimap_cache.clear();
+    }
+
+    fn view_options(&self) -> ViewOptions {
+        self.view_options_for(&self.language())
+    }
+
+    fn view_options_for(&self, language: &Language) -> ViewOptions {
+        self.view_options
+            .get(language.name)
+            .cloned()
+            .unwrap_or_else(|| self.settings.editor(language).view_options)
+    }
+
+    fn view_options_mut(&mut self) -> &mut ViewOptions {
+        let language = self.language();
+        self.view_options
+            .entry(language.name)
+            .or_insert_with(|| self.settings.editor(&language).view_options)
+    }
+
+    fn wrap_columns(&self) -> Option<usize> {
+        match self.view_options().soft_wrap {
+            SoftWrap::Off => None,
+            SoftWrap::Column(column) => Some(usize::from(column)),
+            SoftWrap::Viewport => self.editor.viewport.map(|viewport| {
+                let width = viewport.bounds().width - 2.0 * layout::PADDING;
+                (width / self.char_width()).floor().max(1.0) as usize
+            }),
+        }
+    }
+
+    fn refresh_line_markers(&self) -> Task<Message> {
+        if let Some(base) = &self.buffer().diff_base {
+            let markers = diff::line_markers(base, &self.text());
+            return self.in_buffer(Task::done(Message::LineMarkersLoaded(markers)));
+        }
+
+        match &self.buffer().path {
+            Some(path) => self.in_buffer(Task::perform(
+                git::line_changes(path.clone()),
+                Message::LineMarkersLoaded,
+            )),
+            None => Task::none(),
+        }
+    }
+
+    // Selects whole display lines from `anchor` to `line`, in either direction.
+    fn select_lines(&mut self, anchor: usize, line: usize) {
+        let last = self.editor.content.line_count().saturating_sub(1);
+        let line_end = |line: usize| {
+            if line < last {
+                Position {
+                    line: line + 1,
+                    column: 0,
+                }
+            } else {
+                Position {
+                    line: last,
+                    column: self
+                        .editor
+                        .content
+                        .line(last)
+                        .map(|line| line.text.chars().count())
+                        .unwrap_or_default(),
+                }
+            }
+        };
+        let line_start = |line: usize| Position { line, column: 0 };
+
+        let (anchor, position) = if line >= anchor {
+            (line_start(anchor.min(last)), line_end(line.min(last)))
+        } else {
+            (line_end(anchor.min(last)), line_start(line))
+        };
+
+        self.editor.content.move_to(text_editor::Cursor {
+            position,
+            selection: Some(anchor),
+        });
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        // Shortcuts are bound in the editor; this catches them while it is not focused.
+        let shortcuts = event::listen_with(|event, status, _window| match event {
+            iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. })
+                if status == event::Status::Ignored =>
+            {
+                command::find(&key, modifiers).map(Command::message)
+            }
+            _ => None,
+        });
+
+        // The gutter only sees releases over its own rows, so listen globally while dragging.
+        let gutter_drag = if self.editor.gutter_drag.is_some() {
+            event::listen_with(|event, _status, _window| match event {
+                iced::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                    Some(Message::GutterReleased)
+                }
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        // The palette input captures these keys, so listen regardless of the status.
+        let palette = if self.palette.is_some() {
+            event::listen_with(|event, _status, _window| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(key),
+                    ..
+                }) => match key {
+                    keyboard::key::Named::ArrowUp => Some(Message::PaletteMoved(-1)),
+                    keyboard::key::Named::ArrowDown => Some(Message::PaletteMoved(1)),
+                    keyboard::key::Named::Escape => Some(Message::PaletteClosed),
+                    _ => None,
+                },
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        // Settings files are polled so edits from any editor apply once saved.
+        let settings = iced::time::every(Duration::from_secs(1)).map(|_| Message::SettingsPolled);
+
+        let menu = if self.menu.is_some() {
+            event::listen_with(|event, _status, _window| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(keyboard::key::Named::Escape),
+                    ..
+                }) => Some(Message::MenuClosed),
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        // Escape leaves zen mode, unless it closes the palette.
+        let zen = if self.sidebar.zen && self.palette.is_none() {
+            event::listen_with(|event, _status, _window| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(keyboard::key::Named::Escape),
+                    ..
+                }) => Some(Message::ToggleZenMode),
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        let auto_save = if self.settings.auto_save == AutoSave::OnFocusLoss {
+            event::listen_with(|event, _status, _window| match event {
+                iced::Event::Window(iced::window::Event::Unfocused) => Some(Message::AutoSave),
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        // The desktop switching between light and dark, also while the theme does not follow
+        // it, so turning `follow_system_theme` on picks the current one.
+        let system_theme = iced::system::theme_changes().map(Message::SystemThemeChanged);
+
+        let close_requests = iced::window::close_requests().map(Message::WindowCloseRequested);
+        let resize_events =
+            iced::window::resize_events().map(|(_window, size)| Message::WindowResized(size));
+
+        // Saved now and then too, so a crash loses little.
+        let session = if self.session.is_some() {
+            iced::time::every(SESSION_INTERVAL).map(|_| Message::SaveSession)
+        } else {
+            Subscription::none()
+        };
+
+        // Unsaved buffers are copied aside often, a crash loses only the last seconds.
+        let backups = iced::time::every(BACKUP_INTERVAL).map(|_| Message::BackupBuffers);
+
+        let clients = match &self.server {
+            Some(server) => Subscription::run_with(server.clone(), ipc::serve)
+                .map(|(client, requests)| Message::ClientRequested(client, requests)),
+            None => Subscription::none(),
+        };
+
+        Subscription::batch([
+            shortcuts,
+            gutter_drag,
+            palette,
+            menu,
+            zen,
+            settings,
+            auto_save,
+            system_theme,
+            close_requests,
+            resize_events,
+            session,
+            backups,
+            clients,
+        ])
+    }
+
+    fn minimap<'a>(&'a self, editor: &'a Editor, buffer: &'a Buffer) -> Element<'a, Message> {
+        let markers = buffer
+            .line_markers
+            .iter()
+            .map(|&(line, marker)| minimap::Tick {
+                line: editor.folds.to_display_line(line),
+                color: marker.color(self.appearance.tokens),
+            });
+        let searching = self.sidebar.visible
+            && self.sidebar.panel == Panel::Search
+            && !self.search_query.is_empty();
+        let matches = if searching {
+            minimap::query_matches(&editor.content, &self.search_query)
+        } else {
+            minimap::selection_matches(&editor.content)
+        };
+        let matches = matches.into_iter().map(|line| minimap::Tick {
+            line,
+            color: self.appearance.tokens.search_match,
+        });
+
+        canvas(Minimap {
+            content: &editor.content,
+            cache: &editor.minimap_cache,
+            viewport: editor.viewport.as_ref(),
+            layout: &editor.line_layout,
+            ticks: markers.chain(matches).collect(),
+            tokens: self.appearance.tokens,
+        })
+        .width(minimap::WIDTH)
+        .height(Fill)
+        .into()
+    }
+
+    fn scroll_to_cursor(&self) -> Task<Message> {
+        let Some(viewport) = self.editor.viewport else {
+            return Task::none();
+        };
+
+        let Position { line, column } = self.editor.content.cursor().position;
+        let offset = viewport.absolute_offset();
+        let bounds = viewport.bounds();
+
+        let top = self.editor.line_layout.line_top(line);
+        let bottom = top + self.editor.line_layout.line_height(line);
+
+        let y = if top < offset.y {
+            Some(top - layout::PADDING)
+        } else if bottom > offset.y + bounds.height {
+            Some(bottom + layout::PADDING - bounds.height)
+        } else {
+            None
+        };
+
+        // Only unwrapped lines can leave the viewport horizontally.
+        let x = if self.view_options().soft_wrap == SoftWrap::Off {
+            let text = self
+                .editor
+                .content
+                .line(line)
+                .map(|line| line.text)
+                .unwrap_or_default();
+            let before: String = text.chars().take(column).collect();
+            let char_width = self.editor.line_layout.char_width();
+            let left = layout::PADDING + layout::columns(&before) as f32 * char_width;
+            let right = left + char_width;
+
+            if left < offset.x {
+                Some(left - layout::PADDING)
+            } else if right > offset.x + bounds.width {
+                Some(right + layout::PADDING - bounds.width)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        if x.is_none() && y.is_none() {
+            return Task::none();
+        }
+
+        operation::scroll_to(
+            self.editor.scrollable.clone(),
+            operation::AbsoluteOffset {
+                x: x.map(|x| x.max(0.0)),
+                y: y.map(|y| y.max(0.0)),
+            },
+        )
+    }
+
+    // The text editor with its gutter and everything drawn underneath it.
+    fn editor_view<'a>(&'a self, editor: &'a Editor, buffer: &'a Buffer) -> Element<'a, Message> {
+        let language = buffer.language();
+        let options = self.view_options_for(&language);
+        let viewport_width = editor
+            .viewport
+            .map(|viewport| viewport.bounds().width)
+            .unwrap_or_default();
+
+        let char_width = editor.line_layout.char_width();
+
+        let width = match options.soft_wrap {
+            // Wide enough for the longest line and the cursor after it.
+            SoftWrap::Off => Some(
+                ((editor.line_layout.max_columns() + 1) as f32 * char_width
+                    + 2.0 * layout::PADDING)
+                    .max(viewport_width),
+            ),
+            SoftWrap::Viewport => None,
+            SoftWrap::Column(column) => {
+                Some(f32::from(column) * char_width + 2.0 * layout::PADDING)
+            }
+        };
+
+        let mut text_editor = text_editor(&editor.content)
+            .id(editor.id.clone())
+            .placeholder(PLACEHOLDER_TEXT)
+            .min_height(
+                editor
+                    .viewport
+                    .map(|viewport| viewport.bounds().height)
+                    .unwrap_or_default(),
+            )
+            .wrapping(if options.soft_wrap == SoftWrap::Off {
+                Wrapping::None
+            } else {
+                Wrapping::WordOrGlyph
+            })
+            .on_action(Message::ActionPerformed)
+            .style(|theme, status| text_editor::Style {
+                background: Color::TRANSPARENT.into(),
+                ..text_editor::default(theme, status)
+            })
+            .font(self.editor_font)
+            .size(self.text_size())
+            .line_height(self.settings.line_height)
+            .highlight(language.token, self.appearance.syntax_theme)
+            .key_binding(|key_press| {
+                // The subscription handles shortcuts while the editor is not focused.
+                if !matches!(key_press.status, text_editor::Status::Focused { .. }) {
+                    return None;
+                }
+
+                if key_press.key == keyboard::Key::Named(keyboard::key::Named::Tab)
+                    && !key_press.modifiers.command()
+                {
+                    return Some(text_editor::Binding::Custom(
+                        if key_press.modifiers.shift() {
+                            Message::Outdent
+                        } else {
+                            Message::Indent
+                        },
+                    ));
+                }
+
+                match command::find(&key_press.key, key_press.modifiers) {
+                    Some(command) => Some(text_editor::Binding::Custom(command.message())),
+                    None => text_editor::Binding::from_key_press(key_press),
+                }
+            });
+
+        if let Some(width) = width {
+            text_editor = text_editor.width(width);
+        }
+
+        let line_count = editor.content.line_count();
+        let cursor_line = editor.content.cursor().position.line;
+        let visible = editor.line_layout.visible_lines(editor.viewport.as_ref());
+
+        let gutter = gutter::view(
+            &editor.line_layout,
+            editor
+                .viewport
+                .map(|viewport| viewport.absolute_offset().y)
+                .unwrap_or_default(),
+            visible.clone(),
+            cursor_line,
+            self.line_numbers,
+            editor.folds.to_buffer_line(line_count.saturating_sub(1)) + 1,
+            self.appearance.tokens,
+            self.icon_font,
+            |line| {
+                let number = editor.folds.to_buffer_line(line);
+                gutter::Line {
+                    number,
+                    fold: editor.fold_marker(line, &buffer.fold_ranges),
+                    marker: buffer.line_marker(number),
+                }
+            },
+        );
+
+        let mut layers = stack![text_editor];
+
+        if options.show_whitespace {
+            layers = layers.push_under(
+                canvas(Whitespace {
+                    content: &editor.content,
+                    layout: &editor.line_layout,
+                    visible,
+                    wrap_columns: editor.line_layout.wrap_columns(),
+                    tokens: self.appearance.tokens,
+                })
+                .width(Fill)
+                .height(Fill),
+            );
+        }
+
+        if options.show_rulers {
+            layers = layers.push_under(rulers(&options.rulers, char_width, self.appearance.tokens));
+        }
+
+        if let Some((bracket, other)) =
+            bracket::matching(&editor.content, editor.content.cursor().position, &language)
+        {
+            let bounds = [bracket, other].map(|position| {
+                let text = editor
+                    .content
+                    .line(position.line)
+                    .map(|line| line.text.into_owned())
+                    .unwrap_or_default();
+                editor
+                    .line_layout
+                    .glyph_bounds(position.line, &text, position.column)
+            });
+
+            layers = layers.push_under(bracket::highlight(bounds, self.appearance.tokens));
+        }
+
+        let layers = layers.push_under(gutter::current_line_highlight(
+            &editor.line_layout,
+            cursor_line,
+            self.appearance.tokens,
+        ));
+
+        let direction = if options.soft_wrap == SoftWrap::Off {
+            scrollable::Direction::Both {
+                vertical: scrollable::Scrollbar::default(),
+                horizontal: scrollable::Scrollbar::default(),
+            }
+        } else {
+            scrollable::Direction::default()
+        };
+
+        let scrollable = scrollable(layers)
+            .id(editor.scrollable.clone())
+            .direction(direction)
+            .on_scroll(Message::EditorScrolled)
+            .width(Fill)
+            .height(Fill);
+
+        row![gutter, scrollable].into()
+    }
+
+    fn theme(&self) -> Theme {
+        self.appearance.theme.clone()
+    }
+
+    fn language(&self) -> Language {
+        self.buffer().language()
+    }
+
+    fn buffer(&self) -> &Buffer {
+        &self.buffers[&self.editor.buffer]
+    }
+
+    fn buffer_mut(&mut self) -> &mut Buffer {
+        self.buffers
+            .get_mut(&self.editor.buffer)
+            .expect("every editor shows an open buffer")
+    }
+
+    // Replaces the editor panes and the explorer with those of `session`. Panes start out
+    // empty and show their file once it is read.
+    fn restore(&mut self, session: Session) -> Task<Message> {
+        fn configuration(
+            panes: &session::Panes,
+            editor: &mut impl FnMut() -> PaneKind,
+        ) -> pane_grid::Configuration<PaneKind> {
+            match panes {
+                session::Panes::Split { axis, ratio, a, b } => pane_grid::Configuration::Split {
+                    axis: *axis,
+                    ratio: *ratio,
+                    a: Box::new(configuration(a, editor)),
+                    b: Box::new(configuration(b, editor)),
+                },
+                session::Panes::Editor(_) => pane_grid::Configuration::Pane(editor()),
+            }
+        }
+
+        let text_size = self.text_size();
+        let char_width = self.char_width();
+        let line_height = self.settings.line_height;
+        let indentation = self.settings.editor(&language::PLAIN_TEXT).indentation;
+
+        let mut placeholder = || {
+            let buffer = BufferId::unique();
+            self.buffers
+                .insert(buffer, Buffer::new(None, language::PLAIN_TEXT, indentation));
+            PaneKind::Editor(Some(Box::new(Editor::new(
+                buffer,
+                "",
+                text_size,
+                char_width,
+                line_height,
+            ))))
+        };
+
+        self.panes =
+            pane_grid::State::with_configuration(configuration(&session.panes, &mut placeholder));
+
+        let mut order = Vec::new();
+        let _ = self.session_panes(self.panes.layout(), &mut order);
+
+        let focus = order.get(session.focus).or(order.first()).copied();
+        if let Some(focus) = focus
+            && let Some(PaneKind::Editor(parked)) = self.panes.get_mut(focus)
+            && let Some(editor) = parked.take()
+        {
+            self.editor = *editor;
+            self.focus = focus;
+        }
+        self.drop_unused_buffers();
+
+        if self.sidebar.visible {
+            self.show_sidebar();
+        }
+        self.session = Some(session.name.clone());
+
+        self.welcome = false;
+
+        let workspace = if self.workspace.roots == session.roots {
+            Task::none()
+        } else {
+            self.workspace.roots = session.roots.clone();
+            self.workspace_changed()
+        };
+        let tree = Task::perform(
+            read_workspace(session.roots.clone(), session.expanded.clone()),
+            Message::WorkspaceRead,
+        );
+
+        let files = order
+            .into_iter()
+            .zip(session.panes.views())
+            .filter_map(|(pane, view)| {
+                let path = view.path.clone()?;
+                let view = view.clone();
+
+                Some(Task::perform(read_file(path), move |result| {
+                    Message::InPane(
+                        pane,
+                        Box::new(Message::SessionFileRead(view.clone(), result)),
+                    )
+                }))
+            });
+
+        Task::batch(files.chain([workspace, tree, operation::focus(self.editor.id.clone())]))
+    }
+
+    // The session as it is now, `None` when sessions are not saved.
+    fn session(&self) -> Option<Session> {
+        let name = self.session.clone()?;
+        let mut order = Vec::new();
+        let panes = self.session_panes(self.panes.layout(), &mut order)?;
+
+        Some(Session {
+            name,
+            roots: self.workspace.roots.clone(),
+            expanded: expanded_directories(&self.tree_content),
+            window: self.window_size,
+            panes,
+            focus: order
+                .iter()
+                .position(|&pane| pane == self.focus)
+                .unwrap_or(0),
+        })
+    }
+
+    // The editor panes below `node`, collecting them in `order` from left to right and top
+    // to bottom.
+    fn session_panes(
+        &self,
+        node: &pane_grid::Node,
+        order: &mut Vec<pane_grid::Pane>,
+    ) -> Option<session::Panes> {
+        match node {
+            pane_grid::Node::Split {
+                axis, ratio, a, b, ..
+            } => match (self.session_panes(a, order), self.session_panes(b, order)) {
+                (Some(a), Some(b)) => Some(session::Panes::Split {
+                    axis: *axis,
+                    ratio: *ratio,
+                    a: Box::new(a),
+                    b: Box::new(b),
+                }),
+                // The other side is the sidebar.
+                (a, b) => a.or(b),
+            },
+            pane_grid::Node::Pane(pane) => {
+                let Some(PaneKind::Editor(parked)) = self.panes.get(*pane) else {
+                    return None;
+                };
+                let editor = parked.as_deref().unwrap_or(&self.editor);
+                let cursor = editor.content.cursor().position;
+                order.push(*pane);
+
+                Some(session::Panes::Editor(session::View {
+                    path: self.buffers[&editor.buffer].path.clone(),
+                    cursor: Position {
+                        line: editor.folds.to_buffer_line(cursor.line),
+                        column: cursor.column,
+                    },
+                    scroll: editor
+                        .viewport
+                        .map_or(0.0, |viewport| viewport.absolute_offset().y),
+                }))
+            }
+        }
+    }
+
+    // Opens what the command line asks for. Files, standard input and both `--diff` files
+    // are shown side by side, each in a pane of its own. Those waited for tell `waiter`
+    // once they are closed.
+    fn open_requests(&mut self, requests: Vec<cli::Request>, waiter: Waiter) -> Task<Message> {
+        let count: usize = requests
+            .iter()
+            .map(|request| match request {
+                cli::Request::Open { .. } | cli::Request::OpenText { .. } => 1,
+                cli::Request::Diff { .. } => 2,
+                cli::Request::OpenFolder(_) | cli::Request::Focus => 0,
+            })
+            .sum();
+
+        // Registered up front, so the waiter hears back even when nothing could be opened.
+        if requests.iter().any(cli::Request::waits) {
+            self.waiting_for(&waiter);
+        }
+
+        let first = self.focus;
+        let mut panes = Vec::new();
+        let mut tasks = Vec::new();
+        if count > 0 {
+            self.welcome = false;
+            // Another editor's files never replace unsaved changes.
+            tasks.push(self.split_if_unsaved());
+            panes.push(self.focus);
+        }
+        for _ in 1..count {
+            tasks.push(self.update(Message::SplitPane(pane_grid::Axis::Vertical)));
+            panes.push(self.focus);
+        }
+        self.focus_pane(panes.first().copied().unwrap_or(first));
+        let first = self.focus;
+        let mut panes = panes.into_iter();
+
+        for request in requests {
+            let wait = request.waits().then(|| waiter.clone());
+
+            match request {
+                cli::Request::Open {
+                    target, readonly, ..
+                } => {
+                    let Some(pane) = panes.next() else {
+                        continue;
+                    };
+
+                    if let Some(waiter) = &wait {
+                        self.waiting_for(waiter).reading += 1;
+                    }
+                    tasks.push(Task::perform(
+                        read_file(target.path.clone()),
+                        move |result| Message::CommandLineFileRead {
+                            pane,
+                            target: target.clone(),
+                            readonly,
+                            wait: wait.clone(),
+                            result,
+                        },
+                    ));
+                }
+                cli::Request::OpenText { text, readonly, .. } => {
+                    let Some(pane) = panes.next() else {
+                        continue;
+                    };
+
+                    self.focus_pane(pane);
+                    self.new_file();
+                    self.editor.reset(self.editor.buffer, &text);
+                    self.detect_language();
+                    self.detect_indentation();
+                    self.refresh_fold_ranges();
+                    self.buffer_mut().read_only = readonly;
+                    self.wait_for_buffer(wait);
+                    self.focus_pane(first);
+                }
+                cli::Request::Diff {
+                    paths: (a, b),
+                    readonly,
+                    ..
+                } => {
+                    let (Some(left), Some(right)) = (panes.next(), panes.next()) else {
+                        continue;
+                    };
+
+                    if let Some(waiter) = &wait {
+                        self.waiting_for(waiter).reading += 1;
+                    }
+
+                    tasks.push(Task::perform(read_files([a, b]), move |result| {
+                        Message::CommandLineDiffRead {
+                            panes: (left, right),
+                            readonly,
+                            wait: wait.clone(),
+                            result,
+                        }
+                    }));
+                }
+                // The folders given at startup already are roots.
+                cli::Request::OpenFolder(path) if self.workspace.roots.contains(&path) => {}
+                cli::Request::OpenFolder(path) => {
+                    tasks.push(Task::perform(read_directory(path), Message::AddedFolder));
+                }
+                cli::Request::Focus => {
+                    tasks.push(iced::window::latest().and_then(iced::window::gain_focus));
+                }
+            }
+        }
+
+        Task::batch(tasks)
+    }
+
+    // Shows a file read for the command line in the focused pane.
+    fn open_command_line_file(
+        &mut self,
+        target: cli::Target,
+        readonly: bool,
+        wait: Option<Waiter>,
+        result: Result<(Arc<String>, PathBuf), Error>,
+    ) -> Task<Message> {
+        let task = match result {
+            Ok((text, path)) => self.open(&text, path),
+            // A new file, created when saved.
+            Err(Error::IoError(ErrorKind::NotFound)) => self.open("", target.path),
+            Err(error) => {
+                return self.notifications.push(
+                    notification::Level::Error,
+                    format!(
+                        "Could not open {}: {}",
+                        target.path.display(),
+                        describe(&error)
+                    ),
+                );
+            }
+        };
+
+        self.buffer_mut().read_only = readonly;
+        self.wait_for_buffer(wait);
+
+        let Some(position) = target.position else {
+            return task;
+        };
+        self.move_cursor(position);
+
+        // The viewport is not known yet, so the line goes near the top.
+        let line = self.editor.content.cursor().position.line;
+        let top = self.editor.line_layout.line_top(line.saturating_sub(5));
+        let scroll = operation::scroll_to(
+            self.editor.scrollable.clone(),
+            operation::AbsoluteOffset {
+                x: None,
+                y: Some(top),
+            },
+        );
+        Task::batch([task, scroll])
+    }
+
+    // Registers the focused buffer with `waiter`, if any.
+    fn wait_for_buffer(&mut self, waiter: Option<Waiter>) {
+        let Some(waiter) = waiter else {
+            return;
+        };
+        let buffer = self.editor.buffer;
+
+        let waiting = self.waiting_for(&waiter);
+        waiting.buffers.insert(buffer);
+        waiting.opened = true;
+    }
+
+    fn waiting_for(&mut self, waiter: &Waiter) -> &mut Waiting {
+        match self
+            .waiting
+            .iter()
+            .position(|waiting| waiting.waiter == *waiter)
+        {
+            Some(index) => &mut self.waiting[index],
+            None => {
+                self.waiting.push(Waiting {
+                    waiter: waiter.clone(),
+                    buffers: HashSet::new(),
+                    reading: 0,
+                    opened: false,
+                });
+                self.waiting.last_mut().unwrap()
+            }
+        }
+    }
+
+    // Counts a file read for `waiter` as done, whether it could be opened or not.
+    fn finish_reading(&mut self, waiter: Option<&Waiter>) {
+        if let Some(waiting) = waiter.and_then(|waiter| {
+            self.waiting
+                .iter_mut()
+                .find(|waiting| waiting.waiter == *waiter)
+        }) {
+            waiting.reading = waiting.reading.saturating_sub(1);
+        }
+    }
+
+    // Tells those waiting for buffers once all of theirs are closed, by closing their panes
+    // or showing other files in them, or once none of their files could be opened. This
+    // editor exits when it was waiting itself for files it opened.
+    fn finish_waiting(&mut self) -> Task<Message> {
+        if self.waiting.is_empty() {
+            return Task::none();
+        }
+
+        let mut tasks = Vec::new();
+        self.waiting.retain_mut(|waiting| {
+            waiting
+                .buffers
+                .retain(|buffer| self.buffers.contains_key(buffer));
+            if !waiting.buffers.is_empty() || waiting.reading > 0 {
+                return true;
+            }
+
+            match &waiting.waiter {
+                Waiter::Editor if waiting.opened => tasks.push(close_window()),
+                Waiter::Editor => {}
+                Waiter::Client(client) => {
+                    tasks.push(Task::future(client.clone().reply(ipc::Reply::Closed)).discard())
+                }
+            }
+            false
+        });
+
+        Task::batch(tasks)
+    }
+
+    // Moves the cursor to a buffer position, kept inside the text.
+    fn move_cursor(&mut self, position: Position) {
+        let last = self.editor.content.line_count().saturating_sub(1);
+        let line = position.line.min(last);
+        let length = self
+            .editor
+            .content
+            .line(line)
+            .map_or(0, |line| line.text.chars().count());
+
+        self.editor.content.move_to(text_editor::Cursor {
+            position: Position {
+                line,
+                column: position.column.min(length),
+            },
+            selection: None,
+        });
+    }
+
+    // The unsaved buffers worth keeping, empty untitled ones are not.
+    fn snapshots(&self) -> Vec<backup::Snapshot> {
+        let mut seen = HashSet::new();
+
+        self.editors()
+            .filter(|(_, editor)| seen.insert(editor.buffer))
+            .filter_map(|(_, editor)| {
+                let buffer = &self.buffers[&editor.buffer];
+                let text = editor.text();
+
+                (buffer.is_dirty && (buffer.path.is_some() || !text.is_empty())).then(|| {
+                    backup::Snapshot {
+                        buffer: editor.buffer,
+                        path: buffer.path.clone(),
+                        text,
+                    }
+                })
+            })
+            .collect()
+    }
+
+    // Splits the focused pane when its buffer has unsaved changes no other pane shows, so
+    // showing something else in the new pane does not drop them.
+    fn split_if_unsaved(&mut self) -> Task<Message> {
+        let untouched = self.buffer().path.is_none() && self.text().is_empty();
+        let buffer = self.editor.buffer;
+        let views = self
+            .editors()
+            .filter(|(_, editor)| editor.buffer == buffer)
+            .count();
+
+        if self.buffer().is_dirty && !untouched && views == 1 {
+            self.update(Message::SplitPane(pane_grid::Axis::Vertical))
+        } else {
+            Task::none()
+        }
+    }
+
+    // Whether closing every buffer would lose changes.
+    fn has_unsaved_changes(&self) -> bool {
+        self.editors().any(|(_, editor)| self.is_modified(editor))
+    }
+
+    // Whether the buffer of `editor` has changes worth saving. New files only count once
+    // something was typed.
+    fn is_modified(&self, editor: &Editor) -> bool {
+        let buffer = &self.buffers[&editor.buffer];
+        buffer.is_dirty && (buffer.path.is_some() || !editor.content.is_empty())
+    }
+
+    // The files with unsaved changes, for marking them in the explorer.
+    fn modified_paths(&self) -> HashSet<&Path> {
+        self.buffers
+            .values()
+            .filter(|buffer| buffer.is_dirty)
+            .filter_map(|buffer| buffer.path.as_deref())
+            .collect()
+    }
+
+    // Like "● main.rs — xeditor — crate", the dot while the focused file has unsaved
+    // changes.
+    fn title(&self) -> String {
+        let workspace = if self.workspace.is_empty() {
+            String::new()
+        } else {
+            format!(" — {}", workspace::name(&self.workspace.roots))
+        };
+
+        if self.welcome {
+            return format!("xeditor{workspace}");
+        }
+
+        let dot = if self.is_modified(&self.editor) {
+            "● "
+        } else {
+            ""
+        };
+
+        format!("{dot}{} — xeditor{workspace}", buffer_name(self.buffer()))
+    }
+
+    // The sidebar pane and the split next to it, while it is shown.
+    fn sidebar_pane(&self) -> Option<(pane_grid::Pane, pane_grid::Split)> {
+        fn find(node: &pane_grid::Node, sidebar: pane_grid::Pane) -> Option<pane_grid::Split> {
+            match node {
+                pane_grid::Node::Split { id, a, b, .. } => {
+                    if [a, b].iter().any(
+                        |child| matches!(***child, pane_grid::Node::Pane(pane) if pane == sidebar),
+                    ) {
+                        Some(*id)
+                    } else {
+                        find(a, sidebar).or_else(|| find(b, sidebar))
+                    }
+                }
+                pane_grid::Node::Pane(_) => None,
+            }
+        }
+
+        let (&pane, _) = self
+            .panes
+            .iter()
+            .find(|(_, kind)| matches!(kind, PaneKind::Sidebar))?;
+
+        find(self.panes.layout(), pane).map(|split| (pane, split))
+    }
+
+    // Docks the sidebar to its side of the whole window.
+    fn show_sidebar(&mut self) {
+        self.sidebar.visible = true;
+        if self.sidebar_pane().is_some() {
+            return;
+        }
+
+        let Some((pane, _)) =
+            self.panes
+                .split(pane_grid::Axis::Vertical, self.focus, PaneKind::Sidebar)
+        else {
+            return;
+        };
+
+        let edge = match self.sidebar.side {
+            Side::Left => pane_grid::Edge::Left,
+            Side::Right => pane_grid::Edge::Right,
+        };
+        self.panes.drop(pane, pane_grid::Target::Edge(edge));
+        self.resize_sidebar();
+    }
+
+    fn hide_sidebar(&mut self) {
+        self.sidebar.visible = false;
+        if let Some((pane, _)) = self.sidebar_pane() {
+            self.panes.close(pane);
+        }
+    }
+
+    fn resize_sidebar(&mut self) {
+        let width = self.sidebar.width.unwrap_or(self.settings.explorer_width);
+
+        if let Some((_, split)) = self.sidebar_pane() {
+            let ratio = match self.sidebar.side {
+                Side::Left => width,
+                Side::Right => 1.0 - width,
+            };
+            self.panes.resize(split, ratio);
+        }
+    }
+
+    // Every editor pane with its editor, the focused one included.
+    fn editors(&self) -> impl Iterator<Item = (pane_grid::Pane, &Editor)> {
+        self.panes.iter().filter_map(|(&pane, kind)| match kind {
+            PaneKind::Editor(parked) => Some((pane, parked.as_deref().unwrap_or(&self.editor))),
+            PaneKind::Sidebar => None,
+        })
+    }
+
+    // Moves the focus to `pane`, swapping its editor in. Returns `false` when `pane` is not
+    // an editor pane.
+    fn focus_pane(&mut self, pane: pane_grid::Pane) -> bool {
+        if pane == self.focus {
+            return true;
+        }
+
+        let Some(PaneKind::Editor(parked)) = self.panes.get_mut(pane) else {
+            return false;
+        };
+        let Some(editor) = parked.take() else {
+            return false;
+        };
+
+        let previous = std::mem::replace(&mut self.editor, *editor);
+        // The previous pane is gone when it was just closed.
+        if let Some(PaneKind::Editor(parked)) = self.panes.get_mut(self.focus) {
+            *parked = Some(Box::new(previous));
+        }
+
+        self.focus = pane;
+        true
+    }
+
+    // Runs `message` as if `pane` had the focus. The previous pane gets the focus back,
+    // unless the message moved it on, like splitting does.
+    fn in_pane(&mut self, pane: pane_grid::Pane, message: Message) -> Task<Message> {
+        self.in_pane_with(pane, |xeditor| xeditor.update(message))
+    }
+
+    fn in_pane_with(
+        &mut self,
+        pane: pane_grid::Pane,
+        f: impl FnOnce(&mut Self) -> Task<Message>,
+    ) -> Task<Message> {
+        let previous = self.focus;
+        if !self.focus_pane(pane) {
+            return Task::none();
+        }
+
+        let task = f(self);
+
+        if self.focus == pane || self.panes.get(pane).is_none() {
+            self.focus_pane(previous);
+        }
+        task
+    }
+
+    // Runs `f` with each editor pane focused in turn.
+    fn for_each_editor(&mut self, mut f: impl FnMut(&mut Self)) {
+        let focus = self.focus;
+        let panes: Vec<_> = self.editors().map(|(pane, _)| pane).collect();
+
+        for pane in panes {
+            self.focus_pane(pane);
+            f(self);
+        }
+        self.focus_pane(focus);
+    }
+
+    // Brings the other views of the focused buffer up to date after it was edited.
+    fn sync_views(&mut self) {
+        let buffer = self.editor.buffer;
+        let focus = self.focus;
+        let siblings: Vec<_> = self
+            .editors()
+            .filter(|&(pane, editor)| pane != focus && editor.buffer == buffer)
+            .map(|(pane, _)| pane)
+            .collect();
+
+        if siblings.is_empty() {
+            return;
+        }
+
+        let text = self.text();
+        for pane in siblings {
+            self.focus_pane(pane);
+            self.editor.sync(&text, &self.buffers[&buffer].fold_ranges);
+            self.refresh_layout();
+        }
+        self.focus_pane(focus);
+    }
+
+    // Never closes the last editor pane, unless the editor waits for it.
+    fn close_pane(&mut self, pane: pane_grid::Pane) -> Task<Message> {
+        if self.editors().filter(|&(other, _)| other != pane).count() == 0 {
+            let waiter = self
+                .waiting
+                .iter()
+                .find(|waiting| waiting.buffers.contains(&self.editor.buffer))
+                .map(|waiting| &waiting.waiter);
+
+            return match waiter {
+                // Closing what `--wait` waits for ends the editor, like it would in a
+                // terminal.
+                Some(Waiter::Editor) => close_window(),
+                // Another editor waits for the buffer, the pane stays with a new one.
+                Some(Waiter::Client(_)) => {
+                    self.new_file();
+                    Task::none()
+                }
+                None => Task::none(),
+            };
+        }
+
+        if pane == self.focus {
+            let neighbour = [
+                pane_grid::Direction::Left,
+                pane_grid::Direction::Up,
+                pane_grid::Direction::Right,
+                pane_grid::Direction::Down,
+            ]
+            .into_iter()
+            .filter_map(|direction| self.panes.adjacent(pane, direction))
+            .find(|&other| matches!(self.panes.get(other), Some(PaneKind::Editor(_))));
+
+            let next = neighbour.or_else(|| {
+                self.editors()
+                    .map(|(other, _)| other)
+                    .find(|&other| other != pane)
+            });
+
+            if let Some(next) = next {
+                self.focus_pane(next);
+            }
+        }
+
+        self.panes.close(pane);
+        self.drop_unused_buffers();
+        operation::focus(self.editor.id.clone())
+    }
+
+    // Shows a new untitled buffer in the focused pane, in place of what it showed.
+    fn new_file(&mut self) {
+        let buffer = BufferId::unique();
+        let mut untitled = Buffer::new(
+            None,
+            language::PLAIN_TEXT,
+            self.settings.editor(&language::PLAIN_TEXT).indentation,
+        );
+        untitled.is_dirty = true;
+
+        self.buffers.insert(buffer, untitled);
+        self.editor.reset(buffer, "");
+        self.drop_unused_buffers();
+        self.refresh_layout();
+        self.welcome = false;
+    }
+
+    fn drop_unused_buffers(&mut self) {
+        let shown: HashSet<BufferId> = self.editors().map(|(_, editor)| editor.buffer).collect();
+        self.buffers.retain(|buffer, _| shown.contains(buffer));
+    }
+
+    // Shows a file read from disk in the focused pane. A file open in another pane shares
+    // its buffer, unsaved changes included.
+    fn open(&mut self, text: &str, path: PathBuf) -> Task<Message> {
+        let focus = self.focus;
+        let shown = self
+            .editors()
+            .find(|&(pane, editor)| {
+                pane != focus && self.buffers[&editor.buffer].path.as_ref() == Some(&path)
+            })
+            .map(|(_, editor)| (editor.buffer, editor.text()));
 
-            Message::OpenedChildDirectory(Err(error)) => {
-                self.error = Some(error);
-                Task::none()
-            }
+        self.welcome = false;
+        let remember = self.remember(recent::Item::File(path.clone()));
+
+        if let Some((buffer, text)) = shown {
+            self.editor.reset(buffer, &text);
+            self.drop_unused_buffers();
+            self.refresh_layout();
+            return remember;
         }
+
+        let buffer = BufferId::unique();
+        let indentation = self.settings.editor(&language::PLAIN_TEXT).indentation;
+        self.buffers.insert(
+            buffer,
+            Buffer::new(Some(path.clone()), language::PLAIN_TEXT, indentation),
+        );
+        self.editor.reset(buffer, text);
+        self.drop_unused_buffers();
+        self.detect_language();
+        self.detect_indentation();
+        self.refresh_fold_ranges();
+
+        let permissions = self.in_buffer(Task::perform(
+            is_writable(path),
+            Message::PermissionsChecked,
+        ));
+        Task::batch([self.refresh_line_markers(), remember, permissions])
+    }
+
+    fn title_bar<'a>(
+        &'a self,
+        pane: pane_grid::Pane,
+        editor: &'a Editor,
+        is_maximized: bool,
+    ) -> pane_grid::TitleBar<'a, Message> {
+        let tokens = self.appearance.tokens;
+        let name = buffer_name(&self.buffers[&editor.buffer]);
+        let name = if self.is_modified(editor) {
+            format!("● {name}")
+        } else {
+            name
+        };
+        let color = if pane == self.focus {
+            tokens.text
+        } else {
+            tokens.text_muted
+        };
+
+        let control = |label: &'a str, message: Message| {
+            button(text(label).size(13))
+                .on_press(Message::InPane(pane, Box::new(message)))
+                .padding([0, 6])
+                .style(button::text)
+        };
+
+        let controls = row![
+            control("│", Message::SplitPane(pane_grid::Axis::Vertical)),
+            control("─", Message::SplitPane(pane_grid::Axis::Horizontal)),
+            control(
+                if is_maximized { "▣" } else { "□" },
+                Message::ToggleMaximizedPane
+            ),
+            control("×", Message::ClosePane),
+        ]
+        .spacing(2);
+
+        pane_grid::TitleBar::new(text(name).size(13).color(color))
+            .controls(pane_grid::Controls::new(controls))
+            .padding([2, 8])
     }
 
     fn view(&self) -> Element<'_, Message> {
+        let tokens = self.appearance.tokens;
         let border = Border {
             width: 1.0,
-            color: Color::from_rgb8(69, 71, 90),
+            color: tokens.border,
             radius: border::Radius {
                 top_left: 5.0,
                 top_right: 5.0,
@@ -244,23 +4727,55 @@ impl Xeditor {
             },
         };
 
-        let grid = pane_grid(&self.panes, |_, kind, _is_maximized| match kind {
-            PaneKind::Explorer => {
-                let mut tree_column = column![text("EXPLORER").size(12)];
-                tree_column = tree_column.spacing(4);
-                tree_column = tree_column.extend(render_tree_nodes(&self.tree_content, 0));
+        let grid = pane_grid(&self.panes, |pane, kind, is_maximized| match kind {
+            PaneKind::Sidebar => {
+                let panel = match self.sidebar.panel {
+                    Panel::Explorer if self.workspace.is_empty() => column![
+                        text("EXPLORER").size(12),
+                        text("No folder is open").size(13).color(tokens.text_muted),
+                        button(text("Open Folder").size(13))
+                            .on_press(Message::OpenDirectory)
+                            .padding([4, 8]),
+                    ]
+                    .spacing(8)
+                    .into(),
+                    Panel::Explorer => {
+                        let mut tree_column = column![text("EXPLORER").size(12)];
+                        tree_column = tree_column.spacing(4);
+                        tree_column = tree_column.extend(render_tree_nodes(
+                            &self.tree_content,
+                            0,
+                            &self.modified_paths(),
+                            self.icon_font,
+                            tokens,
+                        ));
+                        tree_column.into()
+                    }
+                    Panel::Search => sidebar::search_view(
+                        &self.search_query,
+                        sidebar::search(&self.text(), &self.search_query),
+                        tokens,
+                    ),
+                    Panel::SourceControl => {
+                        sidebar::source_control_view(&self.workspace, &self.git_status, tokens)
+                    }
+                    Panel::Outline => sidebar::outline_view(
+                        sidebar::outline(&self.text(), &self.buffer().fold_ranges),
+                        tokens,
+                    ),
+                };
 
-                let tree_area = container(column![tree_column])
+                let tree_area = container(panel)
                     .width(Fill)
                     .padding(10)
                     .height(Fill)
                     .clip(true)
                     .style(move |_theme| container::Style {
-                        text_color: Some(Color::WHITE),
-                        background: Some(Theme::CatppuccinMocha.base().background_color.into()),
+                        text_color: Some(tokens.text),
+                        background: Some(tokens.background.into()),
                         border,
                         shadow: iced::Shadow {
-                            color: Color::from_rgb8(30, 32, 48),
+                            color: tokens.shadow,
                             offset: iced::Vector { x: 0.5, y: 1.0 },
                             blur_radius: 3.0,
                         },
@@ -269,106 +4784,266 @@ impl Xeditor {
 
                 pane_grid::Content::new(tree_area)
             }
-            PaneKind::Editor => {
-                let editor_area = text_editor(&self.content)
-                    .placeholder(PLACEHOLDER_TEXT)
-                    .height(Fill)
-                    .on_action(Message::ActionPerformed)
-                    .highlight(
-                        self.path
-                            .as_ref()
-                            .and_then(|path| path.extension())
-                            .and_then(|ext| ext.to_str())
-                            .unwrap_or("rs"),
-                        highlighter::Theme::Base16Mocha,
-                    )
-                    .key_binding(|key_press| match key_press.key.as_ref() {
-                        keyboard::Key::Character("s") if key_press.modifiers.command() => {
-                            Some(text_editor::Binding::Custom(Message::SaveFile))
-                        }
-                        keyboard::Key::Character("o") if key_press.modifiers.command() => {
-                            if key_press.modifiers.shift() {
-                                Some(text_editor::Binding::Custom(Message::OpenDirectory))
-                            } else {
-                                Some(text_editor::Binding::Custom(Message::OpenFile))
-                            }
-                        }
-                        keyboard::Key::Character("n") if key_press.modifiers.command() => {
-                            Some(text_editor::Binding::Custom(Message::NewFile))
-                        }
-                        _ => text_editor::Binding::from_key_press(key_press),
-                    });
-
-                let editor_container = container(editor_area).width(Fill).height(Fill);
+            PaneKind::Editor(parked) => {
+                let editor = parked.as_deref().unwrap_or(&self.editor);
+                let buffer = &self.buffers[&editor.buffer];
 
-                let status_bar = {
-                    let status = if let Some(Error::IoError(error)) = self.error {
-                        text(error.to_string())
-                    } else {
-                        match self.path.as_deref().and_then(Path::to_str) {
-                            Some(path) => text(path).size(14),
-                            None => text("New File"),
-                        }
-                    };
+                if self.welcome && pane == self.focus {
+                    return pane_grid::Content::new(welcome::view(&self.recent, tokens));
+                }
 
-                    let position = {
-                        let Position { line, column } = self.content.cursor().position;
-                        text(format!("Ln {}, Col {}", line + 1, column + 1))
-                            .width(FillPortion(1))
-                            .size(16)
-                            .align_x(Alignment::End)
-                    };
-                    row![status, position]
+                let body = if self.show_minimap {
+                    row![
+                        self.editor_view(editor, buffer),
+                        self.minimap(editor, buffer)
+                    ]
+                    .into()
+                } else {
+                    self.editor_view(editor, buffer)
                 };
 
-                pane_grid::Content::new(column![editor_container, status_bar].height(Fill))
+                // Outline the focused pane once there is more than one to tell apart.
+                let outlined = pane == self.focus && self.editors().nth(1).is_some();
+
+                pane_grid::Content::new(
+                    body.map(move |message| Message::InPane(pane, Box::new(message))),
+                )
+                .title_bar(self.title_bar(pane, editor, is_maximized))
+                .style(move |_theme| container::Style {
+                    border: Border {
+                        width: if outlined { 1.0 } else { 0.0 },
+                        color: tokens.current_line_number,
+                        radius: 5.0.into(),
+                    },
+                    ..container::Style::default()
+                })
             }
         })
         .spacing(6)
         .min_size(140)
+        .on_click(Message::PaneClicked)
+        .on_drag(Message::PaneDragged)
         .on_resize(12, Message::PaneResized);
 
-        container(grid)
+        let status_bar = {
+            let status = if let Some(Error::IoError(error)) = self.error {
+                text(error.to_string())
+            } else {
+                match self.buffer().path.as_deref().and_then(Path::to_str) {
+                    Some(path) => text(path).size(14),
+                    None => text("New File"),
+                }
+            };
+
+            let position = {
+                let Position { line, column } = self.editor.content.cursor().position;
+                text(format!("Ln {}, Col {}", line + 1, column + 1))
+                    .width(FillPortion(1))
+                    .size(16)
+                    .align_x(Alignment::End)
+            };
+            let language = pick_list(
+                language::LANGUAGES,
+                Some(self.language()),
+                Message::LanguageSelected,
+            )
+            .text_size(14)
+            .padding([0, 6])
+            .style(|theme, status| pick_list::Style {
+                border: Border::default(),
+                ..pick_list::default(theme, status)
+            });
+
+            let indentation = pick_list(
+                indent::OPTIONS,
+                Some(self.buffer().indentation),
+                Message::IndentationSelected,
+            )
+            .text_size(14)
+            .padding([0, 6])
+            .style(|theme, status| pick_list::Style {
+                border: Border::default(),
+                ..pick_list::default(theme, status)
+            });
+
+            // Pressing the lock allows editing.
+            let read_only: Element<'_, Message> = if self.buffer().read_only {
+                button(
+                    row![
+                        text('\u{E008}').font(self.icon_font).size(14),
+                        text("Read-only").size(14),
+                    ]
+                    .spacing(4)
+                    .align_y(Alignment::Center),
+                )
+                .on_press(Message::ToggleReadOnly)
+                .padding([0, 6])
+                .style(button::text)
+                .into()
+            } else {
+                Space::new().into()
+            };
+
+            // Only shown while zoomed, pressing it resets the zoom.
+            let zoom: Element<'_, Message> = if self.zoom == 1.0 {
+                Space::new().into()
+            } else {
+                button(text(format!("{:.0}%", self.zoom * 100.0)).size(14))
+                    .on_press(Message::ResetZoom)
+                    .padding([0, 6])
+                    .style(button::text)
+                    .into()
+            };
+
+            let modified: Element<'_, Message> = if self.is_modified(&self.editor) {
+                text("●").size(14).color(tokens.modified).into()
+            } else {
+                Space::new().into()
+            };
+
+            row![
+                modified,
+                status,
+                position,
+                read_only,
+                zoom,
+                indentation,
+                language
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+        };
+
+        let content: Element<'_, Message> = if self.sidebar.zen {
+            // Only the focused editor, whose messages need no routing.
+            let buffer = self.buffer();
+            if self.show_minimap {
+                row![
+                    self.editor_view(&self.editor, buffer),
+                    self.minimap(&self.editor, buffer)
+                ]
+                .into()
+            } else {
+                self.editor_view(&self.editor, buffer)
+            }
+        } else {
+            let activity_bar =
+                sidebar::activity_bar(self.sidebar.visible.then_some(self.sidebar.panel), tokens);
+            let workbench = column![grid, status_bar].spacing(6).height(Fill);
+
+            let body = match self.sidebar.side {
+                Side::Left => row![activity_bar, workbench],
+                Side::Right => row![workbench, activity_bar],
+            }
+            .spacing(6);
+
+            let top = if self.show_toolbar {
+                column![
+                    menu::bar(self.menu, tokens),
+                    menu::toolbar(self.icon_font, |command| self.is_enabled(command), tokens),
+                ]
+            } else {
+                column![menu::bar(self.menu, tokens)]
+            };
+
+            column![top.spacing(4), body].spacing(6).into()
+        };
+
+        let main = container(content)
             .padding(10)
             .center(Fill)
             .style(move |_theme| container::Style {
-                text_color: Some(Color::WHITE),
-                background: Some(Theme::CatppuccinMocha.base().background_color.into()),
+                text_color: Some(tokens.text),
+                background: Some(tokens.background.into()),
                 border,
                 shadow: iced::Shadow {
-                    color: Color::from_rgb8(30, 32, 48),
+                    color: tokens.shadow,
                     offset: iced::Vector { x: 0.5, y: 1.0 },
                     blur_radius: 3.0,
                 },
                 snap: false,
-            })
-            .into()
+            });
+
+        let mut layers = stack![main];
+
+        if let Some(open) = self.menu
+            && !self.sidebar.zen
+        {
+            // The menu bar starts inside the padding of the window.
+            layers = layers.push(menu::dropdown(
+                open,
+                (10.0, 10.0),
+                |command| self.is_enabled(command),
+                tokens,
+            ));
+        }
+
+        if !self.notifications.is_empty() {
+            layers = layers.push(self.notifications.view(self.appearance.tokens));
+        }
+
+        if let Some(palette) = &self.palette {
+            layers = layers.push(palette.view(self.appearance.tokens));
+        }
+
+        layers.into()
+    }
+}
+
+// The two positions in document order.
+fn ordered(a: Position, b: Position) -> (Position, Position) {
+    if (a.line, a.column) <= (b.line, b.column) {
+        (a, b)
+    } else {
+        (b, a)
     }
 }
 
-fn icon<'a>(codepoint: char) -> Element<'a, Message> {
-    const ICON_FONTS: Font = Font::with_name("xeditor");
-    text(codepoint).font(ICON_FONTS).into()
+// Vertical guides at the given columns, drawn under the editor text.
+fn rulers<'a>(columns: &[u16], char_width: f32, tokens: Tokens) -> Element<'a, Message> {
+    stack(columns.iter().map(|&column| {
+        pin(container(Space::new())
+            .width(1)
+            .height(Fill)
+            .style(move |_theme| container::Style {
+                background: Some(tokens.ruler.into()),
+                ..container::Style::default()
+            }))
+        .x(layout::PADDING + f32::from(column) * char_width)
+        .into()
+    }))
+    .into()
+}
+
+fn icon<'a>(font: Font, codepoint: char) -> Element<'a, Message> {
+    text(codepoint).font(font).into()
 }
 
 #[allow(dead_code)]
-fn directory_icon<'a>() -> Element<'a, Message> {
-    icon('\u{E001}')
+fn directory_icon<'a>(font: Font) -> Element<'a, Message> {
+    icon(font, '\u{E001}')
 }
 
-fn file_icon<'a>() -> Element<'a, Message> {
-    icon('\u{E002}')
+fn file_icon<'a>(font: Font) -> Element<'a, Message> {
+    icon(font, '\u{E002}')
 }
 
-fn closed_chevron<'a>() -> Element<'a, Message> {
-    icon('\u{F001}')
+fn closed_chevron<'a>(font: Font) -> Element<'a, Message> {
+    icon(font, '\u{F001}')
 }
 
-fn opened_chevron<'a>() -> Element<'a, Message> {
-    icon('\u{F002}')
+fn opened_chevron<'a>(font: Font) -> Element<'a, Message> {
+    icon(font, '\u{F002}')
 }
 
-fn render_tree_nodes<'a>(nodes: &'a [FileNode], depth: usize) -> Vec<Element<'a, Message>> {
+// Files with unsaved changes are marked with a dot.
+fn render_tree_nodes<'a>(
+    nodes: &'a [FileNode],
+    depth: usize,
+    modified: &HashSet<&Path>,
+    icons: Font,
+    tokens: Tokens,
+) -> Vec<Element<'a, Message>> {
     let mut out: Vec<Element<'a, Message>> = Vec::new();
     let indent = (depth as f32) * 14.0;
 
@@ -376,8 +5051,18 @@ fn render_tree_nodes<'a>(nodes: &'a [FileNode], depth: usize) -> Vec<Element<'a,
         match node {
             FileNode::File { name, path } => {
                 let chevron = text("").width(Length::Fixed(10.0));
-                let label = text(name);
-                let row_content = row![chevron, file_icon(), label].spacing(6);
+                let is_modified = path.as_deref().is_some_and(|path| modified.contains(path));
+                let row_content = if is_modified {
+                    row![
+                        chevron,
+                        file_icon(icons),
+                        text(name).color(tokens.modified),
+                        text("●").color(tokens.modified),
+                    ]
+                } else {
+                    row![chevron, file_icon(icons), text(name)]
+                }
+                .spacing(6);
 
                 if let Some(path) = path {
                     out.push(
@@ -411,10 +5096,10 @@ fn render_tree_nodes<'a>(nodes: &'a [FileNode], depth: usize) -> Vec<Element<'a,
             } => {
                 let chevron = if *expanded {
                     // text("v").width(Length::Fixed(4.0))
-                    opened_chevron()
+                    opened_chevron(icons)
                 } else {
                     // text(">").width(Length::Fixed(4.0))
-                    closed_chevron()
+                    closed_chevron(icons)
                 };
                 let label = text(name);
                 out.push(
@@ -434,7 +5119,13 @@ fn render_tree_nodes<'a>(nodes: &'a [FileNode], depth: usize) -> Vec<Element<'a,
                 );
 
                 if *expanded && let Some(children) = children_nodes.as_deref() {
-                    out.extend(render_tree_nodes(children, depth + 1));
+                    out.extend(render_tree_nodes(
+                        children,
+                        depth + 1,
+                        modified,
+                        icons,
+                        tokens,
+                    ));
                 }
             }
         }
@@ -479,6 +5170,27 @@ fn toggle_dir_expanded(nodes: &mut [FileNode], target: &PathBuf) -> bool {
     false
 }
 
+fn expanded_directories(nodes: &[FileNode]) -> Vec<PathBuf> {
+    let mut expanded = Vec::new();
+
+    for node in nodes {
+        if let FileNode::Directory {
+            path,
+            expanded: true,
+            children_nodes,
+            ..
+        } = node
+        {
+            expanded.push(path.clone());
+            if let Some(children) = children_nodes.as_deref() {
+                expanded.extend(expanded_directories(children));
+            }
+        }
+    }
+
+    expanded
+}
+
 fn set_dir_children(nodes: &mut [FileNode], target: &PathBuf, children: Vec<FileNode>) -> bool {
     let mut children = Some(children);
     set_dir_children_inner(nodes, target, &mut children)
@@ -513,20 +5225,150 @@ fn set_dir_children_inner(
 }
 
 fn main() -> iced::Result {
-    iced::application(Xeditor::new, Xeditor::update, Xeditor::view)
-        .settings(Settings {
-            default_font: Font::MONOSPACE,
-            fonts: vec![include_bytes!("../fonts/xeditor.ttf").as_slice().into()],
-            ..Settings::default()
-        })
-        .theme(Theme::CatppuccinMocha)
-        .run()
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(problem) => {
+            eprintln!("xeditor: {problem}\nTry `xeditor --help` for more information.");
+            std::process::exit(2);
+        }
+    };
+
+    if args.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if args.version {
+        println!("xeditor {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
+    let stdin = if args.stdin {
+        match std::io::read_to_string(std::io::stdin()) {
+            Ok(text) => Some(text),
+            Err(error) => {
+                eprintln!("xeditor: could not read standard input: {error}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let requests = args.requests(stdin);
+
+    // A running editor opens everything itself, this one only waits when asked to.
+    let socket = ipc::socket_path();
+    if !args.new_window
+        && let Some(stream) = socket.as_deref().and_then(ipc::connect)
+    {
+        if let Err(error) = ipc::send(stream, &requests) {
+            eprintln!("xeditor: the running editor did not answer: {error}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let server = socket.as_deref().and_then(ipc::bind);
+
+    // Files opened from the command line get a window of their own, folders get the
+    // session of their workspace.
+    let directories: Vec<PathBuf> = args
+        .directories
+        .iter()
+        .map(|path| cli::absolute(path))
+        .collect();
+    let restore = !args.no_restore && !args.new_window && !args.opens_files();
+    let session = match directories.first() {
+        _ if !restore => None,
+        Some(directory) => session::read(Some(directory), session::DEFAULT_NAME),
+        None => session::read_last(),
+    }
+    .filter(|session| !session.is_empty());
+    let roots = directories
+        .into_iter()
+        .chain(session.iter().flat_map(|session| session.roots.clone()))
+        .collect();
+    let workspace = Workspace::new(roots, std::env::current_dir().unwrap_or_default());
+    let window = session.as_ref().and_then(|session| session.window);
+
+    let (settings, mut problems) = settings::read_all(workspace.primary());
+    let (themes, theme_problems) = theme::read_all();
+    problems.extend(theme_problems);
+    let layout = sidebar::read();
+    let recent = recent::read();
+    let default_font = settings
+        .ui_font
+        .as_deref()
+        .or(settings.font.as_deref())
+        .map_or(Font::MONOSPACE, font::named);
+
+    let launch = Launch {
+        settings,
+        themes,
+        layout,
+        workspace,
+        recent,
+        restore,
+        session,
+        requests,
+        server,
+        problems,
+    };
+
+    let mut application = iced::application(
+        move || Xeditor::new(launch.clone()),
+        |xeditor: &mut Xeditor, message| {
+            let task = xeditor.update(message);
+            Task::batch([task, xeditor.finish_waiting()])
+        },
+        Xeditor::view,
+    )
+    .settings(Settings {
+        default_font,
+        fonts: vec![include_bytes!("../fonts/xeditor.ttf").as_slice().into()],
+        ..Settings::default()
+    })
+    .subscription(Xeditor::subscription)
+    // The layout and the session are saved before the window closes.
+    .exit_on_close_request(false)
+    .title(Xeditor::title)
+    .theme(Xeditor::theme)
+    .scale_factor(Xeditor::scale_factor);
+
+    if let Some(size) = window {
+        application = application.window_size(size);
+    }
+
+    application.run()
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
 enum Error {
     DialogClosed,
     IoError(ErrorKind),
+    FormatterFailed(String),
+    WriteFailed(String),
+}
+
+// The reason of a failure, for notifications.
+fn describe(error: &Error) -> String {
+    match error {
+        Error::IoError(kind) => kind.to_string(),
+        Error::FormatterFailed(reason) | Error::WriteFailed(reason) => reason.clone(),
+        Error::DialogClosed => String::from("the dialog was closed"),
+    }
+}
+
+// The file name, "New File" for buffers without a file.
+fn buffer_name(buffer: &Buffer) -> String {
+    buffer
+        .path
+        .as_deref()
+        .and_then(Path::file_name)
+        .map_or_else(
+            || String::from("New File"),
+            |name| name.to_string_lossy().into_owned(),
+        )
 }
 
 async fn read_file(path: PathBuf) -> Result<(Arc<String>, PathBuf), Error> {
@@ -539,8 +5381,20 @@ async fn read_file(path: PathBuf) -> Result<(Arc<String>, PathBuf), Error> {
     Ok((contents, path))
 }
 
+// Reads all of `paths`, failing with the first that cannot be read.
+async fn read_files<const N: usize>(
+    paths: [PathBuf; N],
+) -> Result<[(Arc<String>, PathBuf); N], Error> {
+    let mut files = Vec::with_capacity(N);
+    for path in paths {
+        files.push(read_file(path).await?);
+    }
+
+    Ok(files.try_into().expect("one file was read for every path"))
+}
+
 // This is just read the content of the directory and return the vector  fo the fielNone
-async fn read_directory(path: PathBuf) -> Result<Vec<FileNode>, Error> {
+async fn read_directory(path: PathBuf) -> Result<(Vec<FileNode>, PathBuf), Error> {
     let mut read_dir = fs::read_dir(&path)
         .await
         .map_err(|error| error.kind())
@@ -569,45 +5423,81 @@ async fn read_directory(path: PathBuf) -> Result<Vec<FileNode>, Error> {
             });
         }
     }
-    Ok(childrens)
+    Ok((childrens, path))
 }
 
-// Repeated for a reason but need to fix this
-async fn read_child_directory(path: PathBuf) -> Result<(Vec<FileNode>, PathBuf), Error> {
-    let mut read_dir = fs::read_dir(&path)
-        .await
-        .map_err(|error| error.kind())
-        .map_err(Error::IoError)?;
+// The explorer node of a workspace root, open to show its `children`.
+fn root_node(path: PathBuf, children: Vec<FileNode>) -> FileNode {
+    FileNode::Directory {
+        name: workspace::folder_name(&path),
+        path,
+        expanded: true,
+        children_nodes: Box::new(Some(children)),
+    }
+}
 
-    let mut childrens: Vec<FileNode> = Vec::new();
-    while let Some(entry) = read_dir.next_entry().await.unwrap() {
-        let path = entry.path();
-        let name = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("")
-            .to_string();
+// Reads the `roots` with the `expanded` directories below them open, as a session left
+// them. Roots that cannot be read are left out of the explorer.
+async fn read_workspace(roots: Vec<PathBuf>, mut expanded: Vec<PathBuf>) -> Vec<FileNode> {
+    let mut nodes = Vec::new();
+    for root in &roots {
+        if let Ok((children, path)) = read_directory(root.clone()).await {
+            nodes.push(root_node(path, children));
+        }
+    }
 
-        if path.is_dir() {
-            childrens.push(FileNode::Directory {
-                name,
-                path,
-                expanded: false,
-                children_nodes: Box::new(None),
-            });
-        } else {
-            childrens.push(FileNode::File {
-                name,
-                path: Some(path),
-            });
+    // Parents sort before their children, so they are in the tree by then.
+    expanded.sort();
+    for path in expanded {
+        if roots.contains(&path) {
+            continue;
+        }
+
+        if let Ok((children, path)) = read_directory(path).await
+            && set_dir_children(&mut nodes, &path, children)
+        {
+            toggle_dir_expanded(&mut nodes, &path);
         }
     }
-    Ok((childrens, path))
+
+    nodes
+}
+
+// The changed files of every root. Roots in the same repository list them once.
+async fn git_status(roots: Vec<PathBuf>) -> Vec<git::FileStatus> {
+    let mut changes: Vec<git::FileStatus> = Vec::new();
+    for root in roots {
+        for change in git::status(root).await {
+            if !changes.contains(&change) {
+                changes.push(change);
+            }
+        }
+    }
+
+    changes
+}
+
+// Creates the user settings file on first use so there is something to edit.
+async fn open_settings(path: PathBuf) -> Result<(Arc<String>, PathBuf), Error> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)
+            .await
+            .map_err(|error| Error::IoError(error.kind()))?;
+    }
+
+    if !fs::try_exists(&path).await.unwrap_or(false) {
+        fs::write(&path, "")
+            .await
+            .map_err(|error| Error::IoError(error.kind()))?;
+    }
+
+    read_file(path).await
 }
 
-async fn pick_file() -> Result<(Arc<String>, PathBuf), Error> {
+async fn pick_file(directory: PathBuf) -> Result<(Arc<String>, PathBuf), Error> {
     let path = rfd::AsyncFileDialog::new()
         .set_title("Choose a file")
+        .set_directory(directory)
         .pick_file()
         .await
         .ok_or(Error::DialogClosed)?
@@ -617,9 +5507,10 @@ async fn pick_file() -> Result<(Arc<String>, PathBuf), Error> {
     read_file(path).await
 }
 
-async fn pick_directory() -> Result<Vec<FileNode>, Error> {
+async fn pick_directory(directory: PathBuf) -> Result<(Vec<FileNode>, PathBuf), Error> {
     let path = rfd::AsyncFileDialog::new()
         .set_title("Choose a directory")
+        .set_directory(directory)
         .pick_folder()
         .await
         .ok_or(Error::DialogClosed)?
@@ -628,25 +5519,286 @@ async fn pick_directory() -> Result<Vec<FileNode>, Error> {
     read_directory(path).await
 }
 
-fn default_directory() -> PathBuf {
-    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+// Where a recent item is, and whether it is pinned.
+fn recent_detail(entry: &recent::Entry) -> String {
+    let location = entry.item.location();
+    if entry.pinned {
+        format!("{location} · pinned")
+    } else {
+        location
+    }
+}
+
+fn close_window() -> Task<Message> {
+    iced::window::latest().and_then(|window| Task::done(Message::WindowCloseRequested(window)))
+}
+
+fn hash(text: &str) -> u64 {
+    let mut hasher = std::hash::DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+async fn confirm_discard(name: String) -> bool {
+    let result = rfd::AsyncMessageDialog::new()
+        .set_title("Unsaved changes")
+        .set_description(format!("{name} has unsaved changes. Close it anyway?"))
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .show()
+        .await;
+
+    result == rfd::MessageDialogResult::Yes
 }
 
-async fn save_file(path: Option<PathBuf>, text: String) -> Result<PathBuf, Error> {
+async fn save_file(
+    path: Option<PathBuf>,
+    text: String,
+    directory: PathBuf,
+) -> Result<PathBuf, Error> {
     let path = if let Some(path) = path {
         path
     } else {
         rfd::AsyncFileDialog::new()
             .set_title("Choose a file name...")
+            .set_directory(directory)
             .save_file()
             .await
             .ok_or(Error::DialogClosed)
             .map(|handle| handle.path().to_owned())?
     };
 
-    fs::write(&path, text)
-        .await
-        .map_err(|error| Error::IoError(error.kind()))?;
+    write_file(&path, text).await?;
 
     Ok(path)
 }
+
+// Whether the file can be written, tried by opening it for writing. Files that do not
+// exist yet count as writable, saving tells.
+async fn is_writable(path: PathBuf) -> bool {
+    match fs::OpenOptions::new().write(true).open(&path).await {
+        Ok(_) => true,
+        Err(error) => !matches!(
+            error.kind(),
+            ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem
+        ),
+    }
+}
+
+async fn write_file(path: &Path, text: String) -> Result<(), Error> {
+    fs::write(path, text)
+        .await
+        .map_err(|error| Error::IoError(error.kind()))
+}
+
+// Writes every file, going on after those that fail. Those written come with the hash of
+// their text.
+async fn write_files(
+    files: Vec<(BufferId, PathBuf, String)>,
+) -> Vec<(BufferId, PathBuf, Result<u64, Error>)> {
+    let mut results = Vec::with_capacity(files.len());
+    for (buffer, path, text) in files {
+        let saved = hash(&text);
+        let result = write_file(&path, text).await.map(|()| saved);
+        results.push((buffer, path, result));
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::futures::StreamExt;
+
+    fn launch() -> Launch {
+        Launch {
+            settings: settings::Settings::default(),
+            themes: Vec::new(),
+            layout: sidebar::Layout::default(),
+            workspace: Workspace::default(),
+            recent: recent::Recent::default(),
+            restore: false,
+            session: None,
+            requests: Vec::new(),
+            server: None,
+            problems: Vec::new(),
+        }
+    }
+
+    async fn run(task: Task<Message>) {
+        if let Some(mut stream) = iced_runtime::task::into_stream(task) {
+            while stream.next().await.is_some() {}
+        }
+    }
+
+    fn open(path: PathBuf) -> cli::Request {
+        cli::Request::Open {
+            target: cli::Target {
+                path,
+                position: None,
+            },
+            readonly: false,
+            wait: true,
+        }
+    }
+
+    fn type_text(xeditor: &mut Xeditor, text: &str) {
+        for c in text.chars() {
+            let _ = xeditor.update(Message::ActionPerformed(text_editor::Action::Edit(
+                text_editor::Edit::Insert(c),
+            )));
+        }
+    }
+
+    #[tokio::test]
+    async fn opening_a_file_keeps_unsaved_changes_in_a_pane() {
+        let (mut xeditor, _) = Xeditor::new(launch());
+        type_text(&mut xeditor, "draft");
+
+        let _ = xeditor.update(Message::OpenedFile(Ok((
+            Arc::new(String::from("on disk")),
+            PathBuf::from("/file.txt"),
+        ))));
+        type_text(&mut xeditor, "edited ");
+        let _ = xeditor.update(Message::NewFile);
+
+        let mut texts: Vec<_> = xeditor.editors().map(|(_, editor)| editor.text()).collect();
+        texts.sort();
+        assert_eq!(texts, ["", "draft", "edited on disk"]);
+        assert_eq!(xeditor.buffers.len(), 3);
+
+        // A new file replaces one without changes.
+        let _ = xeditor.update(Message::NewFile);
+        assert_eq!(xeditor.editors().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn edits_made_while_saving_stay_unsaved() {
+        let (mut xeditor, _) = Xeditor::new(launch());
+        type_text(&mut xeditor, "saved");
+        let saved = hash(&xeditor.text());
+        type_text(&mut xeditor, " later");
+
+        let path = PathBuf::from("/file.txt");
+        let _ = xeditor.update(Message::SavedFile(Ok((path.clone(), saved))));
+        assert!(xeditor.buffer().is_dirty);
+
+        let saved = hash(&xeditor.text());
+        let _ = xeditor.update(Message::SavedFile(Ok((path, saved))));
+        assert!(!xeditor.buffer().is_dirty);
+    }
+
+    #[tokio::test]
+    async fn runtime_choices_survive_unrelated_settings_changes() {
+        let (mut xeditor, _) = Xeditor::new(launch());
+        let _ = xeditor.update(Message::IndentationSelected(Indentation::Tabs));
+        let _ = xeditor.update(Message::ToggleLigatures);
+
+        let mut settings = xeditor.settings.clone();
+        settings.theme = String::from("Nord");
+        let _ = xeditor.apply_settings(settings.clone());
+        assert_eq!(xeditor.buffer().indentation, Indentation::Tabs);
+        assert!(xeditor.ligatures.is_some());
+
+        settings.ligatures = !settings.ligatures;
+        let _ = xeditor.apply_settings(settings);
+        assert_eq!(xeditor.ligatures, None);
+    }
+
+    #[tokio::test]
+    async fn save_all_applies_once_something_can_be_saved() {
+        let (mut xeditor, _) = Xeditor::new(launch());
+        assert!(!xeditor.is_enabled(Command::SaveAll));
+
+        type_text(&mut xeditor, "new");
+        assert!(xeditor.is_enabled(Command::SaveAll));
+
+        xeditor.buffer_mut().path = Some(PathBuf::from("/etc/hosts"));
+        xeditor.buffer_mut().read_only = true;
+        assert!(!xeditor.is_enabled(Command::SaveAll));
+    }
+
+    #[tokio::test]
+    async fn forgets_recent_files_only_once_they_are_gone() {
+        let (mut xeditor, _) = Xeditor::new(launch());
+        let path = PathBuf::from("/root/notes.txt");
+        let _ = xeditor.remember(recent::Item::File(path.clone()));
+
+        let _ = xeditor.update(Message::RecentFileRead(
+            path.clone(),
+            Err(Error::IoError(ErrorKind::PermissionDenied)),
+        ));
+        assert_eq!(xeditor.recent.entries().len(), 1);
+
+        let _ = xeditor.update(Message::RecentFileRead(
+            path,
+            Err(Error::IoError(ErrorKind::NotFound)),
+        ));
+        assert!(xeditor.recent.is_empty());
+    }
+
+    // A second `xeditor` waiting for files neither of which opens: one can not be read,
+    // the pane of the other was closed while it was read.
+    #[tokio::test]
+    async fn tells_a_waiting_client_when_its_files_could_not_be_opened() {
+        let directory = std::env::temp_dir().join(format!("xeditor-wait-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let socket = directory.join("xeditor.sock");
+        let server = ipc::bind(&socket).unwrap();
+        let mut clients = Box::pin(ipc::serve(&server));
+
+        let requests = vec![
+            open(directory.join("unreadable.txt")),
+            open(directory.join("closed.txt")),
+        ];
+        let stream = ipc::connect(&socket).unwrap();
+        let second = tokio::task::spawn_blocking(move || ipc::send(stream, &requests));
+        let (client, requests) = clients.next().await.unwrap();
+
+        let (mut xeditor, _) = Xeditor::new(launch());
+        let _ = xeditor.update(Message::ClientRequested(client, requests));
+        let panes: Vec<_> = xeditor.editors().map(|(pane, _)| pane).collect();
+        let [unreadable, closed] = panes[..] else {
+            panic!("expected a pane per file, got {panes:?}");
+        };
+
+        let _ = xeditor.in_pane(closed, Message::ClosePane);
+        assert!(xeditor.panes.get(closed).is_none());
+        assert_eq!(xeditor.waiting.len(), 1);
+        run(xeditor.finish_waiting()).await;
+        assert_eq!(xeditor.waiting.len(), 1);
+
+        let _ = xeditor.update(Message::CommandLineFileRead {
+            pane: unreadable,
+            target: cli::Target {
+                path: directory.join("unreadable.txt"),
+                position: None,
+            },
+            readonly: false,
+            wait: Some(xeditor.waiting[0].waiter.clone()),
+            result: Err(Error::IoError(ErrorKind::InvalidData)),
+        });
+        run(xeditor.finish_waiting()).await;
+        assert_eq!(xeditor.waiting.len(), 1);
+
+        let _ = xeditor.update(Message::CommandLineFileRead {
+            pane: closed,
+            target: cli::Target {
+                path: directory.join("closed.txt"),
+                position: None,
+            },
+            readonly: false,
+            wait: Some(xeditor.waiting[0].waiter.clone()),
+            result: Ok((Arc::new(String::from("text")), directory.join("closed.txt"))),
+        });
+        run(xeditor.finish_waiting()).await;
+        assert!(xeditor.waiting.is_empty());
+
+        // Returns only once told the files are closed, it was never told they opened.
+        second.await.unwrap().unwrap();
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::Message;
+use crate::command;
+use crate::command::Command;
+use crate::command::MENUS;
+use crate::command::Menu;
+use crate::theme::Tokens;
+use iced::Alignment;
+use iced::Border;
+use iced::Element;
+use iced::Font;
+use iced::Length::Fill;
+use iced::widget::Space;
+use iced::widget::button;
+use iced::widget::column;
+use iced::widget::container;
+use iced::widget::mouse_area;
+use iced::widget::pin;
+use iced::widget::row;
+use iced::widget::scrollable;
+use iced::widget::stack;
+use iced::widget::text;
+use iced::widget::tooltip;
+
+pub const BAR_HEIGHT: f32 = 26.0;
+
+// Titles have the same width, so a menu opens below its title without measuring it.
+const TITLE_WIDTH: f32 = 80.0;
+const DROPDOWN_WIDTH: f32 = 320.0;
+const DROPDOWN_MAX_HEIGHT: f32 = 560.0;
+
+// The commands of the toolbar with their glyph in the icon font.
+const TOOLBAR: &[(Command, char)] = &[
+    (Command::NewFile, '\u{E002}'),
+    (Command::OpenDirectory, '\u{E001}'),
+    (Command::SaveFile, '\u{E003}'),
+    (Command::ShowSearch, '\u{E007}'),
+    (Command::SplitRight, '\u{E004}'),
+    (Command::ToggleSidebar, '\u{E005}'),
+    (Command::ShowPalette, '\u{E006}'),
+];
+
+// The menu titles. Hovering another title while a menu is open switches to its menu.
+pub fn bar<'a>(open: Option<Menu>, tokens: Tokens) -> Element<'a, Message> {
+    let titles = MENUS.iter().map(|&menu| {
+        let active = open == Some(menu);
+
+        mouse_area(
+            button(text(menu.to_string()).size(14).align_x(Alignment::Center))
+                .on_press(Message::MenuToggled(menu))
+                .padding([3, 0])
+                .width(TITLE_WIDTH)
+                .height(BAR_HEIGHT)
+                .style(move |theme, status| {
+                    let style = button::text(theme, status);
+                    button::Style {
+                        background: (active || status == button::Status::Hovered)
+                            .then(|| tokens.current_line.into()),
+                        text_color: tokens.text,
+                        border: Border {
+                            radius: 5.0.into(),
+                            ..Border::default()
+                        },
+                        ..style
+                    }
+                }),
+        )
+        .on_enter(Message::MenuHovered(menu))
+        .into()
+    });
+
+    row(titles).height(BAR_HEIGHT).into()
+}
+
+// The open menu below its title, over everything but the menu bar. `origin` is where the
+// menu bar starts in the window. Clicking outside of it closes it.
+pub fn dropdown<'a>(
+    menu: Menu,
+    origin: (f32, f32),
+    enabled: impl Fn(Command) -> bool,
+    tokens: Tokens,
+) -> Element<'a, Message> {
+    let mut items = column![].width(Fill);
+
+    for (index, group) in command::menu_groups(menu).into_iter().enumerate() {
+        if index > 0 {
+            let line = container(Space::new().width(Fill).height(1)).style(move |_theme| {
+                container::Style {
+                    background: Some(tokens.border.into()),
+                    ..container::Style::default()
+                }
+            });
+            items = items.push(container(line).padding([4, 0]));
+        }
+
+        for command in group {
+            let shortcut = command
+                .shortcut()
+                .map(|shortcut| shortcut.to_string())
+                .unwrap_or_default();
+
+            items = items.push(
+                button(
+                    row![
+                        text(command.label()).size(14).width(Fill),
+                        text(shortcut).size(12).color(tokens.text_muted),
+                    ]
+                    .spacing(12)
+                    .align_y(Alignment::Center),
+                )
+                .on_press_maybe(enabled(command).then_some(Message::CommandRun(command)))
+                .padding([3, 10])
+                .width(Fill)
+                .style(button::text),
+            );
+        }
+    }
+
+    let index = MENUS.iter().position(|&other| other == menu).unwrap_or(0);
+    let list = container(scrollable(items))
+        .width(DROPDOWN_WIDTH)
+        .max_height(DROPDOWN_MAX_HEIGHT)
+        .padding(4)
+        .style(move |_theme| container::Style {
+            text_color: Some(tokens.text),
+            background: Some(tokens.background.into()),
+            border: Border {
+                width: 1.0,
+                color: tokens.border,
+                radius: 5.0.into(),
+            },
+            shadow: iced::Shadow {
+                color: tokens.shadow,
+                offset: iced::Vector { x: 0.0, y: 2.0 },
+                blur_radius: 8.0,
+            },
+            snap: false,
+        });
+
+    let (x, y) = origin;
+    column![
+        Space::new().height(y + BAR_HEIGHT),
+        stack![
+            mouse_area(Space::new().width(Fill).height(Fill)).on_press(Message::MenuClosed),
+            pin(list).x(x + index as f32 * TITLE_WIDTH),
+        ],
+    ]
+    .into()
+}
+
+// Buttons for the most used commands, with the title and shortcut as tooltip.
+pub fn toolbar<'a>(
+    icons: Font,
+    enabled: impl Fn(Command) -> bool,
+    tokens: Tokens,
+) -> Element<'a, Message> {
+    let buttons = TOOLBAR.iter().map(|&(command, glyph)| {
+        let tip = match command.shortcut() {
+            Some(shortcut) => format!("{} ({shortcut})", command.label()),
+            None => command.label().to_owned(),
+        };
+
+        tooltip(
+            button(text(glyph).font(icons).size(16))
+                .on_press_maybe(enabled(command).then_some(Message::CommandRun(command)))
+                .padding([4, 8])
+                .style(button::text),
+            container(text(tip).size(12))
+                .padding([2, 6])
+                .style(move |_theme| container::Style {
+                    text_color: Some(tokens.text),
+                    background: Some(tokens.background.into()),
+                    border: Border {
+                        width: 1.0,
+                        color: tokens.border,
+                        radius: 4.0.into(),
+                    },
+                    ..container::Style::default()
+                }),
+            tooltip::Position::Bottom,
+        )
+        .into()
+    });
+
+    row(buttons).spacing(2).into()
+}
@@ -0,0 +1,208 @@
+use crate::Message;
+use crate::layout::LineLayout;
+use crate::theme::Tokens;
+use iced::Color;
+use iced::Point;
+use iced::Rectangle;
+use iced::Renderer;
+use iced::Size;
+use iced::Theme;
+use iced::mouse;
+use iced::widget::canvas;
+use iced::widget::scrollable::Viewport;
+use iced::widget::text_editor;
+
+pub const WIDTH: f32 = 100.0;
+
+const MAX_LINE_HEIGHT: f32 = 2.0;
+const CHAR_WIDTH: f32 = 1.0;
+const TAB_WIDTH: usize = 4;
+const TICK_WIDTH: f32 = 5.0;
+const MIN_TICK_HEIGHT: f32 = 2.0;
+
+// A colored mark on the right edge of the minimap, on a display line.
+#[derive(Debug, Clone, Copy)]
+pub struct Tick {
+    pub line: usize,
+    pub color: Color,
+}
+
+pub struct Minimap<'a> {
+    pub content: &'a text_editor::Content,
+    pub cache: &'a canvas::Cache,
+    pub viewport: Option<&'a Viewport>,
+    pub layout: &'a LineLayout,
+    pub ticks: Vec<Tick>,
+    pub tokens: Tokens,
+}
+
+impl Minimap<'_> {
+    // Short buffers are drawn at full scale, long ones get squeezed to fit like an overview ruler.
+    fn line_height(&self, bounds: Rectangle) -> f32 {
+        let line_count = self.content.line_count().max(1) as f32;
+        (bounds.height / line_count).min(MAX_LINE_HEIGHT)
+    }
+
+    fn line_at(&self, bounds: Rectangle, y: f32) -> usize {
+        let line = (y / self.line_height(bounds)).max(0.0) as usize;
+        line.min(self.content.line_count().saturating_sub(1))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct State {
+    dragging: bool,
+}
+
+impl canvas::Program<Message> for Minimap<'_> {
+    type State = State;
+
+    fn update(
+        &self,
+        state: &mut State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<Message>> {
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let position = cursor.position_in(bounds)?;
+                state.dragging = true;
+
+                Some(
+                    canvas::Action::publish(Message::MinimapScrolled(
+                        self.line_at(bounds, position.y),
+                    ))
+                    .and_capture(),
+                )
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { position }) if state.dragging => {
+                Some(canvas::Action::publish(Message::MinimapScrolled(
+                    self.line_at(bounds, position.y - bounds.y),
+                )))
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if state.dragging =>
+            {
+                state.dragging = false;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let line_height = self.line_height(bounds);
+
+        let text = self.cache.draw(renderer, bounds.size(), |frame| {
+            let block_height = (line_height * 0.75).max(0.5);
+
+            for (index, line) in self.content.lines().enumerate() {
+                let y = index as f32 * line_height;
+
+                // One block per run of non-whitespace characters.
+                let mut column = 0;
+                let mut run_start = None;
+                for c in line.text.chars().chain(std::iter::once(' ')) {
+                    if c.is_whitespace() {
+                        if let Some(start) = run_start.take() {
+                            frame.fill_rectangle(
+                                Point::new(start as f32 * CHAR_WIDTH, y),
+                                Size::new((column - start) as f32 * CHAR_WIDTH, block_height),
+                                self.tokens.minimap_text,
+                            );
+                        }
+                    } else if run_start.is_none() {
+                        run_start = Some(column);
+                    }
+
+                    column += if c == '\t' { TAB_WIDTH } else { 1 };
+                }
+            }
+        });
+
+        let mut overlay = canvas::Frame::new(renderer, bounds.size());
+
+        if let Some(viewport) = self.viewport {
+            // Wrapped rows are not drawn separately, so the marker spans whole lines.
+            let offset = viewport.absolute_offset().y;
+            let first = self.layout.line_at(offset);
+            let last = self.layout.line_at(offset + viewport.bounds().height) + 1;
+
+            overlay.fill_rectangle(
+                Point::new(0.0, first as f32 * line_height),
+                Size::new(
+                    bounds.width,
+                    ((last - first) as f32 * line_height).max(MIN_TICK_HEIGHT),
+                ),
+                self.tokens.minimap_viewport,
+            );
+        }
+
+        for tick in &self.ticks {
+            overlay.fill_rectangle(
+                Point::new(bounds.width - TICK_WIDTH, tick.line as f32 * line_height),
+                Size::new(TICK_WIDTH, line_height.max(MIN_TICK_HEIGHT)),
+                tick.color,
+            );
+        }
+
+        vec![text, overlay.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if state.dragging {
+            mouse::Interaction::Grabbing
+        } else if cursor.is_over(bounds) {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+// Display lines containing the search panel's query, ignoring case like the panel does.
+pub fn query_matches(content: &text_editor::Content, query: &str) -> Vec<usize> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let query = query.to_lowercase();
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.text.to_lowercase().contains(&query))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// Display lines containing the selected text; these are the search match ticks unless the
+// search panel is open.
+pub fn selection_matches(content: &text_editor::Content) -> Vec<usize> {
+    let Some(query) = content.selection() else {
+        return Vec::new();
+    };
+
+    if query.trim().is_empty() || query.contains('\n') {
+        return Vec::new();
+    }
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.text.contains(query.as_str()))
+        .map(|(index, _)| index)
+        .collect()
+}
@@ -0,0 +1,111 @@
+use crate::Message;
+use crate::theme::Tokens;
+use iced::Alignment;
+use iced::Border;
+use iced::Element;
+use iced::Length::Fill;
+use iced::task::Task;
+use iced::widget::button;
+use iced::widget::column;
+use iced::widget::container;
+use iced::widget::row;
+use iced::widget::text;
+use std::time::Duration;
+
+// How long a notification stays up unless it is dismissed earlier.
+const LIFETIME: Duration = Duration::from_secs(6);
+
+const WIDTH: f32 = 360.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+struct Notification {
+    id: u64,
+    level: Level,
+    message: String,
+}
+
+// Messages stacked in the bottom right corner of the window, newest last.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    next_id: u64,
+    shown: Vec<Notification>,
+}
+
+impl Notifications {
+    // Shows `message` and returns the task dismissing it once its time is up.
+    pub fn push(&mut self, level: Level, message: impl Into<String>) -> Task<Message> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.shown.push(Notification {
+            id,
+            level,
+            message: message.into(),
+        });
+
+        Task::perform(tokio::time::sleep(LIFETIME), move |()| {
+            Message::NotificationDismissed(id)
+        })
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.shown.retain(|notification| notification.id != id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shown.is_empty()
+    }
+
+    pub fn view(&self, tokens: Tokens) -> Element<'_, Message> {
+        let notifications = self.shown.iter().map(|notification| {
+            let accent = match notification.level {
+                Level::Info => tokens.info,
+                Level::Error => tokens.error,
+            };
+
+            container(
+                row![
+                    text(&notification.message).size(14).width(Fill),
+                    button(text("×").size(14))
+                        .on_press(Message::NotificationDismissed(notification.id))
+                        .padding([0, 6])
+                        .style(button::text),
+                ]
+                .spacing(8)
+                .align_y(Alignment::Start),
+            )
+            .width(WIDTH)
+            .padding([8, 10])
+            .style(move |_theme| container::Style {
+                text_color: Some(tokens.text),
+                background: Some(tokens.overlay.into()),
+                border: Border {
+                    width: 1.0,
+                    color: tokens.border,
+                    radius: 5.0.into(),
+                },
+                shadow: iced::Shadow {
+                    color: accent,
+                    offset: iced::Vector { x: -3.0, y: 0.0 },
+                    blur_radius: 0.0,
+                },
+                ..container::Style::default()
+            })
+            .into()
+        });
+
+        container(column(notifications).spacing(6))
+            .width(Fill)
+            .height(Fill)
+            .padding(20)
+            .align_x(Alignment::End)
+            .align_y(Alignment::End)
+            .into()
+    }
+}
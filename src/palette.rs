@@ -0,0 +1,181 @@
+use crate::Message;
+use crate::command;
+use crate::theme::Tokens;
+use iced::Alignment;
+use iced::Border;
+use iced::Element;
+use iced::Length::Fill;
+use iced::widget::button;
+use iced::widget::column;
+use iced::widget::container;
+use iced::widget::row;
+use iced::widget::scrollable;
+use iced::widget::text;
+use iced::widget::text_input;
+
+pub const INPUT: &str = "palette";
+
+const WIDTH: f32 = 520.0;
+const MAX_HEIGHT: f32 = 360.0;
+
+// One row of the palette and what choosing it does.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub title: String,
+    // Shown dimmed on the right, like the shortcut of a command.
+    pub detail: String,
+    pub message: Message,
+}
+
+#[derive(Debug)]
+pub struct Palette {
+    pub query: String,
+    selected: usize,
+    placeholder: &'static str,
+    entries: Vec<Entry>,
+    // Set when asking for free text, like a name, instead of offering entries.
+    submit: Option<fn(String) -> Message>,
+}
+
+impl Palette {
+    pub fn new(placeholder: &'static str, entries: Vec<Entry>) -> Self {
+        Self {
+            query: String::new(),
+            selected: 0,
+            placeholder,
+            entries,
+            submit: None,
+        }
+    }
+
+    // Asks for text, submitting sends `submit` with it.
+    pub fn prompt(placeholder: &'static str, submit: fn(String) -> Message) -> Self {
+        Self {
+            submit: Some(submit),
+            ..Self::new(placeholder, Vec::new())
+        }
+    }
+
+    // Every command with its shortcut.
+    pub fn commands() -> Self {
+        let entries = command::ALL
+            .iter()
+            .map(|&command| Entry {
+                title: command.title().to_owned(),
+                detail: command
+                    .shortcut()
+                    .map(|shortcut| shortcut.to_string())
+                    .unwrap_or_default(),
+                message: Message::CommandRun(command),
+            })
+            .collect();
+
+        Self::new("Type a command", entries)
+    }
+
+    pub fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.selected = 0;
+    }
+
+    // Entries matching the query, best matches first.
+    pub fn matches(&self) -> Vec<&Entry> {
+        let mut scored: Vec<(usize, &Entry)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| score(&self.query, &entry.title).map(|score| (score, entry)))
+            .collect();
+
+        scored.sort_by_key(|&(score, _)| score);
+        scored.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let count = self.matches().len();
+        if count == 0 {
+            return;
+        }
+
+        self.selected = (self.selected as isize + delta).rem_euclid(count as isize) as usize;
+    }
+
+    pub fn selected(&self) -> Option<Message> {
+        if let Some(submit) = self.submit {
+            let text = self.query.trim();
+            return (!text.is_empty()).then(|| submit(text.to_owned()));
+        }
+
+        self.matches()
+            .get(self.selected)
+            .map(|entry| entry.message.clone())
+    }
+
+    pub fn view(&self, tokens: Tokens) -> Element<'_, Message> {
+        let input = text_input(self.placeholder, &self.query)
+            .id(INPUT)
+            .on_input(Message::PaletteQueryChanged)
+            .on_submit(Message::PaletteSubmitted)
+            .padding(8);
+
+        let entries = self
+            .matches()
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let is_selected = index == self.selected;
+
+                button(
+                    row![
+                        text(&entry.title).width(Fill),
+                        text(&entry.detail).size(13).color(tokens.text_muted)
+                    ]
+                    .align_y(Alignment::Center),
+                )
+                .on_press(entry.message.clone())
+                .width(Fill)
+                .padding([4, 8])
+                .style(move |theme, status| button::Style {
+                    background: is_selected.then(|| tokens.selected.into()),
+                    ..button::text(theme, status)
+                })
+                .into()
+            });
+
+        let list = scrollable(column(entries)).height(iced::Length::Shrink);
+
+        container(
+            container(column![input, container(list).max_height(MAX_HEIGHT)].spacing(6))
+                .width(WIDTH)
+                .padding(6)
+                .style(move |_theme| container::Style {
+                    text_color: Some(tokens.text),
+                    background: Some(tokens.overlay.into()),
+                    border: Border {
+                        width: 1.0,
+                        color: tokens.border,
+                        radius: 5.0.into(),
+                    },
+                    ..container::Style::default()
+                }),
+        )
+        .width(Fill)
+        .padding([40, 0])
+        .align_x(Alignment::Center)
+        .into()
+    }
+}
+
+// Lower is better; `None` when the query is not a subsequence of the title. Every
+// character skipped between two matches costs a point, so contiguous matches win.
+fn score(query: &str, title: &str) -> Option<usize> {
+    let mut title = title.chars().map(|c| c.to_ascii_lowercase());
+    let mut score = 0;
+
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        let wanted = wanted.to_ascii_lowercase();
+        let skipped = title.by_ref().position(|c| c == wanted)?;
+        score += skipped;
+    }
+
+    Some(score)
+}
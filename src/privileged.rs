@@ -0,0 +1,289 @@
+use crate::Error;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process;
+
+// pkexec's exit codes when the password dialog was dismissed and when the user may not
+// run the program.
+const DISMISSED: i32 = 126;
+const NOT_AUTHORIZED: i32 = 127;
+
+// The helper binary, installed next to the editor.
+const HELPER: &str = "xeditor-write";
+// A command run instead of `pkexec xeditor-write`, with the same arguments and input and
+// exiting like pkexec does. For trying the editor against a fake helper.
+const HELPER_OVERRIDE: &str = "XEDITOR_WRITE_HELPER";
+
+// How to write a file the editor has no permission to write. The editor stays
+// unprivileged: another program asks for the password and runs the writing part as root,
+// which gets the text on stdin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    // `xeditor-write` through pkexec, replacing the file atomically.
+    Helper,
+    // `tee` through pkexec, for when the helper is not installed. Writes the file in place.
+    Pkexec,
+    // `tee` through sudo. Asks through `SUDO_ASKPASS` when it is set, there is no terminal
+    // to ask in.
+    Sudo,
+}
+
+const METHODS: &[Method] = &[Method::Helper, Method::Pkexec, Method::Sudo];
+
+impl Method {
+    pub fn title(self) -> &'static str {
+        match self {
+            Method::Helper => "Retry as Administrator",
+            Method::Pkexec => "Retry as Administrator with pkexec tee",
+            Method::Sudo => "Retry with sudo tee",
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Method::Helper => HELPER,
+            Method::Pkexec => "pkexec",
+            Method::Sudo => "sudo",
+        }
+    }
+
+    fn is_available(self, helper_override: Option<&OsStr>) -> bool {
+        match self {
+            Method::Helper => {
+                helper_override.is_some() || (is_installed("pkexec") && helper().is_some())
+            }
+            Method::Pkexec => is_installed("pkexec"),
+            Method::Sudo => is_installed("sudo"),
+        }
+    }
+
+    fn command(self, path: &Path, helper_override: Option<&OsStr>) -> process::Command {
+        let mut command = match self {
+            Method::Helper => match helper_override {
+                Some(fake) => process::Command::new(fake),
+                None => {
+                    let mut command = process::Command::new("pkexec");
+                    command.args(helper());
+                    command
+                }
+            },
+            Method::Pkexec => {
+                let mut command = process::Command::new("pkexec");
+                command.arg("tee").arg("--");
+                command
+            }
+            Method::Sudo => {
+                let mut command = process::Command::new("sudo");
+                // Without a program to ask for the password, sudo only works when it
+                // needs none.
+                command.arg(if std::env::var_os("SUDO_ASKPASS").is_some() {
+                    "--askpass"
+                } else {
+                    "--non-interactive"
+                });
+                command.arg("tee").arg("--");
+                command
+            }
+        };
+
+        command.arg(path);
+        command
+    }
+}
+
+// The ways to retry that are installed. `tee` through pkexec is only offered without the
+// helper, which does the same but atomically.
+pub fn available() -> Vec<Method> {
+    available_with(std::env::var_os(HELPER_OVERRIDE).as_deref())
+}
+
+fn available_with(helper_override: Option<&OsStr>) -> Vec<Method> {
+    let helper = Method::Helper.is_available(helper_override);
+
+    METHODS
+        .iter()
+        .copied()
+        .filter(|&method| {
+            method.is_available(helper_override) && !(helper && method == Method::Pkexec)
+        })
+        .collect()
+}
+
+// The absolute path pkexec needs, next to the running editor. The installed polkit policy
+// has to name the same path, pkexec only applies it to that one.
+fn helper() -> Option<PathBuf> {
+    let helper = std::env::current_exe().ok()?.with_file_name(HELPER);
+    helper.is_file().then_some(helper)
+}
+
+fn is_installed(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|directory| directory.join(program).is_file())
+    })
+}
+
+// Writes `text` to `path` through `method`. There is no timeout, the password dialog
+// waits for the user.
+pub async fn write(method: Method, path: PathBuf, text: String) -> Result<PathBuf, Error> {
+    let helper_override = std::env::var_os(HELPER_OVERRIDE);
+    write_with(method, path, text, helper_override.as_deref()).await
+}
+
+async fn write_with(
+    method: Method,
+    path: PathBuf,
+    text: String,
+    helper_override: Option<&OsStr>,
+) -> Result<PathBuf, Error> {
+    let failed = |reason: String| Error::WriteFailed(format!("{}: {reason}", method.program()));
+
+    // The helper refuses relative paths, pkexec runs it elsewhere.
+    let path = std::path::absolute(&path).map_err(|error| failed(error.to_string()))?;
+
+    let mut child = method
+        .command(&path, helper_override)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|error| failed(error.to_string()))?;
+
+    // Written from its own task, nothing reads it before the password is entered.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = tokio::spawn(async move {
+        let _ = stdin.write_all(text.as_bytes()).await;
+    });
+
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|error| failed(error.to_string()))?;
+
+    let _ = writer.await;
+
+    if matches!(method, Method::Helper | Method::Pkexec) {
+        match output.status.code() {
+            Some(DISMISSED) => {
+                return Err(failed(String::from("the password dialog was dismissed")));
+            }
+            Some(NOT_AUTHORIZED) => return Err(failed(String::from("not authorized"))),
+            _ => {}
+        }
+    }
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // The reason comes last, after whatever pkexec or sudo had to say.
+        let reason = stderr
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map_or_else(|| output.status.to_string(), str::to_owned);
+
+        return Err(failed(reason));
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::describe;
+    use std::fs;
+
+    const FAKE_HELPER: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/fake-write-helper"
+    );
+
+    async fn write_with_fake(path: PathBuf, text: &str) -> Result<PathBuf, Error> {
+        let fake = OsStr::new(FAKE_HELPER);
+        write_with(Method::Helper, path, String::from(text), Some(fake)).await
+    }
+
+    // A scratch directory, removed when dropped.
+    struct Scratch {
+        directory: PathBuf,
+    }
+
+    impl Scratch {
+        fn new(name: &str) -> Self {
+            let directory = std::env::temp_dir()
+                .join(format!("xeditor-privileged-{}-{name}", std::process::id()));
+            let _ = fs::remove_dir_all(&directory);
+            fs::create_dir_all(&directory).unwrap();
+
+            Self { directory }
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    async fn reason(path: PathBuf) -> String {
+        match write_with_fake(path, "text\n").await {
+            Ok(path) => panic!("{} was written", path.display()),
+            Err(error) => describe(&error),
+        }
+    }
+
+    #[tokio::test]
+    async fn hands_the_path_and_the_text_to_the_helper() {
+        let scratch = Scratch::new("write");
+        let path = scratch.directory.join("hosts");
+
+        let written = write_with_fake(path.clone(), "127.0.0.1 localhost\n::1 localhost\n").await;
+
+        assert_eq!(written.ok(), Some(path.clone()));
+        assert_eq!(
+            fs::read_to_string(scratch.directory.join("hosts.args")).unwrap(),
+            format!("{}\n", path.display())
+        );
+        assert_eq!(
+            fs::read_to_string(scratch.directory.join("hosts.input")).unwrap(),
+            "127.0.0.1 localhost\n::1 localhost\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn tells_why_the_helper_failed() {
+        let scratch = Scratch::new("fail");
+        let path = |name: &str| scratch.directory.join(name);
+
+        assert_eq!(
+            reason(path("dismissed")).await,
+            "xeditor-write: the password dialog was dismissed"
+        );
+        assert_eq!(
+            reason(path("not-authorized")).await,
+            "xeditor-write: not authorized"
+        );
+        assert_eq!(
+            reason(path("failing")).await,
+            format!(
+                "xeditor-write: {}: Read-only file system",
+                path("failing").display()
+            )
+        );
+        assert_eq!(
+            reason(path("silent")).await,
+            "xeditor-write: exit status: 1"
+        );
+    }
+
+    #[test]
+    fn offers_the_helper_instead_of_pkexec_tee() {
+        let methods = available_with(Some(OsStr::new(FAKE_HELPER)));
+        assert_eq!(methods.first(), Some(&Method::Helper));
+        assert!(!methods.contains(&Method::Pkexec));
+    }
+}
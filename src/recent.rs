@@ -0,0 +1,239 @@
+use crate::workspace;
+use crate::xdg;
+use serde_json::Value;
+use serde_json::json;
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "recent.json";
+
+// Entries past this many are forgotten, pinned ones are kept regardless.
+const MAX_ENTRIES: usize = 30;
+
+// Something opened recently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    File(PathBuf),
+    // The roots of a workspace.
+    Workspace(Vec<PathBuf>),
+}
+
+impl Item {
+    pub fn title(&self) -> String {
+        match self {
+            Item::File(path) => workspace::folder_name(path),
+            Item::Workspace(roots) => workspace::name(roots),
+        }
+    }
+
+    // Where the item is, for telling items with the same title apart.
+    pub fn location(&self) -> String {
+        let paths = match self {
+            Item::File(path) => std::slice::from_ref(path),
+            Item::Workspace(roots) => roots.as_slice(),
+        };
+
+        paths
+            .iter()
+            .filter_map(|path| path.parent())
+            .map(|parent| parent.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn exists(&self) -> bool {
+        match self {
+            Item::File(path) => path.is_file(),
+            Item::Workspace(roots) => roots.iter().all(|root| root.is_dir()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub item: Item,
+    pub pinned: bool,
+}
+
+// Recently opened files and workspaces, pinned ones first and the others most recent
+// first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recent {
+    entries: Vec<Entry>,
+    // Counts the changes, so a save started earlier never writes over a later one.
+    version: u64,
+}
+
+impl Recent {
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &Entry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.item, Item::File(_)))
+    }
+
+    pub fn workspaces(&self) -> impl Iterator<Item = &Entry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.item, Item::Workspace(_)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Moves `item` to the top of the unpinned entries, pinned ones stay where they are.
+    pub fn add(&mut self, item: Item) {
+        if let Some(entry) = self.entries.iter().find(|entry| entry.item == item)
+            && entry.pinned
+        {
+            return;
+        }
+
+        self.version += 1;
+        self.entries.retain(|entry| entry.item != item);
+        let first_unpinned = self
+            .entries
+            .iter()
+            .position(|entry| !entry.pinned)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(
+            first_unpinned,
+            Entry {
+                item,
+                pinned: false,
+            },
+        );
+
+        let mut unpinned = 0;
+        self.entries.retain(|entry| {
+            unpinned += usize::from(!entry.pinned);
+            entry.pinned || unpinned <= MAX_ENTRIES
+        });
+    }
+
+    pub fn remove(&mut self, item: &Item) {
+        self.version += 1;
+        self.entries.retain(|entry| entry.item != *item);
+    }
+
+    // Pinned entries move to the end of the pinned ones, unpinned ones to the top of the
+    // others.
+    pub fn toggle_pinned(&mut self, item: &Item) {
+        let Some(index) = self.entries.iter().position(|entry| entry.item == *item) else {
+            return;
+        };
+        self.version += 1;
+        let mut entry = self.entries.remove(index);
+        entry.pinned = !entry.pinned;
+
+        let first_unpinned = self
+            .entries
+            .iter()
+            .position(|entry| !entry.pinned)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(first_unpinned, entry);
+    }
+
+    // Forgets everything but the pinned entries.
+    pub fn clear(&mut self) {
+        self.version += 1;
+        self.entries.retain(|entry| entry.pinned);
+    }
+
+    // Forgets files and folders that were deleted or moved. Returns whether any were.
+    pub fn remove_missing(&mut self) -> bool {
+        let count = self.entries.len();
+        self.entries.retain(|entry| entry.item.exists());
+
+        let changed = self.entries.len() != count;
+        self.version += u64::from(changed);
+        changed
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    xdg::state_directory().map(|directory| directory.join(FILE_NAME))
+}
+
+// The list as the last editor left it, without what is gone since. A missing or broken
+// file gives an empty list, like for the layout.
+pub fn read() -> Recent {
+    let json: Option<Value> = path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok());
+
+    let entries = json
+        .as_ref()
+        .and_then(|json| json["entries"].as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let paths = || {
+                entry["paths"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(PathBuf::from)
+            };
+
+            let item = match entry["kind"].as_str()? {
+                "file" => Item::File(paths().next()?),
+                "workspace" => Item::Workspace(paths().collect()),
+                _ => return None,
+            };
+
+            Some(Entry {
+                item,
+                pinned: entry["pinned"].as_bool().unwrap_or(false),
+            })
+        })
+        .collect();
+
+    let mut recent = Recent {
+        entries,
+        version: 0,
+    };
+    recent.remove_missing();
+    recent
+}
+
+pub async fn save(recent: Recent) {
+    // The version last written. Saves run one at a time.
+    static SAVED: tokio::sync::Mutex<Option<u64>> = tokio::sync::Mutex::const_new(None);
+
+    let Some(path) = path() else {
+        return;
+    };
+
+    let mut saved = SAVED.lock().await;
+    if saved.is_some_and(|saved| saved >= recent.version) {
+        return;
+    }
+
+    let entries: Vec<Value> = recent
+        .entries
+        .iter()
+        .map(|entry| {
+            let (kind, paths) = match &entry.item {
+                Item::File(path) => ("file", std::slice::from_ref(path)),
+                Item::Workspace(roots) => ("workspace", roots.as_slice()),
+            };
+
+            json!({
+                "kind": kind,
+                "paths": paths.iter().map(|path| path.to_string_lossy()).collect::<Vec<_>>(),
+                "pinned": entry.pinned,
+            })
+        })
+        .collect();
+
+    if let Some(directory) = path.parent() {
+        let _ = tokio::fs::create_dir_all(directory).await;
+    }
+    let _ = tokio::fs::write(path, format!("{:#}\n", json!({ "entries": entries }))).await;
+    *saved = Some(recent.version);
+}
@@ -0,0 +1,290 @@
+use crate::xdg;
+use iced::Size;
+use iced::widget::pane_grid::Axis;
+use iced::widget::text_editor::Position;
+use serde_json::Value;
+use serde_json::json;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub const DEFAULT_NAME: &str = "default";
+
+const DIRECTORY: &str = "sessions";
+// Which session was open last, restored on the next launch.
+const LAST: &str = "last.json";
+
+// Everything needed to pick up where a session left off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub name: String,
+    // The folders of the workspace, the first one is the primary root.
+    pub roots: Vec<PathBuf>,
+    // Expanded directories of the explorer.
+    pub expanded: Vec<PathBuf>,
+    pub window: Option<Size>,
+    pub panes: Panes,
+    // Index of the focused editor, counting editors from left to right and top to bottom.
+    pub focus: usize,
+}
+
+// The editor panes, without the sidebar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Panes {
+    Split {
+        axis: Axis,
+        ratio: f32,
+        a: Box<Panes>,
+        b: Box<Panes>,
+    },
+    Editor(View),
+}
+
+// What an editor pane showed. Untitled buffers are not kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    pub path: Option<PathBuf>,
+    // In buffer lines, unaffected by folds.
+    pub cursor: Position,
+    pub scroll: f32,
+}
+
+impl Session {
+    // Sessions are kept with the primary root of their workspace.
+    pub fn primary(&self) -> Option<&Path> {
+        self.roots.first().map(PathBuf::as_path)
+    }
+
+    // Whether there is nothing to restore, no folder and no file.
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty() && self.panes.views().iter().all(|view| view.path.is_none())
+    }
+}
+
+impl Panes {
+    // The editors from left to right and top to bottom.
+    pub fn views(&self) -> Vec<&View> {
+        match self {
+            Panes::Split { a, b, .. } => [a.views(), b.views()].concat(),
+            Panes::Editor(view) => vec![view],
+        }
+    }
+}
+
+fn directory() -> Option<PathBuf> {
+    xdg::state_directory().map(|directory| directory.join(DIRECTORY))
+}
+
+// Sessions of a workspace live together in a directory named after its primary root.
+fn workspace_directory(root: Option<&Path>) -> Option<PathBuf> {
+    let key = root.map_or_else(
+        || String::from("none"),
+        |root| escape(&root.to_string_lossy()),
+    );
+    directory().map(|directory| directory.join(key))
+}
+
+fn path(root: Option<&Path>, name: &str) -> Option<PathBuf> {
+    workspace_directory(root).map(|directory| directory.join(format!("{}.json", escape(name))))
+}
+
+// Keeps names readable as file names while avoiding path separators and clashes.
+fn escape(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ') {
+                c.to_string()
+            } else {
+                format!("%{:02X}", u32::from(c))
+            }
+        })
+        .collect()
+}
+
+// The session open when the editor last exited, if it can still be read.
+pub fn read_last() -> Option<Session> {
+    let last: Value = std::fs::read_to_string(directory()?.join(LAST))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())?;
+
+    let root = last["root"].as_str().map(PathBuf::from);
+    let name = last["name"].as_str()?;
+
+    read(root.as_deref(), name)
+}
+
+pub fn read(root: Option<&Path>, name: &str) -> Option<Session> {
+    let text = std::fs::read_to_string(path(root, name)?).ok()?;
+    parse(&serde_json::from_str(&text).ok()?)
+}
+
+// Names of the saved sessions of a workspace, sorted.
+pub async fn list(root: Option<PathBuf>) -> Vec<String> {
+    let Some(directory) = workspace_directory(root.as_deref()) else {
+        return Vec::new();
+    };
+    let Ok(mut entries) = tokio::fs::read_dir(directory).await else {
+        return Vec::new();
+    };
+
+    let mut names = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let json = tokio::fs::read_to_string(entry.path())
+            .await
+            .ok()
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok());
+
+        if let Some(name) = json.as_ref().and_then(|json| json["name"].as_str()) {
+            names.push(name.to_owned());
+        }
+    }
+
+    names.sort();
+    names
+}
+
+// The session of the workspace with the primary `root` that was saved last.
+pub async fn latest(root: PathBuf) -> Option<Session> {
+    let directory = workspace_directory(Some(&root))?;
+    let mut entries = tokio::fs::read_dir(directory).await.ok()?;
+
+    let mut latest = None;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Some(modified) = tokio::fs::metadata(entry.path())
+            .await
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+        else {
+            continue;
+        };
+
+        if latest.as_ref().is_none_or(|(latest, _)| modified > *latest) {
+            latest = Some((modified, entry.path()));
+        }
+    }
+
+    let text = tokio::fs::read_to_string(latest?.1).await.ok()?;
+    parse(&serde_json::from_str(&text).ok()?)
+}
+
+// Saves the session and marks it as the one to restore. Failures are ignored, like for
+// the layout.
+pub async fn save(session: Session) {
+    let (Some(directory), Some(path)) = (directory(), path(session.primary(), &session.name))
+    else {
+        return;
+    };
+
+    if let Some(parent) = path.parent() {
+        let _ = tokio::fs::create_dir_all(parent).await;
+    }
+
+    let last = json!({
+        "root": session.primary().map(Path::to_string_lossy),
+        "name": session.name,
+    });
+
+    let _ = tokio::fs::write(&path, format!("{:#}\n", to_json(&session))).await;
+    let _ = tokio::fs::write(directory.join(LAST), format!("{last:#}\n")).await;
+}
+
+fn to_json(session: &Session) -> Value {
+    json!({
+        "name": session.name,
+        "roots": session
+            .roots
+            .iter()
+            .map(|root| root.to_string_lossy())
+            .collect::<Vec<_>>(),
+        "expanded": session
+            .expanded
+            .iter()
+            .map(|path| path.to_string_lossy())
+            .collect::<Vec<_>>(),
+        "window": session.window.map(|size| json!({
+            "width": size.width,
+            "height": size.height,
+        })),
+        "panes": panes_to_json(&session.panes),
+        "focus": session.focus,
+    })
+}
+
+fn panes_to_json(panes: &Panes) -> Value {
+    match panes {
+        Panes::Split { axis, ratio, a, b } => json!({
+            "split": match axis {
+                Axis::Horizontal => "horizontal",
+                Axis::Vertical => "vertical",
+            },
+            "ratio": ratio,
+            "a": panes_to_json(a),
+            "b": panes_to_json(b),
+        }),
+        Panes::Editor(view) => json!({
+            "path": view.path.as_deref().map(Path::to_string_lossy),
+            "line": view.cursor.line,
+            "column": view.cursor.column,
+            "scroll": view.scroll,
+        }),
+    }
+}
+
+fn parse(json: &Value) -> Option<Session> {
+    let window = &json["window"];
+
+    Some(Session {
+        name: json["name"].as_str()?.to_owned(),
+        // Sessions saved before workspaces had several roots name a single `root`.
+        roots: match json["roots"].as_array() {
+            Some(roots) => roots
+                .iter()
+                .filter_map(Value::as_str)
+                .map(PathBuf::from)
+                .collect(),
+            None => json["root"]
+                .as_str()
+                .map(PathBuf::from)
+                .into_iter()
+                .collect(),
+        },
+        expanded: json["expanded"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(PathBuf::from)
+            .collect(),
+        window: window["width"]
+            .as_f64()
+            .zip(window["height"].as_f64())
+            .map(|(width, height)| Size::new(width as f32, height as f32)),
+        panes: parse_panes(&json["panes"])?,
+        focus: json["focus"].as_u64().unwrap_or(0) as usize,
+    })
+}
+
+fn parse_panes(json: &Value) -> Option<Panes> {
+    let axis = match json["split"].as_str() {
+        Some("horizontal") => Axis::Horizontal,
+        Some("vertical") => Axis::Vertical,
+        _ => {
+            let number = |key: &str| json[key].as_u64().unwrap_or(0) as usize;
+
+            return Some(Panes::Editor(View {
+                path: json["path"].as_str().map(PathBuf::from),
+                cursor: Position {
+                    line: number("line"),
+                    column: number("column"),
+                },
+                scroll: json["scroll"].as_f64().unwrap_or(0.0) as f32,
+            }));
+        }
+    };
+
+    Some(Panes::Split {
+        axis,
+        ratio: json["ratio"].as_f64()?.clamp(0.05, 0.95) as f32,
+        a: Box::new(parse_panes(&json["a"])?),
+        b: Box::new(parse_panes(&json["b"])?),
+    })
+}
@@ -0,0 +1,701 @@
+use crate::format::Formatter;
+use crate::indent;
+use crate::indent::Indentation;
+use crate::language;
+use crate::language::Language;
+use crate::theme;
+use crate::view_options::SoftWrap;
+use crate::view_options::ViewOptions;
+use crate::xdg;
+use iced::Color;
+use iced::highlighter;
+use std::fmt;
+use std::io::ErrorKind;
+use std::ops::Range;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use tokio::fs;
+use toml_edit::Document;
+use toml_edit::Item;
+use toml_edit::Key;
+use toml_edit::Table;
+
+const FILE_NAME: &str = "settings.toml";
+const PROJECT_DIRECTORY: &str = ".xeditor";
+
+const DEFAULT_FONT_SIZE: f32 = 16.0;
+// iced's default line height.
+const DEFAULT_LINE_HEIGHT: f32 = 1.3;
+const DEFAULT_EXPLORER_WIDTH: f32 = 0.22;
+
+const FONT_SIZES: RangeInclusive<f64> = 6.0..=72.0;
+const LINE_HEIGHTS: RangeInclusive<f64> = 1.0..=3.0;
+const EXPLORER_WIDTHS: RangeInclusive<f64> = 0.05..=0.9;
+const TAB_SIZES: RangeInclusive<i64> = 1..=16;
+const COLUMNS: RangeInclusive<i64> = 1..=1000;
+// Milliseconds.
+const AUTO_SAVE_DELAYS: RangeInclusive<i64> = 100..=600_000;
+const DEFAULT_AUTO_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    User,
+    Project,
+}
+
+// Where the settings file of `scope` lives. Projects keep theirs in the opened folder.
+pub fn path(scope: Scope, root: Option<&Path>) -> Option<PathBuf> {
+    match scope {
+        Scope::User => xdg::config_directory().map(|directory| directory.join(FILE_NAME)),
+        Scope::Project => root.map(|root| root.join(PROJECT_DIRECTORY).join(FILE_NAME)),
+    }
+}
+
+// When files with unsaved changes are saved without asking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoSave {
+    Off,
+    // Once no edit was made for `auto_save_delay`.
+    AfterDelay,
+    // When the window loses the focus.
+    OnFocusLoss,
+}
+
+impl AutoSave {
+    const ALL: &[AutoSave] = &[AutoSave::Off, AutoSave::AfterDelay, AutoSave::OnFocusLoss];
+}
+
+impl fmt::Display for AutoSave {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AutoSave::Off => "off",
+            AutoSave::AfterDelay => "after_delay",
+            AutoSave::OnFocusLoss => "on_focus_loss",
+        })
+    }
+}
+
+// Options that can also be set for a single language, in a `[languages.<name>]` table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EditorLayer {
+    tab_size: Option<usize>,
+    insert_spaces: Option<bool>,
+    detect_indentation: Option<bool>,
+    soft_wrap: Option<SoftWrap>,
+    wrap_column: Option<u16>,
+    rulers: Option<Vec<u16>>,
+    show_rulers: Option<bool>,
+    show_whitespace: Option<bool>,
+    // `Some(None)` when the formatter was turned off with an empty command.
+    formatter: Option<Option<Formatter>>,
+    format_on_save: Option<bool>,
+}
+
+impl EditorLayer {
+    // Keeps what is set here and takes the rest from `fallback`.
+    fn or(self, fallback: &EditorLayer) -> Self {
+        Self {
+            tab_size: self.tab_size.or(fallback.tab_size),
+            insert_spaces: self.insert_spaces.or(fallback.insert_spaces),
+            detect_indentation: self.detect_indentation.or(fallback.detect_indentation),
+            soft_wrap: self.soft_wrap.or(fallback.soft_wrap),
+            wrap_column: self.wrap_column.or(fallback.wrap_column),
+            rulers: self.rulers.or_else(|| fallback.rulers.clone()),
+            show_rulers: self.show_rulers.or(fallback.show_rulers),
+            show_whitespace: self.show_whitespace.or(fallback.show_whitespace),
+            formatter: self.formatter.or_else(|| fallback.formatter.clone()),
+            format_on_save: self.format_on_save.or(fallback.format_on_save),
+        }
+    }
+}
+
+// The contents of one settings file. Unset keys fall through to the next file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Layer {
+    font: Option<String>,
+    ui_font: Option<String>,
+    icon_font: Option<String>,
+    font_size: Option<f32>,
+    line_height: Option<f32>,
+    ligatures: Option<bool>,
+    zoom_scales_ui: Option<bool>,
+    theme: Option<String>,
+    light_theme: Option<String>,
+    dark_theme: Option<String>,
+    follow_system_theme: Option<bool>,
+    syntax_theme: Option<highlighter::Theme>,
+    explorer_width: Option<f32>,
+    border_color: Option<Color>,
+    minimap: Option<bool>,
+    toolbar: Option<bool>,
+    relative_line_numbers: Option<bool>,
+    auto_save: Option<AutoSave>,
+    auto_save_delay: Option<Duration>,
+    editor: EditorLayer,
+    languages: Vec<(&'static str, EditorLayer)>,
+}
+
+impl Layer {
+    fn language(&self, language: &Language) -> Option<&EditorLayer> {
+        self.languages
+            .iter()
+            .find(|(name, _)| *name == language.name)
+            .map(|(_, layer)| layer)
+    }
+}
+
+// The effective settings: project settings over user settings over the defaults.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    // Family names, the system monospace font for the editor when unset.
+    pub font: Option<String>,
+    // The editor font when unset.
+    pub ui_font: Option<String>,
+    // The bundled icon font when unset.
+    pub icon_font: Option<String>,
+    pub font_size: f32,
+    // Relative to the font size.
+    pub line_height: f32,
+    pub ligatures: bool,
+    // Whether zooming scales the whole window instead of just the editor text.
+    pub zoom_scales_ui: bool,
+    pub theme: String,
+    // Used instead of `theme` when following the desktop's light or dark preference.
+    pub light_theme: String,
+    pub dark_theme: String,
+    pub follow_system_theme: bool,
+    // Follows the theme when unset.
+    pub syntax_theme: Option<highlighter::Theme>,
+    pub explorer_width: f32,
+    // Overrides the border color of the theme.
+    pub border_color: Option<Color>,
+    pub minimap: bool,
+    pub toolbar: bool,
+    pub relative_line_numbers: bool,
+    pub auto_save: AutoSave,
+    pub auto_save_delay: Duration,
+    user: Layer,
+    project: Layer,
+}
+
+// Settings for buffers of one language.
+#[derive(Debug, Clone)]
+pub struct EditorSettings {
+    pub indentation: Indentation,
+    pub detect_indentation: bool,
+    pub view_options: ViewOptions,
+    pub formatter: Option<Formatter>,
+    pub format_on_save: bool,
+}
+
+impl Settings {
+    pub fn new(user: Layer, project: Layer) -> Self {
+        Self {
+            font: project.font.clone().or_else(|| user.font.clone()),
+            ui_font: project.ui_font.clone().or_else(|| user.ui_font.clone()),
+            icon_font: project.icon_font.clone().or_else(|| user.icon_font.clone()),
+            font_size: project
+                .font_size
+                .or(user.font_size)
+                .unwrap_or(DEFAULT_FONT_SIZE),
+            line_height: project
+                .line_height
+                .or(user.line_height)
+                .unwrap_or(DEFAULT_LINE_HEIGHT),
+            ligatures: project.ligatures.or(user.ligatures).unwrap_or(true),
+            zoom_scales_ui: project
+                .zoom_scales_ui
+                .or(user.zoom_scales_ui)
+                .unwrap_or(false),
+            theme: project
+                .theme
+                .clone()
+                .or_else(|| user.theme.clone())
+                .unwrap_or_else(|| String::from(theme::DEFAULT_DARK)),
+            light_theme: project
+                .light_theme
+                .clone()
+                .or_else(|| user.light_theme.clone())
+                .unwrap_or_else(|| String::from(theme::DEFAULT_LIGHT)),
+            dark_theme: project
+                .dark_theme
+                .clone()
+                .or_else(|| user.dark_theme.clone())
+                .unwrap_or_else(|| String::from(theme::DEFAULT_DARK)),
+            follow_system_theme: project
+                .follow_system_theme
+                .or(user.follow_system_theme)
+                .unwrap_or(false),
+            syntax_theme: project.syntax_theme.or(user.syntax_theme),
+            explorer_width: project
+                .explorer_width
+                .or(user.explorer_width)
+                .unwrap_or(DEFAULT_EXPLORER_WIDTH),
+            border_color: project.border_color.or(user.border_color),
+            minimap: project.minimap.or(user.minimap).unwrap_or(true),
+            toolbar: project.toolbar.or(user.toolbar).unwrap_or(false),
+            relative_line_numbers: project
+                .relative_line_numbers
+                .or(user.relative_line_numbers)
+                .unwrap_or(false),
+            auto_save: project
+                .auto_save
+                .or(user.auto_save)
+                .unwrap_or(AutoSave::Off),
+            auto_save_delay: project
+                .auto_save_delay
+                .or(user.auto_save_delay)
+                .unwrap_or(DEFAULT_AUTO_SAVE_DELAY),
+            user,
+            project,
+        }
+    }
+
+    // The same settings with the file of `scope` replaced by `layer`.
+    pub fn with(&self, scope: Scope, layer: Layer) -> Self {
+        match scope {
+            Scope::User => Self::new(layer, self.project.clone()),
+            Scope::Project => Self::new(self.user.clone(), layer),
+        }
+    }
+
+    // Language tables win over the top level keys, project files over user files.
+    pub fn editor(&self, language: &Language) -> EditorSettings {
+        let layer = [
+            self.project.language(language),
+            self.user.language(language),
+            Some(&self.project.editor),
+            Some(&self.user.editor),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(EditorLayer::default(), EditorLayer::or);
+
+        let mut view_options = ViewOptions::for_language(language);
+
+        if let Some(wrap_column) = layer.wrap_column {
+            view_options.wrap_column = wrap_column;
+        }
+        if let Some(soft_wrap) = layer.soft_wrap {
+            view_options.soft_wrap = soft_wrap;
+        }
+        if let Some(rulers) = layer.rulers {
+            view_options.rulers = rulers;
+        }
+        if let Some(show_rulers) = layer.show_rulers {
+            view_options.show_rulers = show_rulers;
+        }
+        if let Some(show_whitespace) = layer.show_whitespace {
+            view_options.show_whitespace = show_whitespace;
+        }
+
+        let default = indent::default_for(language);
+        let insert_spaces = layer.insert_spaces.unwrap_or(default != Indentation::Tabs);
+        let indentation = if insert_spaces {
+            Indentation::Spaces(layer.tab_size.unwrap_or(default.width()))
+        } else {
+            Indentation::Tabs
+        };
+
+        EditorSettings {
+            indentation,
+            detect_indentation: layer.detect_indentation.unwrap_or(true),
+            view_options,
+            formatter: layer.formatter.flatten(),
+            format_on_save: layer.format_on_save.unwrap_or(false),
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new(Layer::default(), Layer::default())
+    }
+}
+
+// Something wrong in a settings file. Keys with problems are ignored, the rest of the
+// file still applies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    // 1-based, `None` when the file could not be read at all.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+// Reads both files at startup, when nothing can be loaded asynchronously yet. Problems
+// come back ready to be shown.
+pub fn read_all(root: Option<&Path>) -> (Settings, Vec<String>) {
+    let mut problems = Vec::new();
+
+    let mut layer = |scope| {
+        let Some(path) = self::path(scope, root) else {
+            return Layer::default();
+        };
+
+        match read(&path) {
+            Ok((layer, found)) => {
+                problems.extend(found.iter().map(|problem| describe(&path, problem)));
+                layer
+            }
+            Err(problem) => {
+                problems.push(describe(&path, &problem));
+                Layer::default()
+            }
+        }
+    };
+
+    let user = layer(Scope::User);
+    let project = layer(Scope::Project);
+
+    (Settings::new(user, project), problems)
+}
+
+pub fn describe(path: &Path, problem: &Problem) -> String {
+    format!("{}: {problem}", path.display())
+}
+
+// Compared between polls to notice saved files.
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// A missing file is the same as an empty one. An unreadable or malformed file is an
+// error as a whole, callers keep what they had.
+pub fn read(path: &Path) -> Result<(Layer, Vec<Problem>), Problem> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => parse(&text),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok((Layer::default(), Vec::new())),
+        Err(error) => Err(Problem {
+            line: None,
+            message: error.to_string(),
+        }),
+    }
+}
+
+pub async fn load(path: PathBuf) -> Result<(Layer, Vec<Problem>), Problem> {
+    match fs::read_to_string(&path).await {
+        Ok(text) => parse(&text),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok((Layer::default(), Vec::new())),
+        Err(error) => Err(Problem {
+            line: None,
+            message: error.to_string(),
+        }),
+    }
+}
+
+pub fn parse(text: &str) -> Result<(Layer, Vec<Problem>), Problem> {
+    let document = Document::parse(text).map_err(|error| Problem {
+        line: error.span().map(|span| line_at(text, span.start)),
+        message: error.message().to_owned(),
+    })?;
+
+    let mut parser = Parser {
+        text,
+        problems: Vec::new(),
+    };
+    let layer = parser.layer(document.as_table());
+
+    Ok((layer, parser.problems))
+}
+
+fn key_span(key: Option<&Key>) -> Option<Range<usize>> {
+    key.and_then(Key::span)
+}
+
+fn line_at(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    problems: Vec<Problem>,
+}
+
+impl Parser<'_> {
+    fn problem(&mut self, span: Option<Range<usize>>, message: String) {
+        self.problems.push(Problem {
+            line: span.map(|span| line_at(self.text, span.start)),
+            message,
+        });
+    }
+
+    fn layer(&mut self, table: &Table) -> Layer {
+        let mut layer = Layer::default();
+
+        for (key, item) in table.iter() {
+            match key {
+                // Like themes, fonts are checked once the installed ones are known.
+                "font" => layer.font = self.string(key, item),
+                "ui_font" => layer.ui_font = self.string(key, item),
+                "icon_font" => layer.icon_font = self.string(key, item),
+                "font_size" => {
+                    layer.font_size = self.float(key, item, FONT_SIZES).map(|size| size as f32);
+                }
+                "line_height" => {
+                    layer.line_height = self
+                        .float(key, item, LINE_HEIGHTS)
+                        .map(|height| height as f32);
+                }
+                "ligatures" => layer.ligatures = self.bool(key, item),
+                "zoom_scales_ui" => layer.zoom_scales_ui = self.bool(key, item),
+                // Custom themes are only known once the themes directory is read, so
+                // names are checked when the theme is applied.
+                "theme" => layer.theme = self.string(key, item),
+                "light_theme" => layer.light_theme = self.string(key, item),
+                "dark_theme" => layer.dark_theme = self.string(key, item),
+                "follow_system_theme" => layer.follow_system_theme = self.bool(key, item),
+                "syntax_theme" => {
+                    layer.syntax_theme = self.choice(key, item, highlighter::Theme::ALL);
+                }
+                "explorer_width" => {
+                    layer.explorer_width = self
+                        .float(key, item, EXPLORER_WIDTHS)
+                        .map(|width| width as f32);
+                }
+                "border_color" => layer.border_color = self.color(key, item),
+                "minimap" => layer.minimap = self.bool(key, item),
+                "toolbar" => layer.toolbar = self.bool(key, item),
+                "relative_line_numbers" => layer.relative_line_numbers = self.bool(key, item),
+                "auto_save" => layer.auto_save = self.choice(key, item, AutoSave::ALL),
+                "auto_save_delay" => {
+                    layer.auto_save_delay = self
+                        .integer(key, item, AUTO_SAVE_DELAYS)
+                        .map(|delay| Duration::from_millis(delay as u64));
+                }
+                "languages" => layer.languages = self.languages(item),
+                _ => {
+                    if !self.editor_key(&mut layer.editor, key, item) {
+                        self.problem(key_span(table.key(key)), format!("unknown setting `{key}`"));
+                    }
+                }
+            }
+        }
+
+        layer
+    }
+
+    fn languages(&mut self, item: &Item) -> Vec<(&'static str, EditorLayer)> {
+        let Some(table) = item.as_table_like() else {
+            self.problem(item.span(), String::from("`languages` must be a table"));
+            return Vec::new();
+        };
+
+        let mut languages = Vec::new();
+
+        for (name, item) in table.iter() {
+            let Some(language) = language::by_name(name) else {
+                self.problem(
+                    key_span(table.key(name)),
+                    format!("unknown language `{name}`"),
+                );
+                continue;
+            };
+
+            let Some(table) = item.as_table_like() else {
+                self.problem(item.span(), format!("`languages.{name}` must be a table"));
+                continue;
+            };
+
+            let mut layer = EditorLayer::default();
+
+            for (key, item) in table.iter() {
+                if !self.editor_key(&mut layer, key, item) {
+                    self.problem(
+                        key_span(table.key(key)),
+                        format!("unknown language setting `{key}`"),
+                    );
+                }
+            }
+
+            languages.push((language.name, layer));
+        }
+
+        languages
+    }
+
+    // Returns false when `key` is not an editor option.
+    fn editor_key(&mut self, layer: &mut EditorLayer, key: &str, item: &Item) -> bool {
+        match key {
+            "tab_size" => {
+                layer.tab_size = self.integer(key, item, TAB_SIZES).map(|size| size as usize);
+            }
+            "insert_spaces" => layer.insert_spaces = self.bool(key, item),
+            "detect_indentation" => layer.detect_indentation = self.bool(key, item),
+            "soft_wrap" => layer.soft_wrap = self.soft_wrap(key, item),
+            "wrap_column" => {
+                layer.wrap_column = self.integer(key, item, COLUMNS).map(|column| column as u16);
+            }
+            "rulers" => layer.rulers = self.rulers(key, item),
+            "show_rulers" => layer.show_rulers = self.bool(key, item),
+            "show_whitespace" => layer.show_whitespace = self.bool(key, item),
+            "formatter" => {
+                layer.formatter = self
+                    .string(key, item)
+                    .map(|command| Formatter::parse(&command));
+            }
+            "format_on_save" => layer.format_on_save = self.bool(key, item),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn string(&mut self, key: &str, item: &Item) -> Option<String> {
+        let value = item.as_str().map(str::to_owned);
+
+        if value.is_none() {
+            self.problem(item.span(), format!("`{key}` must be a string"));
+        }
+
+        value
+    }
+
+    fn bool(&mut self, key: &str, item: &Item) -> Option<bool> {
+        let value = item.as_bool();
+
+        if value.is_none() {
+            self.problem(item.span(), format!("`{key}` must be true or false"));
+        }
+
+        value
+    }
+
+    fn integer(&mut self, key: &str, item: &Item, range: RangeInclusive<i64>) -> Option<i64> {
+        match item.as_integer() {
+            Some(value) if range.contains(&value) => Some(value),
+            _ => {
+                self.problem(
+                    item.span(),
+                    format!(
+                        "`{key}` must be a whole number from {} to {}",
+                        range.start(),
+                        range.end()
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    fn float(&mut self, key: &str, item: &Item, range: RangeInclusive<f64>) -> Option<f64> {
+        let value = item
+            .as_float()
+            .or_else(|| item.as_integer().map(|value| value as f64));
+
+        match value {
+            Some(value) if range.contains(&value) => Some(value),
+            _ => {
+                self.problem(
+                    item.span(),
+                    format!(
+                        "`{key}` must be a number from {} to {}",
+                        range.start(),
+                        range.end()
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    fn choice<T: Clone + fmt::Display>(
+        &mut self,
+        key: &str,
+        item: &Item,
+        options: &[T],
+    ) -> Option<T> {
+        let name = self.string(key, item)?;
+        let found = options
+            .iter()
+            .find(|option| theme::same_name(&option.to_string(), &name))
+            .cloned();
+
+        if found.is_none() {
+            let names: Vec<String> = options.iter().map(ToString::to_string).collect();
+            self.problem(
+                item.span(),
+                format!(
+                    "unknown {key} `{name}`, expected one of: {}",
+                    names.join(", ")
+                ),
+            );
+        }
+
+        found
+    }
+
+    fn color(&mut self, key: &str, item: &Item) -> Option<Color> {
+        let value = self.string(key, item)?;
+        let color = theme::parse_color(&value);
+
+        if color.is_none() {
+            self.problem(
+                item.span(),
+                format!("`{key}` must be a color like \"#45475a\""),
+            );
+        }
+
+        color
+    }
+
+    // "off", "viewport" or the column to wrap at.
+    fn soft_wrap(&mut self, key: &str, item: &Item) -> Option<SoftWrap> {
+        if item.as_integer().is_some() {
+            return self
+                .integer(key, item, COLUMNS)
+                .map(|column| SoftWrap::Column(column as u16));
+        }
+
+        let soft_wrap = match item.as_str() {
+            Some("off") => Some(SoftWrap::Off),
+            Some("viewport") => Some(SoftWrap::Viewport),
+            _ => None,
+        };
+
+        if soft_wrap.is_none() {
+            self.problem(
+                item.span(),
+                format!("`{key}` must be \"off\", \"viewport\" or a column number"),
+            );
+        }
+
+        soft_wrap
+    }
+
+    fn rulers(&mut self, key: &str, item: &Item) -> Option<Vec<u16>> {
+        let columns: Option<Vec<u16>> = item.as_array().and_then(|array| {
+            array
+                .iter()
+                .map(|value| {
+                    value
+                        .as_integer()
+                        .filter(|column| COLUMNS.contains(column))
+                        .map(|column| column as u16)
+                })
+                .collect()
+        });
+
+        if columns.is_none() {
+            self.problem(
+                item.span(),
+                format!("`{key}` must be a list of column numbers"),
+            );
+        }
+
+        columns
+    }
+}
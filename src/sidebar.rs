@@ -0,0 +1,388 @@
+use crate::Message;
+use crate::fold::FoldRange;
+use crate::git::FileStatus;
+use crate::theme::Tokens;
+use crate::workspace;
+use crate::workspace::Workspace;
+use crate::xdg;
+use iced::Alignment;
+use iced::Border;
+use iced::Element;
+use iced::Length::Fill;
+use iced::widget::button;
+use iced::widget::column;
+use iced::widget::container;
+use iced::widget::row;
+use iced::widget::scrollable;
+use iced::widget::text;
+use iced::widget::text_input;
+use serde_json::Value;
+use serde_json::json;
+use std::fmt;
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "layout.json";
+
+const ACTIVITY_BAR_WIDTH: f32 = 40.0;
+
+// Search results past this many are not listed.
+const MAX_MATCHES: usize = 500;
+
+pub const SEARCH_INPUT: &str = "sidebar-search";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    Explorer,
+    Search,
+    SourceControl,
+    Outline,
+}
+
+pub const PANELS: &[Panel] = &[
+    Panel::Explorer,
+    Panel::Search,
+    Panel::SourceControl,
+    Panel::Outline,
+];
+
+impl Panel {
+    fn key(self) -> &'static str {
+        match self {
+            Panel::Explorer => "explorer",
+            Panel::Search => "search",
+            Panel::SourceControl => "source_control",
+            Panel::Outline => "outline",
+        }
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            Panel::Explorer => "☰",
+            Panel::Search => "⌕",
+            Panel::SourceControl => "⑂",
+            Panel::Outline => "≡",
+        }
+    }
+}
+
+impl fmt::Display for Panel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Panel::Explorer => "Explorer",
+            Panel::Search => "Search",
+            Panel::SourceControl => "Source Control",
+            Panel::Outline => "Outline",
+        })
+    }
+}
+
+// The window edge the sidebar is docked to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+// The parts of the window layout kept between sessions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub visible: bool,
+    pub side: Side,
+    pub panel: Panel,
+    // Fraction of the window width, the `explorer_width` setting when unset.
+    pub width: Option<f32>,
+    pub zen: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            side: Side::Left,
+            panel: Panel::Explorer,
+            width: None,
+            zen: false,
+        }
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    xdg::state_directory().map(|directory| directory.join(FILE_NAME))
+}
+
+// The layout of the last session. A missing or broken file gives the default layout, it
+// is only a convenience.
+pub fn read() -> Layout {
+    let json: Option<Value> = path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok());
+
+    let Some(json) = json else {
+        return Layout::default();
+    };
+
+    let default = Layout::default();
+    let sidebar = &json["sidebar"];
+
+    Layout {
+        visible: sidebar["visible"].as_bool().unwrap_or(default.visible),
+        side: match sidebar["side"].as_str() {
+            Some("right") => Side::Right,
+            _ => Side::Left,
+        },
+        panel: sidebar["panel"]
+            .as_str()
+            .and_then(|key| PANELS.iter().copied().find(|panel| panel.key() == key))
+            .unwrap_or(default.panel),
+        width: sidebar["width"]
+            .as_f64()
+            .map(|width| width as f32)
+            .filter(|width| (0.05..=0.95).contains(width)),
+        zen: json["zen"].as_bool().unwrap_or(default.zen),
+    }
+}
+
+pub async fn save(layout: Layout) {
+    let Some(path) = path() else {
+        return;
+    };
+
+    let json = json!({
+        "sidebar": {
+            "visible": layout.visible,
+            "side": match layout.side {
+                Side::Left => "left",
+                Side::Right => "right",
+            },
+            "panel": layout.panel.key(),
+            "width": layout.width,
+        },
+        "zen": layout.zen,
+    });
+
+    // Losing the layout is not worth bothering anyone about.
+    if let Some(directory) = path.parent() {
+        let _ = tokio::fs::create_dir_all(directory).await;
+    }
+    let _ = tokio::fs::write(path, format!("{json:#}\n")).await;
+}
+
+// One entry of the outline, a line starting a fold range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub line: usize,
+    pub depth: usize,
+    pub label: String,
+}
+
+// The first line of every fold range, nested like the ranges.
+pub fn outline(text: &str, fold_ranges: &[FoldRange]) -> Vec<Symbol> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut open: Vec<&FoldRange> = Vec::new();
+
+    fold_ranges
+        .iter()
+        .filter_map(|range| {
+            open.retain(|outer| outer.contains(range.start));
+            let depth = open.len();
+            open.push(range);
+
+            let label = lines.get(range.start)?.trim();
+            Some(Symbol {
+                line: range.start,
+                depth,
+                label: label.to_owned(),
+            })
+        })
+        .collect()
+}
+
+// Lines containing `query`, ignoring case, with their buffer line.
+pub fn search(text: &str, query: &str) -> Vec<(usize, String)> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let query = query.to_lowercase();
+
+    text.split('\n')
+        .enumerate()
+        .filter(|(_, line)| line.to_lowercase().contains(&query))
+        .take(MAX_MATCHES)
+        .map(|(line, text)| (line, text.trim().to_owned()))
+        .collect()
+}
+
+pub fn activity_bar<'a>(current: Option<Panel>, tokens: Tokens) -> Element<'a, Message> {
+    let buttons = PANELS.iter().map(|&panel| {
+        let active = current == Some(panel);
+
+        button(
+            text(panel.icon())
+                .size(18)
+                .width(Fill)
+                .align_x(Alignment::Center),
+        )
+        .on_press(Message::ActivitySelected(panel))
+        .padding([6, 0])
+        .width(Fill)
+        .style(move |theme, status| {
+            let style = button::text(theme, status);
+            button::Style {
+                text_color: if active || status == button::Status::Hovered {
+                    tokens.text
+                } else {
+                    tokens.text_muted
+                },
+                border: Border {
+                    width: if active { 1.0 } else { 0.0 },
+                    color: tokens.border,
+                    radius: 5.0.into(),
+                },
+                ..style
+            }
+        })
+        .into()
+    });
+
+    column(buttons)
+        .spacing(4)
+        .width(ACTIVITY_BAR_WIDTH)
+        .height(Fill)
+        .into()
+}
+
+fn header<'a>(panel: Panel) -> Element<'a, Message> {
+    text(panel.to_string().to_uppercase()).size(12).into()
+}
+
+// A row jumping to `line` of the focused buffer.
+fn line_entry<'a>(line: usize, label: String, indent: f32, tokens: Tokens) -> Element<'a, Message> {
+    button(
+        row![
+            text(label).size(13).width(Fill),
+            text(line + 1).size(12).color(tokens.text_muted),
+        ]
+        .spacing(6),
+    )
+    .on_press(Message::GoToLine(line))
+    .padding(iced::Padding {
+        left: 6.0 + indent,
+        ..iced::Padding::from([2, 6])
+    })
+    .width(Fill)
+    .style(button::text)
+    .into()
+}
+
+pub fn search_view<'a>(
+    query: &'a str,
+    matches: Vec<(usize, String)>,
+    tokens: Tokens,
+) -> Element<'a, Message> {
+    let summary = match matches.len() {
+        _ if query.is_empty() => String::new(),
+        0 => String::from("No results in this file"),
+        MAX_MATCHES => format!("First {MAX_MATCHES} results in this file"),
+        1 => String::from("1 result in this file"),
+        count => format!("{count} results in this file"),
+    };
+
+    let entries = matches
+        .into_iter()
+        .map(|(line, label)| line_entry(line, label, 0.0, tokens));
+
+    column![
+        header(Panel::Search),
+        text_input("Search", query)
+            .id(SEARCH_INPUT)
+            .on_input(Message::SearchQueryChanged)
+            .size(13)
+            .padding(4),
+        text(summary).size(12).color(tokens.text_muted),
+        scrollable(column(entries)).height(Fill),
+    ]
+    .spacing(6)
+    .into()
+}
+
+pub fn source_control_view<'a>(
+    workspace: &'a Workspace,
+    changes: &'a [FileStatus],
+    tokens: Tokens,
+) -> Element<'a, Message> {
+    let entries = changes.iter().map(|change| {
+        let relative = workspace
+            .root_of(&change.path)
+            .and_then(|root| change.path.strip_prefix(root).ok())
+            .unwrap_or(&change.path)
+            .display();
+        // Several roots are told apart by their folder name.
+        let name = match workspace.root_of(&change.path) {
+            Some(root) if workspace.roots.len() > 1 => {
+                format!("{}: {relative}", workspace::folder_name(root))
+            }
+            _ => relative.to_string(),
+        };
+        let color = match change.code.trim() {
+            "??" | "A" => tokens.added,
+            "D" => tokens.deleted,
+            _ => tokens.modified,
+        };
+
+        button(
+            row![
+                text(name).size(13).width(Fill),
+                text(change.code.trim().to_owned()).size(12).color(color),
+            ]
+            .spacing(6),
+        )
+        .on_press(Message::OpenTreeFile(change.path.clone()))
+        .padding([2, 6])
+        .width(Fill)
+        .style(button::text)
+        .into()
+    });
+
+    let summary = if changes.is_empty() { "No changes" } else { "" };
+
+    column![
+        row![
+            container(header(Panel::SourceControl)).width(Fill),
+            button(text("⟳").size(13))
+                .on_press(Message::RefreshGitStatus)
+                .padding([0, 6])
+                .style(button::text),
+        ]
+        .align_y(Alignment::Center),
+        text(summary).size(12).color(tokens.text_muted),
+        scrollable(column(entries)).height(Fill),
+    ]
+    .spacing(6)
+    .into()
+}
+
+pub fn outline_view<'a>(symbols: Vec<Symbol>, tokens: Tokens) -> Element<'a, Message> {
+    let summary = if symbols.is_empty() {
+        "No symbols in this file"
+    } else {
+        ""
+    };
+
+    let entries = symbols.into_iter().map(|symbol| {
+        line_entry(
+            symbol.line,
+            symbol.label,
+            symbol.depth as f32 * 12.0,
+            tokens,
+        )
+    });
+
+    column![
+        header(Panel::Outline),
+        text(summary).size(12).color(tokens.text_muted),
+        scrollable(column(entries)).height(Fill),
+    ]
+    .spacing(6)
+    .into()
+}
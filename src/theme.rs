@@ -0,0 +1,493 @@
+use crate::xdg;
+use iced::Color;
+use iced::highlighter;
+use iced::theme::Palette;
+use iced::theme::Theme;
+use iced::theme::palette;
+use serde_json::Value;
+use std::path::Path;
+use std::path::PathBuf;
+use toml_edit::Document;
+
+pub const DEFAULT_DARK: &str = "Catppuccin Mocha";
+pub const DEFAULT_LIGHT: &str = "Catppuccin Latte";
+
+const THEMES_DIRECTORY: &str = "themes";
+
+// Colors of everything xeditor draws itself, on top of what the iced theme styles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tokens {
+    pub background: Color,
+    pub text: Color,
+    pub text_muted: Color,
+    pub border: Color,
+    pub shadow: Color,
+    pub overlay: Color,
+    pub selected: Color,
+    pub current_line: Color,
+    pub current_line_number: Color,
+    pub ruler: Color,
+    pub bracket: Color,
+    pub bracket_border: Color,
+    pub whitespace: Color,
+    pub trailing_whitespace: Color,
+    pub minimap_text: Color,
+    pub minimap_viewport: Color,
+    pub search_match: Color,
+    pub added: Color,
+    pub modified: Color,
+    pub deleted: Color,
+    pub info: Color,
+    pub warning: Color,
+    pub error: Color,
+}
+
+impl Tokens {
+    // Everything is mixed from the six palette colors, so any iced theme gets matching
+    // chrome. On Catppuccin the mixes land on its surface and overlay shades.
+    pub fn new(palette: Palette) -> Self {
+        let Palette {
+            background,
+            text,
+            primary,
+            success,
+            warning,
+            danger,
+        } = palette;
+
+        let surface = mix(background, text, 0.1);
+        let border = mix(background, text, 0.22);
+        let muted = mix(background, text, 0.44);
+        let subtle = mix(background, text, 0.66);
+
+        Self {
+            background,
+            text,
+            text_muted: muted,
+            border,
+            shadow: mix(background, Color::BLACK, 0.2),
+            overlay: background,
+            selected: surface,
+            current_line: alpha(border, 0.45),
+            current_line_number: text,
+            ruler: alpha(border, 0.8),
+            bracket: alpha(subtle, 0.2),
+            bracket_border: subtle,
+            whitespace: alpha(subtle, 0.5),
+            trailing_whitespace: alpha(danger, 0.25),
+            minimap_text: alpha(text, 0.35),
+            minimap_viewport: alpha(subtle, 0.2),
+            search_match: mix(warning, danger, 0.35),
+            added: success,
+            modified: primary,
+            deleted: danger,
+            info: primary,
+            warning,
+            error: danger,
+        }
+    }
+
+    // Sets the token called `name`, false when there is no such token.
+    fn set(&mut self, name: &str, color: Color) -> bool {
+        let token = match name {
+            "background" => &mut self.background,
+            "text" => &mut self.text,
+            "text_muted" => &mut self.text_muted,
+            "border" => &mut self.border,
+            "shadow" => &mut self.shadow,
+            "overlay" => &mut self.overlay,
+            "selected" => &mut self.selected,
+            "current_line" => &mut self.current_line,
+            "current_line_number" => &mut self.current_line_number,
+            "ruler" => &mut self.ruler,
+            "bracket" => &mut self.bracket,
+            "bracket_border" => &mut self.bracket_border,
+            "whitespace" => &mut self.whitespace,
+            "trailing_whitespace" => &mut self.trailing_whitespace,
+            "minimap_text" => &mut self.minimap_text,
+            "minimap_viewport" => &mut self.minimap_viewport,
+            "search_match" => &mut self.search_match,
+            "added" => &mut self.added,
+            "modified" => &mut self.modified,
+            "deleted" => &mut self.deleted,
+            "info" => &mut self.info,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            _ => return false,
+        };
+
+        *token = color;
+        true
+    }
+}
+
+fn mix(from: Color, to: Color, amount: f32) -> Color {
+    Color {
+        r: from.r + (to.r - from.r) * amount,
+        g: from.g + (to.g - from.g) * amount,
+        b: from.b + (to.b - from.b) * amount,
+        a: from.a + (to.a - from.a) * amount,
+    }
+}
+
+fn alpha(color: Color, a: f32) -> Color {
+    Color { a, ..color }
+}
+
+// "#rgb", "#rgba", "#rrggbb" or "#rrggbbaa".
+pub fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+
+    if !hex.is_ascii() {
+        return None;
+    }
+
+    let digits: Vec<u8> = match hex.len() {
+        3 | 4 => hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|digit| digit as u8 * 17))
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+
+    let alpha = digits.get(3).copied().unwrap_or(255);
+
+    Some(Color::from_rgba8(
+        digits[0],
+        digits[1],
+        digits[2],
+        f32::from(alpha) / 255.0,
+    ))
+}
+
+// A theme loaded from the themes directory.
+#[derive(Debug, Clone)]
+pub struct CustomTheme {
+    pub name: String,
+    palette: Palette,
+    tokens: Vec<(String, Color)>,
+    syntax_theme: Option<highlighter::Theme>,
+}
+
+// Everything needed to draw with one theme.
+#[derive(Debug, Clone)]
+pub struct Appearance {
+    pub theme: Theme,
+    pub tokens: Tokens,
+    pub syntax_theme: highlighter::Theme,
+}
+
+impl Appearance {
+    // The built-in or custom theme called `name`, custom ones shadowing built-in ones.
+    pub fn find(name: &str, custom: &[CustomTheme]) -> Option<Self> {
+        if let Some(custom) = custom.iter().find(|theme| same_name(&theme.name, name)) {
+            let mut tokens = Tokens::new(custom.palette);
+            for (token, color) in &custom.tokens {
+                tokens.set(token, *color);
+            }
+
+            return Some(Self {
+                theme: Theme::custom(custom.name.clone(), custom.palette),
+                tokens,
+                syntax_theme: custom
+                    .syntax_theme
+                    .unwrap_or_else(|| syntax_theme_for(custom.palette)),
+            });
+        }
+
+        let theme = Theme::ALL
+            .iter()
+            .find(|theme| same_name(&theme.to_string(), name))?
+            .clone();
+        let palette = theme.palette();
+
+        Some(Self {
+            theme,
+            tokens: Tokens::new(palette),
+            syntax_theme: syntax_theme_for(palette),
+        })
+    }
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self::find(DEFAULT_DARK, &[]).expect("the default theme is built in")
+    }
+}
+
+// Highlighting that stays readable on the background of `palette`.
+fn syntax_theme_for(palette: Palette) -> highlighter::Theme {
+    if palette::is_dark(palette.background) {
+        highlighter::Theme::Base16Mocha
+    } else {
+        highlighter::Theme::InspiredGitHub
+    }
+}
+
+// Names compare without case, spaces or punctuation, so "catppuccin-mocha" works.
+pub fn same_name(a: &str, b: &str) -> bool {
+    let normalize = |name: &str| -> String {
+        name.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    };
+
+    normalize(a) == normalize(b)
+}
+
+// Every theme that can be picked, built-in ones first.
+pub fn names(custom: &[CustomTheme]) -> Vec<String> {
+    Theme::ALL
+        .iter()
+        .map(ToString::to_string)
+        .chain(custom.iter().map(|theme| theme.name.clone()))
+        .collect()
+}
+
+pub fn directory() -> Option<PathBuf> {
+    xdg::config_directory().map(|directory| directory.join(THEMES_DIRECTORY))
+}
+
+// Reads every theme file in the themes directory. Broken files are skipped and
+// described in the returned problems.
+pub fn read_all() -> (Vec<CustomTheme>, Vec<String>) {
+    let mut themes = Vec::new();
+    let mut problems = Vec::new();
+
+    let Some(entries) = directory().and_then(|directory| std::fs::read_dir(directory).ok()) else {
+        return (themes, problems);
+    };
+
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        let parse = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => parse_toml,
+            Some("json") => parse_json,
+            _ => continue,
+        };
+
+        let result = std::fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|text| parse(&text, &file_stem(&path)));
+
+        match result {
+            Ok(theme) => themes.push(theme),
+            Err(problem) => problems.push(format!("{}: {problem}", path.display())),
+        }
+    }
+
+    (themes, problems)
+}
+
+pub async fn load_all() -> (Vec<CustomTheme>, Vec<String>) {
+    tokio::task::spawn_blocking(read_all)
+        .await
+        .unwrap_or_default()
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// name = "...", syntax_theme = "...", a [palette] with the six iced palette colors and
+// [tokens] overriding single tokens. Palette colors left out come from the light or
+// dark default, whichever matches the background.
+fn parse_toml(text: &str, fallback_name: &str) -> Result<CustomTheme, String> {
+    let document = Document::parse(text).map_err(|error| {
+        let line = error
+            .span()
+            .map(|span| text[..span.start].matches('\n').count() + 1);
+
+        match line {
+            Some(line) => format!("line {line}: {}", error.message()),
+            None => error.message().to_owned(),
+        }
+    })?;
+    let root = document.as_table();
+
+    let color = |key: &str, value: &toml_edit::Item| {
+        value
+            .as_str()
+            .and_then(parse_color)
+            .ok_or_else(|| format!("`{key}` must be a color like \"#1e1e2e\""))
+    };
+
+    let mut colors = Vec::new();
+    if let Some(table) = root.get("palette").and_then(|item| item.as_table_like()) {
+        for (key, value) in table.iter() {
+            colors.push((key.to_owned(), color(key, value)?));
+        }
+    }
+
+    let mut tokens = Vec::new();
+    if let Some(table) = root.get("tokens").and_then(|item| item.as_table_like()) {
+        for (key, value) in table.iter() {
+            tokens.push((key.to_owned(), color(key, value)?));
+        }
+    }
+
+    let syntax_theme = root
+        .get("syntax_theme")
+        .and_then(|item| item.as_str())
+        .map(|name| {
+            highlighter::Theme::ALL
+                .iter()
+                .copied()
+                .find(|theme| same_name(&theme.to_string(), name))
+                .ok_or_else(|| format!("unknown syntax_theme `{name}`"))
+        })
+        .transpose()?;
+
+    let name = root
+        .get("name")
+        .and_then(|item| item.as_str())
+        .unwrap_or(fallback_name);
+
+    custom_theme(name, &colors, tokens, syntax_theme)
+}
+
+// VS Code color themes ("colors") and TextMate themes converted to JSON (the global
+// "settings" entry). Token colors are left to the syntax theme.
+fn parse_json(text: &str, fallback_name: &str) -> Result<CustomTheme, String> {
+    let json: Value =
+        serde_json::from_str(text).map_err(|error| format!("line {}: {error}", error.line()))?;
+
+    let mut palette = Vec::new();
+    let mut tokens = Vec::new();
+
+    let take = |key: &str, value: Option<&Value>| {
+        let color = value.and_then(Value::as_str).and_then(parse_color)?;
+        Some((key.to_owned(), color))
+    };
+
+    if let Some(colors) = json.get("colors") {
+        const PALETTE: &[(&str, &str)] = &[
+            ("editor.background", "background"),
+            ("editor.foreground", "text"),
+            ("focusBorder", "primary"),
+            ("button.background", "primary"),
+            ("gitDecoration.addedResourceForeground", "success"),
+            ("terminal.ansiGreen", "success"),
+            ("editorWarning.foreground", "warning"),
+            ("editorError.foreground", "danger"),
+        ];
+        const TOKENS: &[(&str, &str)] = &[
+            ("editorLineNumber.foreground", "text_muted"),
+            ("editorLineNumber.activeForeground", "current_line_number"),
+            ("editor.lineHighlightBackground", "current_line"),
+            ("editorRuler.foreground", "ruler"),
+            ("editorBracketMatch.background", "bracket"),
+            ("editorBracketMatch.border", "bracket_border"),
+            ("editorWhitespace.foreground", "whitespace"),
+            ("editorGroup.border", "border"),
+            ("widget.shadow", "shadow"),
+            ("quickInput.background", "overlay"),
+            ("list.activeSelectionBackground", "selected"),
+            ("minimapSlider.background", "minimap_viewport"),
+            ("editor.findMatchHighlightBackground", "search_match"),
+            ("editorGutter.addedBackground", "added"),
+            ("editorGutter.modifiedBackground", "modified"),
+            ("editorGutter.deletedBackground", "deleted"),
+            ("editorInfo.foreground", "info"),
+            ("editorWarning.foreground", "warning"),
+            ("editorError.foreground", "error"),
+        ];
+
+        // The first key found wins, so list the preferred source first.
+        for &(key, target) in PALETTE {
+            if !palette.iter().any(|(name, _)| name == target) {
+                palette.extend(take(target, colors.get(key)));
+            }
+        }
+        for &(key, target) in TOKENS {
+            tokens.extend(take(target, colors.get(key)));
+        }
+    }
+
+    let global = json
+        .get("settings")
+        .and_then(Value::as_array)
+        .and_then(|settings| settings.iter().find(|entry| entry.get("scope").is_none()))
+        .and_then(|entry| entry.get("settings"));
+
+    if let Some(global) = global {
+        for (key, target) in [("background", "background"), ("foreground", "text")] {
+            if !palette.iter().any(|(name, _)| name == target) {
+                palette.extend(take(target, global.get(key)));
+            }
+        }
+        tokens.extend(take("current_line", global.get("lineHighlight")));
+        tokens.extend(take("whitespace", global.get("invisibles")));
+    }
+
+    if palette.is_empty() {
+        return Err(String::from("no \"colors\" or global \"settings\" found"));
+    }
+
+    let name = json
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or(fallback_name);
+
+    custom_theme(name, &palette, tokens, None)
+}
+
+fn custom_theme(
+    name: &str,
+    colors: &[(String, Color)],
+    tokens: Vec<(String, Color)>,
+    syntax_theme: Option<highlighter::Theme>,
+) -> Result<CustomTheme, String> {
+    let get = |key: &str| {
+        colors
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|&(_, color)| color)
+    };
+
+    let background = get("background").ok_or("the palette needs a `background` color")?;
+    let defaults = if palette::is_dark(background) {
+        Palette::DARK
+    } else {
+        Palette::LIGHT
+    };
+
+    for (key, _) in colors {
+        if !matches!(
+            key.as_str(),
+            "background" | "text" | "primary" | "success" | "warning" | "danger"
+        ) {
+            return Err(format!("unknown palette color `{key}`"));
+        }
+    }
+
+    if let Some((key, _)) = tokens
+        .iter()
+        .find(|(key, color)| !Tokens::new(defaults).set(key, *color))
+    {
+        return Err(format!("unknown token `{key}`"));
+    }
+
+    Ok(CustomTheme {
+        name: name.to_owned(),
+        palette: Palette {
+            background,
+            text: get("text").unwrap_or(defaults.text),
+            primary: get("primary").unwrap_or(defaults.primary),
+            success: get("success").unwrap_or(defaults.success),
+            warning: get("warning").unwrap_or(defaults.warning),
+            danger: get("danger").unwrap_or(defaults.danger),
+        },
+        tokens,
+        syntax_theme,
+    })
+}
@@ -0,0 +1,75 @@
+use crate::language::Language;
+use std::fmt;
+
+const DEFAULT_WRAP_COLUMN: u16 = 80;
+const DEFAULT_RULERS: &[u16] = &[80, 100];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoftWrap {
+    Off,
+    Viewport,
+    Column(u16),
+}
+
+impl SoftWrap {
+    pub fn next(self, column: u16) -> Self {
+        match self {
+            SoftWrap::Off => SoftWrap::Viewport,
+            SoftWrap::Viewport => SoftWrap::Column(column),
+            SoftWrap::Column(_) => SoftWrap::Off,
+        }
+    }
+}
+
+impl fmt::Display for SoftWrap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoftWrap::Off => f.write_str("No Wrap"),
+            SoftWrap::Viewport => f.write_str("Wrap"),
+            SoftWrap::Column(column) => write!(f, "Wrap at {column}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewOptions {
+    pub soft_wrap: SoftWrap,
+    // Column used when cycling into `SoftWrap::Column`.
+    pub wrap_column: u16,
+    pub rulers: Vec<u16>,
+    pub show_rulers: bool,
+    pub show_whitespace: bool,
+}
+
+impl ViewOptions {
+    // Prose wraps by default, commit messages get the usual 50/72 guides.
+    pub fn for_language(language: &Language) -> Self {
+        let mut options = ViewOptions {
+            soft_wrap: SoftWrap::Off,
+            wrap_column: DEFAULT_WRAP_COLUMN,
+            rulers: DEFAULT_RULERS.to_vec(),
+            show_rulers: false,
+            show_whitespace: false,
+        };
+
+        match language.name {
+            "Markdown" | "Plain Text" => {
+                options.soft_wrap = SoftWrap::Viewport;
+            }
+            "Git Commit" => {
+                options.wrap_column = 72;
+                options.rulers = vec![50, 72];
+                options.show_rulers = true;
+            }
+            "Rust" => {
+                options.rulers = vec![100];
+            }
+            "Python" => {
+                options.rulers = vec![79, 99];
+            }
+            _ => {}
+        }
+
+        options
+    }
+}
@@ -0,0 +1,101 @@
+use crate::Message;
+use crate::recent::Entry;
+use crate::recent::Recent;
+use crate::theme::Tokens;
+use iced::Alignment;
+use iced::Element;
+use iced::Length::Fill;
+use iced::widget::button;
+use iced::widget::column;
+use iced::widget::container;
+use iced::widget::row;
+use iced::widget::scrollable;
+use iced::widget::text;
+
+const WIDTH: f32 = 480.0;
+
+// Recent entries past this many are not listed in each section.
+const MAX_RECENT: usize = 10;
+
+// Shown instead of an empty editor when the editor starts with nothing to open.
+pub fn view<'a>(recent: &'a Recent, tokens: Tokens) -> Element<'a, Message> {
+    let start = column![
+        heading("Start", tokens),
+        action("New File", Message::NewFile),
+        action("Open File...", Message::OpenFile),
+        action("Open Folder...", Message::OpenDirectory),
+    ]
+    .spacing(4);
+
+    let content = column![
+        text("xeditor").size(28),
+        start,
+        section("Recent folders", recent.workspaces(), tokens),
+        section("Recent files", recent.files(), tokens),
+    ]
+    .spacing(24)
+    .max_width(WIDTH);
+
+    let content = if recent.is_empty() {
+        content
+    } else {
+        content.push(action("Clear Recently Opened", Message::ClearRecent))
+    };
+
+    container(scrollable(content))
+        .center(Fill)
+        .padding(20)
+        .into()
+}
+
+// Entries open on click and are pinned with the button at their end.
+fn section<'a>(
+    label: &'a str,
+    entries: impl Iterator<Item = &'a Entry>,
+    tokens: Tokens,
+) -> Element<'a, Message> {
+    let rows: Vec<Element<'a, Message>> = entries
+        .take(MAX_RECENT)
+        .map(|entry| {
+            let open = button(
+                row![
+                    text(entry.item.title()).size(14),
+                    text(entry.item.location())
+                        .size(12)
+                        .color(tokens.text_muted),
+                ]
+                .spacing(10),
+            )
+            .on_press(Message::RecentSelected(entry.item.clone()))
+            .padding([2, 0])
+            .width(Fill)
+            .style(button::text);
+
+            let pin = button(text(if entry.pinned { "Unpin" } else { "Pin" }).size(12))
+                .on_press(Message::RecentPinToggled(entry.item.clone()))
+                .padding([2, 6])
+                .style(button::text);
+
+            row![open, pin].align_y(Alignment::Center).into()
+        })
+        .collect();
+
+    let list: Element<'a, Message> = if rows.is_empty() {
+        text("Nothing yet").size(13).color(tokens.text_muted).into()
+    } else {
+        column(rows).spacing(2).into()
+    };
+
+    column![heading(label, tokens), list].spacing(4).into()
+}
+
+fn action<'a>(label: &'a str, message: Message) -> button::Button<'a, Message> {
+    button(text(label).size(14))
+        .on_press(message)
+        .padding([2, 0])
+        .style(button::text)
+}
+
+fn heading<'a>(label: &'a str, tokens: Tokens) -> Element<'a, Message> {
+    text(label).size(13).color(tokens.text_muted).into()
+}
@@ -0,0 +1,110 @@
+use crate::Message;
+use crate::layout;
+use crate::layout::LineLayout;
+use crate::theme::Tokens;
+use iced::Point;
+use iced::Rectangle;
+use iced::Renderer;
+use iced::Size;
+use iced::Theme;
+use iced::mouse;
+use iced::widget::canvas;
+use iced::widget::text_editor;
+use std::ops::Range;
+
+const DOT_RADIUS: f32 = 1.2;
+
+// Draws dots for spaces, arrows for tabs and a tint behind trailing whitespace. It sits
+// under the (transparent) editor, so only the visible lines are drawn.
+pub struct Whitespace<'a> {
+    pub content: &'a text_editor::Content,
+    pub layout: &'a LineLayout,
+    pub visible: Range<usize>,
+    pub wrap_columns: Option<usize>,
+    pub tokens: Tokens,
+}
+
+impl canvas::Program<Message> for Whitespace<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+
+        for line in self.visible.clone() {
+            let Some(text) = self.content.line(line).map(|line| line.text) else {
+                continue;
+            };
+
+            let trailing_start = text.trim_end().chars().count();
+            let wraps = self
+                .wrap_columns
+                .map(|columns| layout::wrap_points(&text, columns))
+                .unwrap_or_default();
+
+            let mut row = 0;
+            let mut column = 0;
+            let mut row_start_column = 0;
+
+            for (index, c) in text.chars().enumerate() {
+                if wraps.contains(&index) {
+                    row += 1;
+                    row_start_column = column;
+                }
+
+                let next = layout::advance(column, c);
+                let x =
+                    layout::PADDING + (column - row_start_column) as f32 * self.layout.char_width();
+                let y = self.layout.line_top(line) + row as f32 * self.layout.row_height();
+                let width = (next - column) as f32 * self.layout.char_width();
+                let middle = y + self.layout.row_height() / 2.0;
+
+                if index >= trailing_start {
+                    frame.fill_rectangle(
+                        Point::new(x, y),
+                        Size::new(width, self.layout.row_height()),
+                        self.tokens.trailing_whitespace,
+                    );
+                }
+
+                match c {
+                    ' ' => {
+                        frame.fill(
+                            &canvas::Path::circle(Point::new(x + width / 2.0, middle), DOT_RADIUS),
+                            self.tokens.whitespace,
+                        );
+                    }
+                    '\t' => {
+                        let start = Point::new(x + 2.0, middle);
+                        let end = Point::new(x + width - 2.0, middle);
+                        let arrow = canvas::Path::new(|path| {
+                            path.move_to(start);
+                            path.line_to(end);
+                            path.move_to(Point::new(end.x - 4.0, middle - 3.0));
+                            path.line_to(end);
+                            path.line_to(Point::new(end.x - 4.0, middle + 3.0));
+                        });
+
+                        frame.stroke(
+                            &arrow,
+                            canvas::Stroke::default()
+                                .with_color(self.tokens.whitespace)
+                                .with_width(1.0),
+                        );
+                    }
+                    _ => {}
+                }
+
+                column = next;
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
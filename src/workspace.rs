@@ -0,0 +1,85 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+// The folders the editor works on. The first root is the primary one: project settings
+// are read from its `.xeditor` directory and sessions are kept under its name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Workspace {
+    pub roots: Vec<PathBuf>,
+    // Where the editor was started, file dialogs open there while there is no root.
+    pub working_directory: PathBuf,
+}
+
+impl Workspace {
+    pub fn new(roots: Vec<PathBuf>, working_directory: PathBuf) -> Self {
+        let mut workspace = Self {
+            roots: Vec::new(),
+            working_directory,
+        };
+        for root in roots {
+            workspace.add(root);
+        }
+
+        workspace
+    }
+
+    pub fn primary(&self) -> Option<&Path> {
+        self.roots.first().map(PathBuf::as_path)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    // Returns whether `root` was not a root yet.
+    pub fn add(&mut self, root: PathBuf) -> bool {
+        if self.roots.contains(&root) {
+            return false;
+        }
+
+        self.roots.push(root);
+        true
+    }
+
+    // Returns whether `root` was a root.
+    pub fn remove(&mut self, root: &Path) -> bool {
+        let count = self.roots.len();
+        self.roots.retain(|existing| existing != root);
+
+        self.roots.len() != count
+    }
+
+    // The root containing `path`, the innermost one when roots are nested.
+    pub fn root_of(&self, path: &Path) -> Option<&Path> {
+        self.roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .map(PathBuf::as_path)
+    }
+
+    // Where file dialogs start.
+    pub fn directory(&self) -> &Path {
+        self.primary().unwrap_or(&self.working_directory)
+    }
+}
+
+// The folder names, like `crate` or `crate, docs`.
+pub fn name(roots: &[PathBuf]) -> String {
+    if roots.is_empty() {
+        return String::from("No folder");
+    }
+
+    roots
+        .iter()
+        .map(|root| folder_name(root))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn folder_name(root: &Path) -> String {
+    root.file_name()
+        .unwrap_or(root.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
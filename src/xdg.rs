@@ -0,0 +1,32 @@
+use std::env;
+use std::path::PathBuf;
+
+const APP_DIRECTORY: &str = "xeditor";
+
+// A base directory from the XDG spec. Relative values are invalid per the spec and
+// fall back to the default under $HOME like unset ones.
+fn base_directory(variable: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+}
+
+// $XDG_CONFIG_HOME/xeditor
+pub fn config_directory() -> Option<PathBuf> {
+    base_directory("XDG_CONFIG_HOME", ".config").map(|path| path.join(APP_DIRECTORY))
+}
+
+// $XDG_STATE_HOME/xeditor
+pub fn state_directory() -> Option<PathBuf> {
+    base_directory("XDG_STATE_HOME", ".local/state").map(|path| path.join(APP_DIRECTORY))
+}
+
+// $XDG_RUNTIME_DIR, which has no default in the spec. The state directory stands in for
+// it, it is private to the user too.
+pub fn runtime_directory() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(state_directory)
+}
@@ -0,0 +1,185 @@
+// Runs `xeditor` as a second instance against a socket these tests listen on, standing in
+// for the running editor.
+use serde_json::Value;
+use serde_json::json;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+
+// A scratch directory with a socket in it, removed when dropped.
+struct Editor {
+    directory: PathBuf,
+    listener: UnixListener,
+}
+
+impl Editor {
+    fn new(name: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("xeditor-ipc-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let listener = UnixListener::bind(directory.join("xeditor.sock")).unwrap();
+        Self {
+            directory,
+            listener,
+        }
+    }
+
+    fn spawn(&self, args: &[&str]) -> Child {
+        Command::new(env!("CARGO_BIN_EXE_xeditor"))
+            .args(args)
+            .current_dir(&self.directory)
+            .env("XEDITOR_SOCKET", self.directory.join("xeditor.sock"))
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap()
+    }
+
+    // The requests of the next client, up to `end`.
+    fn accept(&self) -> (UnixStream, Vec<Value>) {
+        let (stream, _) = self.listener.accept().unwrap();
+        let mut requests = Vec::new();
+
+        for line in BufReader::new(&stream).lines() {
+            let request: Value = serde_json::from_str(&line.unwrap()).unwrap();
+            if request["request"] == "end" {
+                break;
+            }
+            requests.push(request);
+        }
+        (stream, requests)
+    }
+}
+
+impl Drop for Editor {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
+fn reply(mut stream: &UnixStream, reply: &str) {
+    writeln!(stream, "{}", json!({ "reply": reply })).unwrap();
+}
+
+// Whether `child` is still running. Clients waiting for `closed` are covered by the unit
+// tests of the replies, this only catches one that exits before it.
+fn still_running(child: &mut Child) -> bool {
+    child.try_wait().unwrap().is_none()
+}
+
+#[test]
+fn opens_files_and_folders_in_the_running_editor() {
+    let editor = Editor::new("open");
+    std::fs::create_dir(editor.directory.join("src")).unwrap();
+
+    let mut client = editor.spawn(&["src", "main.rs:12:4", "--readonly"]);
+    let (stream, requests) = editor.accept();
+
+    assert_eq!(
+        requests,
+        [
+            json!({
+                "request": "open_folder",
+                "path": editor.directory.join("src"),
+            }),
+            json!({
+                "request": "open",
+                "path": editor.directory.join("main.rs"),
+                "line": 12,
+                "column": 4,
+                "readonly": true,
+                "wait": false,
+            }),
+            json!({ "request": "focus" }),
+        ]
+    );
+
+    reply(&stream, "opened");
+    assert!(client.wait().unwrap().success());
+}
+
+#[test]
+fn focuses_the_running_editor_without_arguments() {
+    let editor = Editor::new("focus");
+
+    let mut client = editor.spawn(&[]);
+    let (stream, requests) = editor.accept();
+
+    assert_eq!(requests, [json!({ "request": "focus" })]);
+
+    reply(&stream, "opened");
+    assert!(client.wait().unwrap().success());
+}
+
+#[test]
+fn hands_over_standard_input() {
+    let editor = Editor::new("stdin");
+
+    let mut client = editor.spawn(&["-"]);
+    let mut stdin = client.stdin.take().unwrap();
+    stdin.write_all(b"piped\ntext\n").unwrap();
+    drop(stdin);
+
+    let (stream, requests) = editor.accept();
+
+    assert_eq!(requests[0]["request"], "open_text");
+    assert_eq!(requests[0]["text"], "piped\ntext\n");
+
+    reply(&stream, "opened");
+    assert!(client.wait().unwrap().success());
+}
+
+#[test]
+fn waits_until_the_files_are_closed() {
+    let editor = Editor::new("wait");
+
+    let mut client = editor.spawn(&["--wait", "COMMIT_EDITMSG"]);
+    let (stream, requests) = editor.accept();
+
+    assert_eq!(requests[0]["request"], "open");
+    assert_eq!(requests[0]["wait"], true);
+
+    reply(&stream, "opened");
+    assert!(still_running(&mut client));
+
+    reply(&stream, "closed");
+    assert!(client.wait().unwrap().success());
+}
+
+#[test]
+fn waiting_clients_are_told_separately() {
+    let editor = Editor::new("two");
+
+    let mut first = editor.spawn(&["--wait", "a.txt"]);
+    let (first_stream, _) = editor.accept();
+    let mut second = editor.spawn(&["--wait", "b.txt"]);
+    let (second_stream, _) = editor.accept();
+
+    reply(&first_stream, "opened");
+    reply(&second_stream, "opened");
+    reply(&second_stream, "closed");
+
+    assert!(second.wait().unwrap().success());
+    assert!(still_running(&mut first));
+
+    reply(&first_stream, "closed");
+    assert!(first.wait().unwrap().success());
+}
+
+#[test]
+fn fails_when_the_running_editor_goes_away() {
+    let editor = Editor::new("gone");
+
+    let mut client = editor.spawn(&["a.txt"]);
+    let (stream, _) = editor.accept();
+    drop(stream);
+
+    assert_eq!(client.wait().unwrap().code(), Some(1));
+}
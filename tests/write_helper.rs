@@ -0,0 +1,156 @@
+// Runs `xeditor-write` unprivileged on files of a scratch directory, the way the editor
+// runs it through pkexec.
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Output;
+use std::process::Stdio;
+
+// A scratch directory, removed when dropped.
+struct Scratch {
+    directory: PathBuf,
+}
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("xeditor-write-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+
+        Self { directory }
+    }
+
+    // The names in the directory, to catch leftover temporary files.
+    fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(&self.directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
+
+fn write(path: &Path, text: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_xeditor-write"))
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(text.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn reason(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .last()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+#[test]
+fn replaces_the_file_keeping_its_permissions() {
+    let scratch = Scratch::new("replace");
+    let path = scratch.directory.join("hosts");
+    fs::write(&path, "127.0.0.1 localhost\n").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+    let output = write(&path, "127.0.0.1 localhost\n::1 localhost\n");
+
+    assert!(output.status.success(), "{}", reason(&output));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "127.0.0.1 localhost\n::1 localhost\n"
+    );
+    assert_eq!(
+        fs::metadata(&path).unwrap().permissions().mode() & 0o7777,
+        0o640
+    );
+    assert_eq!(scratch.names(), ["hosts"]);
+}
+
+#[test]
+fn drops_the_setuid_setgid_and_sticky_bits() {
+    let scratch = Scratch::new("setuid");
+    let path = scratch.directory.join("program");
+    fs::write(&path, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o7755)).unwrap();
+
+    let output = write(&path, "#!/bin/sh\nid\n");
+
+    assert!(output.status.success(), "{}", reason(&output));
+    assert_eq!(
+        fs::metadata(&path).unwrap().permissions().mode() & 0o7777,
+        0o755
+    );
+}
+
+#[test]
+fn creates_missing_files() {
+    let scratch = Scratch::new("create");
+    let path = scratch.directory.join("new.conf");
+
+    let output = write(&path, "key = value\n");
+
+    assert!(output.status.success(), "{}", reason(&output));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "key = value\n");
+}
+
+#[test]
+fn replaces_the_file_a_link_points_to() {
+    let scratch = Scratch::new("link");
+    let target = scratch.directory.join("target.conf");
+    let link = scratch.directory.join("link.conf");
+    fs::write(&target, "old\n").unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    let output = write(&link, "new\n");
+
+    assert!(output.status.success(), "{}", reason(&output));
+    assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "new\n");
+}
+
+#[test]
+fn reports_failures_and_leaves_nothing_behind() {
+    let scratch = Scratch::new("fail");
+    let directory = scratch.directory.join("directory");
+    fs::create_dir(&directory).unwrap();
+
+    let output = write(&directory, "text\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(reason(&output).ends_with("not a regular file"));
+
+    let output = write(&scratch.directory.join("missing/file"), "text\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!reason(&output).is_empty());
+
+    assert_eq!(scratch.names(), ["directory"]);
+}
+
+#[test]
+fn refuses_relative_paths() {
+    let output = write(Path::new("relative.conf"), "text\n");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!Path::new("relative.conf").exists());
+}